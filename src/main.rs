@@ -43,6 +43,11 @@ fn main() -> glib::ExitCode {
             1
         } else {
             adw::init().expect("Failed to init GTK/libadwaita");
+            // Only voice notes and audio files need GStreamer, so the client can be used without
+            // recording and playing them
+            if let Err(e) = gst::init() {
+                log::warn!("Failed to init GStreamer: {e:?}");
+            }
             ui::init();
 
             // Prepare i18n
@@ -0,0 +1,514 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use glib::clone;
+use glib::Properties;
+use gst::prelude::*;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use crate::model;
+use crate::utils;
+
+/// The maximum number of messages following the playing one that are queued.
+const QUEUE_LIMIT: i32 = 20;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Properties)]
+    #[properties(wrapper_type = super::AudioPlayer)]
+    pub(crate) struct AudioPlayer {
+        /// The playbin element, or `None` if it couldn't be created and playback is disabled
+        pub(super) playbin: OnceCell<Option<gst::Element>>,
+        pub(super) bus_watch: OnceCell<gst::bus::BusWatchGuard>,
+        pub(super) progress_source_id: RefCell<Option<glib::SourceId>>,
+        /// The messages that will be played after the current one, from the same chat
+        pub(super) queue: RefCell<VecDeque<model::Message>>,
+        /// Whether the file of the current message has been downloaded and set to the playbin
+        pub(super) loaded: Cell<bool>,
+        /// Whether the playback rate still needs to be applied to the loaded stream
+        pub(super) rate_pending: Cell<bool>,
+        #[property(get)]
+        pub(super) message: RefCell<Option<model::Message>>,
+        #[property(get)]
+        pub(super) playing: Cell<bool>,
+        /// Whether there are messages queued after the playing one
+        #[property(get)]
+        pub(super) has_next: Cell<bool>,
+        /// The playback position, in milliseconds
+        #[property(get)]
+        pub(super) position: Cell<u64>,
+        /// The duration of the playing message, in milliseconds
+        #[property(get)]
+        pub(super) duration: Cell<u64>,
+        #[property(get, set = Self::set_rate, explicit_notify, minimum = 0.5, maximum = 2.0, default = 1.0)]
+        pub(super) rate: Cell<f64>,
+    }
+
+    impl Default for AudioPlayer {
+        fn default() -> Self {
+            Self {
+                playbin: Default::default(),
+                bus_watch: Default::default(),
+                progress_source_id: Default::default(),
+                queue: Default::default(),
+                loaded: Default::default(),
+                rate_pending: Default::default(),
+                message: Default::default(),
+                playing: Default::default(),
+                has_next: Default::default(),
+                position: Default::default(),
+                duration: Default::default(),
+                rate: Cell::new(1.0),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AudioPlayer {
+        const NAME: &'static str = "AudioPlayer";
+        type Type = super::AudioPlayer;
+    }
+
+    impl ObjectImpl for AudioPlayer {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn dispose(&self) {
+            self.obj().stop();
+        }
+    }
+
+    impl AudioPlayer {
+        fn set_rate(&self, rate: f64) {
+            if self.rate.get() == rate {
+                return;
+            }
+
+            self.rate.set(rate);
+
+            let obj = &*self.obj();
+            if obj.imp().loaded.get() {
+                if let Some(playbin) = obj.playbin() {
+                    obj.seek_to(playbin.query_position::<gst::ClockTime>());
+                }
+            }
+
+            obj.notify_rate();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The session-wide player of audio files and voice notes.
+    pub(crate) struct AudioPlayer(ObjectSubclass<imp::AudioPlayer>);
+}
+
+impl Default for AudioPlayer {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl AudioPlayer {
+    /// Returns the playbin element, creating it on first use.
+    ///
+    /// Returns `None` if it couldn't be created, in which case the playback is disabled.
+    fn playbin(&self) -> Option<&gst::Element> {
+        let imp = self.imp();
+
+        imp.playbin
+            .get_or_init(|| {
+                // This fails again if GStreamer couldn't be initialized on startup
+                if let Err(e) = gst::init() {
+                    log::warn!("Failed to init GStreamer: {e:?}");
+                    return None;
+                }
+
+                let playbin = match gst::ElementFactory::make("playbin").build() {
+                    Ok(playbin) => playbin,
+                    Err(e) => {
+                        log::warn!("Failed to create the playbin element: {e:?}");
+                        return None;
+                    }
+                };
+
+                let bus_watch = playbin.bus().map(|bus| {
+                    bus.add_watch_local(
+                        clone!(@weak self as obj => @default-return glib::ControlFlow::Break,
+                            move |_, message| {
+                                obj.handle_bus_message(message);
+                                glib::ControlFlow::Continue
+                            }
+                        ),
+                    )
+                });
+                match bus_watch {
+                    Some(Ok(bus_watch)) => {
+                        imp.bus_watch.set(bus_watch).unwrap();
+                    }
+                    Some(Err(e)) => {
+                        log::warn!("Failed to watch the playbin bus: {e:?}");
+                        return None;
+                    }
+                    None => {
+                        log::warn!("Failed to watch the playbin bus: it has no bus");
+                        return None;
+                    }
+                }
+
+                Some(playbin)
+            })
+            .as_ref()
+    }
+
+    fn handle_bus_message(&self, message: &gst::Message) {
+        match message.view() {
+            gst::MessageView::Eos(_) => self.play_next(),
+            gst::MessageView::Error(e) => {
+                log::warn!("Error playing an audio message: {:?}", e.error());
+                self.stop();
+            }
+            gst::MessageView::AsyncDone(_) => {
+                // Apply the playback rate as soon as the stream can be seeked
+                if self.imp().rate_pending.replace(false) && self.rate() != 1.0 {
+                    self.seek_to(Some(gst::ClockTime::ZERO));
+                }
+                self.update_progress();
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether messages can be played, which isn't the case if the playbin couldn't be created.
+    pub(crate) fn is_available(&self) -> bool {
+        self.playbin().is_some()
+    }
+
+    /// Plays the given voice note or audio message, or toggles its playback if it's already
+    /// the one being played.
+    pub(crate) fn play_message(&self, message: &model::Message) {
+        if self.message().as_ref() == Some(message) {
+            self.toggle_playback();
+            return;
+        }
+
+        self.imp().queue.borrow_mut().clear();
+        self.update_has_next();
+        self.load_message(message);
+
+        utils::spawn(clone!(@weak self as obj, @weak message => async move {
+            obj.fill_queue(&message).await;
+        }));
+    }
+
+    pub(crate) fn toggle_playback(&self) {
+        if self.playing() {
+            self.pause();
+        } else {
+            self.resume();
+        }
+    }
+
+    pub(crate) fn resume(&self) {
+        if self.message().is_none() {
+            return;
+        }
+
+        // Otherwise the playback will start as soon as the file is loaded
+        if self.imp().loaded.get() {
+            let Some(playbin) = self.playbin() else {
+                return;
+            };
+            if let Err(e) = playbin.set_state(gst::State::Playing) {
+                log::warn!("Failed to resume the audio playback: {e:?}");
+                return;
+            }
+        }
+
+        self.set_playing(true);
+    }
+
+    pub(crate) fn pause(&self) {
+        if self.imp().loaded.get() {
+            if let Some(Err(e)) = self.playbin().map(|p| p.set_state(gst::State::Paused)) {
+                log::warn!("Failed to pause the audio playback: {e:?}");
+            }
+        }
+
+        self.set_playing(false);
+    }
+
+    /// Stops the playback and clears the queue.
+    pub(crate) fn stop(&self) {
+        let imp = self.imp();
+
+        if let Some(playbin) = imp.playbin.get().and_then(Option::as_ref) {
+            if let Err(e) = playbin.set_state(gst::State::Null) {
+                log::warn!("Failed to stop the audio playback: {e:?}");
+            }
+        }
+
+        imp.queue.borrow_mut().clear();
+        imp.loaded.set(false);
+        self.update_has_next();
+
+        self.set_playing(false);
+        self.set_message(None);
+        self.set_position(0);
+        self.set_duration(0);
+    }
+
+    /// Plays the next message of the queue, if any, otherwise stops the playback.
+    pub(crate) fn play_next(&self) {
+        let next = self.imp().queue.borrow_mut().pop_front();
+        match next {
+            Some(message) => {
+                self.update_has_next();
+                self.load_message(&message);
+            }
+            None => self.stop(),
+        }
+    }
+
+    /// Seeks to the given progress of the playing message, in the `0.0..=1.0` range.
+    pub(crate) fn seek(&self, progress: f64) {
+        if !self.imp().loaded.get() {
+            return;
+        }
+        let Some(playbin) = self.playbin() else {
+            return;
+        };
+
+        if let Some(duration) = playbin.query_duration::<gst::ClockTime>() {
+            let position = gst::ClockTime::from_nseconds(
+                (duration.nseconds() as f64 * progress.clamp(0.0, 1.0)) as u64,
+            );
+            self.seek_to(Some(position));
+            self.update_progress();
+        }
+    }
+
+    /// Returns the progress of the playing message, in the `0.0..=1.0` range.
+    pub(crate) fn progress(&self) -> f64 {
+        let duration = self.duration();
+        if duration > 0 {
+            self.position() as f64 / duration as f64
+        } else {
+            0.0
+        }
+    }
+
+    fn seek_to(&self, position: Option<gst::ClockTime>) {
+        let Some(position) = position else {
+            return;
+        };
+        let Some(playbin) = self.playbin() else {
+            return;
+        };
+
+        if let Err(e) = playbin.seek(
+            self.rate(),
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            position,
+            gst::SeekType::None,
+            gst::ClockTime::NONE,
+        ) {
+            log::warn!("Failed to seek the audio playback: {e:?}");
+        }
+    }
+
+    fn load_message(&self, message: &model::Message) {
+        let Some((file, duration)) = audio_file(message) else {
+            log::warn!("Tried to play a message without audio");
+            return;
+        };
+
+        let Some(playbin) = self.playbin() else {
+            return;
+        };
+        if let Err(e) = playbin.set_state(gst::State::Null) {
+            log::warn!("Failed to stop the audio playback: {e:?}");
+        }
+        self.imp().loaded.set(false);
+
+        self.set_message(Some(message));
+        self.set_position(0);
+        self.set_duration(duration.max(0) as u64 * 1000);
+        // Show the message as playing while it's being downloaded
+        self.set_playing(true);
+
+        let session = message.chat_().session_();
+        utils::spawn(clone!(@weak self as obj, @weak message => async move {
+            let file = match session.download_file(file.id).await {
+                Ok(file) => file,
+                Err(e) => {
+                    log::warn!("Failed to download an audio message: {e:?}");
+                    obj.stop();
+                    return;
+                }
+            };
+
+            // Another message could have been chosen in the meantime
+            if obj.message().as_ref() != Some(&message) {
+                return;
+            }
+
+            let imp = obj.imp();
+            let Some(playbin) = obj.playbin() else {
+                return;
+            };
+            playbin.set_property("uri", gio::File::for_path(&file.local.path).uri());
+            imp.loaded.set(true);
+            imp.rate_pending.set(true);
+
+            let state = if obj.playing() {
+                gst::State::Playing
+            } else {
+                gst::State::Paused
+            };
+            if let Err(e) = playbin.set_state(state) {
+                log::warn!("Failed to load an audio message: {e:?}");
+            }
+
+            if let tdlib::enums::MessageContent::MessageVoiceNote(data) = message.content().0 {
+                if !data.is_listened && !message.is_outgoing() {
+                    if let Err(e) = message.open_content().await {
+                        log::warn!("Failed to mark a voice note as listened: {e:?}");
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Queues the messages of the same kind that directly follow the given one in its chat.
+    async fn fill_queue(&self, message: &model::Message) {
+        let kind = std::mem::discriminant(&message.content().0);
+
+        let result = message
+            .chat_()
+            .get_chat_history(message.id(), -QUEUE_LIMIT, QUEUE_LIMIT + 1)
+            .await;
+
+        match result {
+            Ok(mut messages) => {
+                // The playing message could have been changed in the meantime
+                if self.message().as_ref() != Some(message) {
+                    return;
+                }
+
+                messages.retain(|m| m.id() > message.id());
+                messages.sort_by_key(model::Message::id);
+
+                self.imp().queue.borrow_mut().extend(
+                    messages
+                        .into_iter()
+                        .take_while(|m| std::mem::discriminant(&m.content().0) == kind),
+                );
+                self.update_has_next();
+            }
+            Err(e) => log::warn!("Failed to queue the following audio messages: {e:?}"),
+        }
+    }
+
+    fn update_progress(&self) {
+        if !self.imp().loaded.get() {
+            return;
+        }
+        let Some(playbin) = self.playbin() else {
+            return;
+        };
+
+        if let Some(position) = playbin.query_position::<gst::ClockTime>() {
+            self.set_position(position.mseconds());
+        }
+        if let Some(duration) = playbin.query_duration::<gst::ClockTime>() {
+            self.set_duration(duration.mseconds());
+        }
+    }
+
+    fn update_has_next(&self) {
+        let has_next = !self.imp().queue.borrow().is_empty();
+        if self.has_next() == has_next {
+            return;
+        }
+        self.imp().has_next.set(has_next);
+        self.notify_has_next();
+    }
+
+    fn set_message(&self, message: Option<&model::Message>) {
+        if self.message().as_ref() == message {
+            return;
+        }
+        self.imp().message.replace(message.cloned());
+        self.notify_message();
+    }
+
+    fn set_playing(&self, playing: bool) {
+        let imp = self.imp();
+
+        if playing {
+            if imp.progress_source_id.borrow().is_none() {
+                let source_id = glib::timeout_add_local(
+                    Duration::from_millis(250),
+                    clone!(@weak self as obj => @default-return glib::ControlFlow::Break, move || {
+                        obj.update_progress();
+                        glib::ControlFlow::Continue
+                    }),
+                );
+                imp.progress_source_id.replace(Some(source_id));
+            }
+        } else if let Some(source_id) = imp.progress_source_id.take() {
+            source_id.remove();
+        }
+
+        if self.playing() == playing {
+            return;
+        }
+        imp.playing.set(playing);
+        self.notify_playing();
+    }
+
+    fn set_position(&self, position: u64) {
+        if self.position() == position {
+            return;
+        }
+        self.imp().position.set(position);
+        self.notify_position();
+    }
+
+    fn set_duration(&self, duration: u64) {
+        if self.duration() == duration {
+            return;
+        }
+        self.imp().duration.set(duration);
+        self.notify_duration();
+    }
+}
+
+/// Returns the audio file of the message and its duration in seconds, if it has one.
+fn audio_file(message: &model::Message) -> Option<(tdlib::types::File, i32)> {
+    use tdlib::enums::MessageContent::*;
+
+    match message.content().0 {
+        MessageAudio(data) => Some((data.audio.audio, data.audio.duration)),
+        MessageVoiceNote(data) => Some((data.voice_note.voice, data.voice_note.duration)),
+        _ => None,
+    }
+}
@@ -80,6 +80,8 @@ mod imp {
     #[properties(wrapper_type = super::Chat)]
     pub(crate) struct Chat {
         pub(super) messages: RefCell<HashMap<i64, model::Message>>,
+        /// The messages scheduled to be sent later, which aren't part of the history
+        pub(super) scheduled_messages: RefCell<HashMap<i64, model::Message>>,
         #[property(get, set, construct_only)]
         pub(super) session: glib::WeakRef<model::ClientStateSession>,
         #[property(get, set, construct_only)]
@@ -129,6 +131,14 @@ mod imp {
                     Signal::builder("deleted-message")
                         .param_types([model::Message::static_type()])
                         .build(),
+                    Signal::builder("pinned-messages-changed").build(),
+                    Signal::builder("scheduled-messages-changed").build(),
+                    Signal::builder("forum-topic-changed")
+                        .param_types([i64::static_type()])
+                        .build(),
+                    Signal::builder("deleted-forum-topic")
+                        .param_types([i64::static_type()])
+                        .build(),
                 ]
             })
         }
@@ -244,17 +254,35 @@ impl Chat {
                     let mut messages = imp.messages.borrow_mut();
                     let deleted_messages: Vec<model::Message> = data
                         .message_ids
-                        .into_iter()
-                        .filter_map(|id| messages.remove(&id))
+                        .iter()
+                        .filter_map(|id| messages.remove(id))
                         .collect();
 
                     drop(messages);
                     for message in deleted_messages {
                         self.emit_by_name::<()>("deleted-message", &[&message]);
                     }
+
+                    let mut scheduled_messages = imp.scheduled_messages.borrow_mut();
+                    let len = scheduled_messages.len();
+                    scheduled_messages.retain(|id, _| !data.message_ids.contains(id));
+                    let changed = scheduled_messages.len() != len;
+
+                    drop(scheduled_messages);
+                    if changed {
+                        self.emit_by_name::<()>("scheduled-messages-changed", &[]);
+                    }
                 }
             }
             MessageContent(ref data) => {
+                if let Some(message) = self.message(data.message_id) {
+                    message.handle_update(update);
+                } else if let Some(message) = self.scheduled_message(data.message_id) {
+                    message.handle_update(update);
+                    self.emit_by_name::<()>("scheduled-messages-changed", &[]);
+                }
+            }
+            MessageContentOpened(ref data) => {
                 if let Some(message) = self.message(data.message_id) {
                     message.handle_update(update);
                 }
@@ -262,6 +290,9 @@ impl Chat {
             MessageEdited(ref data) => {
                 if let Some(message) = self.message(data.message_id) {
                     message.handle_update(update);
+                } else if let Some(message) = self.scheduled_message(data.message_id) {
+                    message.handle_update(update);
+                    self.emit_by_name::<()>("scheduled-messages-changed", &[]);
                 }
             }
             MessageInteractionInfo(ref data) => {
@@ -269,6 +300,23 @@ impl Chat {
                     message.handle_update(update);
                 }
             }
+            MessageIsPinned(ref data) => {
+                if let Some(message) = self.message(data.message_id) {
+                    message.handle_update(update);
+                }
+                self.emit_by_name::<()>("pinned-messages-changed", &[]);
+            }
+            ForumTopicInfo(data) => {
+                self.emit_by_name::<()>("forum-topic-changed", &[&data.info.message_thread_id])
+            }
+            MessageSendSucceeded(data) if data.message.scheduling_state.is_some() => {
+                let mut scheduled_messages = imp.scheduled_messages.borrow_mut();
+                scheduled_messages.remove(&data.old_message_id);
+                scheduled_messages.insert(data.message.id, model::Message::new(self, data.message));
+
+                drop(scheduled_messages);
+                self.emit_by_name::<()>("scheduled-messages-changed", &[]);
+            }
             MessageSendSucceeded(data) => {
                 let mut messages = imp.messages.borrow_mut();
                 let old_message = messages.remove(&data.old_message_id);
@@ -281,6 +329,12 @@ impl Chat {
                 self.emit_by_name::<()>("deleted-message", &[&old_message]);
                 self.emit_by_name::<()>("new-message", &[&message]);
             }
+            NewMessage(data) if data.message.scheduling_state.is_some() => {
+                imp.scheduled_messages
+                    .borrow_mut()
+                    .insert(data.message.id, model::Message::new(self, data.message));
+                self.emit_by_name::<()>("scheduled-messages-changed", &[]);
+            }
             NewMessage(data) => {
                 let message_id = data.message.id;
                 let message = model::Message::new(self, data.message);
@@ -429,6 +483,204 @@ impl Chat {
         })
     }
 
+    /// Connects to the signal emitted when a message of this chat has been pinned or unpinned.
+    pub(crate) fn connect_pinned_messages_changed<F: Fn(&Self) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("pinned-messages-changed", true, move |values| {
+            let obj = values[0].get().unwrap();
+            f(obj);
+            None
+        })
+    }
+
+    /// Connects to the signal emitted when a message has been scheduled, or when a scheduled
+    /// message has been changed, sent or deleted.
+    pub(crate) fn connect_scheduled_messages_changed<F: Fn(&Self) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("scheduled-messages-changed", true, move |values| {
+            let obj = values[0].get().unwrap();
+            f(obj);
+            None
+        })
+    }
+
+    /// Connects to the signal emitted when a forum topic of this chat has been created, edited,
+    /// closed or reopened, with the message thread id of the topic.
+    pub(crate) fn connect_forum_topic_changed<F: Fn(&Self, MessageId) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("forum-topic-changed", true, move |values| {
+            let obj = values[0].get().unwrap();
+            let message_thread_id = values[1].get().unwrap();
+            f(obj, message_thread_id);
+            None
+        })
+    }
+
+    /// Connects to the signal emitted when a forum topic of this chat has been deleted, with
+    /// the message thread id of the topic.
+    pub(crate) fn connect_deleted_forum_topic<F: Fn(&Self, MessageId) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("deleted-forum-topic", true, move |values| {
+            let obj = values[0].get().unwrap();
+            let message_thread_id = values[1].get().unwrap();
+            f(obj, message_thread_id);
+            None
+        })
+    }
+
+    /// Returns the scheduled message of the specified id, if present in the cache.
+    pub(crate) fn scheduled_message(&self, id: MessageId) -> Option<model::Message> {
+        self.imp().scheduled_messages.borrow().get(&id).cloned()
+    }
+
+    /// Returns the cached scheduled messages, in the order in which they will be sent.
+    pub(crate) fn scheduled_messages(&self) -> Vec<model::Message> {
+        use tdlib::enums::MessageSchedulingState::*;
+
+        let mut messages: Vec<model::Message> = self
+            .imp()
+            .scheduled_messages
+            .borrow()
+            .values()
+            .cloned()
+            .collect();
+
+        // Messages sent when the other user comes online are shown first
+        messages.sort_by_key(|message| match message.scheduling_state().map(|s| s.0) {
+            Some(SendAtDate(data)) => (data.send_date, message.id()),
+            _ => (0, message.id()),
+        });
+
+        messages
+    }
+
+    /// Fetches the scheduled messages of this chat from the server, updating the cache.
+    pub(crate) async fn load_scheduled_messages(&self) -> Result<(), tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        let tdlib::enums::Messages::Messages(data) =
+            tdlib::functions::get_chat_scheduled_messages(self.id(), client_id).await?;
+
+        let scheduled_messages = data
+            .messages
+            .into_iter()
+            .flatten()
+            .map(|m| (m.id, model::Message::new(self, m)))
+            .collect();
+        self.imp().scheduled_messages.replace(scheduled_messages);
+
+        self.emit_by_name::<()>("scheduled-messages-changed", &[]);
+
+        Ok(())
+    }
+
+    /// Whether the user is allowed to pin and unpin messages in this chat.
+    pub(crate) fn can_pin_messages(&self) -> bool {
+        use tdlib::enums::ChatMemberStatus::*;
+
+        let (status, is_channel) = match self.chat_type() {
+            ChatType::Private(_) => return true,
+            ChatType::Secret(_) => return false,
+            ChatType::BasicGroup(basic_group) => (basic_group.status(), false),
+            ChatType::Supergroup(supergroup) => (supergroup.status(), supergroup.is_channel()),
+        };
+
+        match status.0 {
+            Creator(_) => true,
+            // Pinning in channels is tied to the right of editing messages
+            Administrator(data) if is_channel => data.rights.can_edit_messages,
+            Administrator(data) => data.rights.can_pin_messages,
+            Member => !is_channel && self.permissions().0.can_pin_messages,
+            Restricted(data) => {
+                self.permissions().0.can_pin_messages && data.permissions.can_pin_messages
+            }
+            Left | Banned(_) => false,
+        }
+    }
+
+    /// Whether this chat is a supergroup whose messages are organized in topics.
+    pub(crate) fn is_forum(&self) -> bool {
+        matches!(self.chat_type(), ChatType::Supergroup(supergroup) if supergroup.is_forum())
+    }
+
+    /// Whether the user is allowed to create, edit, close and delete the topics of this forum.
+    pub(crate) fn can_manage_topics(&self) -> bool {
+        use tdlib::enums::ChatMemberStatus::*;
+
+        let ChatType::Supergroup(supergroup) = self.chat_type() else {
+            return false;
+        };
+
+        match supergroup.status().0 {
+            Creator(_) => true,
+            Administrator(data) => data.rights.can_manage_topics,
+            Member => self.permissions().0.can_manage_topics,
+            Restricted(data) => {
+                self.permissions().0.can_manage_topics && data.permissions.can_manage_topics
+            }
+            Left | Banned(_) => false,
+        }
+    }
+
+    /// Whether the user is allowed to send messages to this chat.
+    pub(crate) fn can_send_messages(&self) -> bool {
+        use tdlib::enums::ChatMemberStatus::*;
+
+        let (status, is_channel) = match self.chat_type() {
+            ChatType::Private(_) => return !self.is_blocked(),
+            ChatType::Secret(secret_chat) => {
+                return secret_chat.state() == model::SecretChatState::Ready
+            }
+            ChatType::BasicGroup(basic_group) => (basic_group.status(), false),
+            ChatType::Supergroup(supergroup) => (supergroup.status(), supergroup.is_channel()),
+        };
+
+        match status.0 {
+            Creator(_) => true,
+            Administrator(data) if is_channel => data.rights.can_post_messages,
+            Administrator(_) => true,
+            Member => !is_channel && self.permissions().0.can_send_basic_messages,
+            Restricted(data) => {
+                self.permissions().0.can_send_basic_messages
+                    && data.permissions.can_send_basic_messages
+            }
+            Left | Banned(_) => false,
+        }
+    }
+
+    /// Whether the user is allowed to send polls to this chat.
+    pub(crate) fn can_send_polls(&self) -> bool {
+        use tdlib::enums::ChatMemberStatus::*;
+
+        let (status, is_channel) = match self.chat_type() {
+            // Polls can only be sent to bots among private chats
+            ChatType::Private(user) => {
+                return matches!(user.user_type().0, tdlib::enums::UserType::Bot(_))
+            }
+            ChatType::Secret(_) => return false,
+            ChatType::BasicGroup(basic_group) => (basic_group.status(), false),
+            ChatType::Supergroup(supergroup) => (supergroup.status(), supergroup.is_channel()),
+        };
+
+        match status.0 {
+            Creator(_) => true,
+            Administrator(data) if is_channel => data.rights.can_post_messages,
+            Administrator(_) => true,
+            Member => !is_channel && self.permissions().0.can_send_polls,
+            Restricted(data) => {
+                self.permissions().0.can_send_polls && data.permissions.can_send_polls
+            }
+            Left | Banned(_) => false,
+        }
+    }
+
     /// Returns the `Message` of the specified id, if present in the cache.
     pub(crate) fn message(&self, id: MessageId) -> Option<model::Message> {
         self.imp().messages.borrow().get(&id).cloned()
@@ -462,11 +714,12 @@ impl Chat {
     pub(crate) async fn get_chat_history(
         &self,
         from_id: MessageId,
+        offset: i32,
         limit: i32,
     ) -> Result<Vec<model::Message>, tdlib::types::Error> {
         let client_id = self.session_().client_().id();
         let result =
-            tdlib::functions::get_chat_history(self.id(), from_id, 0, limit, false, client_id)
+            tdlib::functions::get_chat_history(self.id(), from_id, offset, limit, false, client_id)
                 .await;
 
         let tdlib::enums::Messages::Messages(data) = result?;
@@ -486,6 +739,204 @@ impl Chat {
         Ok(loaded_messages)
     }
 
+    /// Returns the messages of the thread of the message with the specified id, like the
+    /// comments of a channel post, starting from the message with the id `from_id`.
+    pub(crate) async fn get_message_thread_history(
+        &self,
+        message_thread_id: MessageId,
+        from_id: MessageId,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<model::Message>, tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        let result = tdlib::functions::get_message_thread_history(
+            self.id(),
+            message_thread_id,
+            from_id,
+            offset,
+            limit,
+            client_id,
+        )
+        .await;
+
+        let tdlib::enums::Messages::Messages(data) = result?;
+
+        let mut messages = self.imp().messages.borrow_mut();
+        let loaded_messages: Vec<model::Message> = data
+            .messages
+            .into_iter()
+            .flatten()
+            .map(|m| model::Message::new(self, m))
+            .collect();
+
+        for message in &loaded_messages {
+            messages.insert(message.id(), message.clone());
+        }
+
+        Ok(loaded_messages)
+    }
+
+    /// Returns all the topics of this forum, in the order in which they are shown.
+    pub(crate) async fn get_forum_topics(
+        &self,
+    ) -> Result<Vec<tdlib::types::ForumTopic>, tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+
+        let mut topics = Vec::new();
+        let (mut offset_date, mut offset_message_id, mut offset_message_thread_id) = (0, 0, 0);
+
+        loop {
+            let tdlib::enums::ForumTopics::ForumTopics(data) = tdlib::functions::get_forum_topics(
+                self.id(),
+                String::new(),
+                offset_date,
+                offset_message_id,
+                offset_message_thread_id,
+                100,
+                client_id,
+            )
+            .await?;
+
+            let is_last_page = data.topics.is_empty() || data.next_offset_date == 0;
+            topics.extend(data.topics);
+
+            if is_last_page || topics.len() >= data.total_count as usize {
+                break;
+            }
+
+            offset_date = data.next_offset_date;
+            offset_message_id = data.next_offset_message_id;
+            offset_message_thread_id = data.next_offset_message_thread_id;
+        }
+
+        Ok(topics)
+    }
+
+    /// Returns the topic of this forum with the given message thread id.
+    pub(crate) async fn get_forum_topic(
+        &self,
+        message_thread_id: MessageId,
+    ) -> Result<tdlib::types::ForumTopic, tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        let tdlib::enums::ForumTopic::ForumTopic(topic) =
+            tdlib::functions::get_forum_topic(self.id(), message_thread_id, client_id).await?;
+
+        Ok(topic)
+    }
+
+    /// Creates a new topic in this forum and returns the id of its message thread.
+    pub(crate) async fn create_forum_topic(
+        &self,
+        name: String,
+        icon_color: i32,
+    ) -> Result<MessageId, tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        let icon = tdlib::types::ForumTopicIcon {
+            color: icon_color,
+            custom_emoji_id: 0,
+        };
+
+        let tdlib::enums::ForumTopicInfo::ForumTopicInfo(info) =
+            tdlib::functions::create_forum_topic(self.id(), name, icon, client_id).await?;
+
+        Ok(info.message_thread_id)
+    }
+
+    /// Renames the topic of this forum with the given message thread id.
+    pub(crate) async fn edit_forum_topic(
+        &self,
+        message_thread_id: MessageId,
+        name: String,
+    ) -> Result<(), tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        tdlib::functions::edit_forum_topic(self.id(), message_thread_id, name, false, 0, client_id)
+            .await
+    }
+
+    /// Closes or reopens the topic of this forum with the given message thread id.
+    pub(crate) async fn toggle_forum_topic_is_closed(
+        &self,
+        message_thread_id: MessageId,
+        is_closed: bool,
+    ) -> Result<(), tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        tdlib::functions::toggle_forum_topic_is_closed(
+            self.id(),
+            message_thread_id,
+            is_closed,
+            client_id,
+        )
+        .await
+    }
+
+    /// Deletes the topic of this forum with the given message thread id, with all its messages.
+    pub(crate) async fn delete_forum_topic(
+        &self,
+        message_thread_id: MessageId,
+    ) -> Result<(), tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        tdlib::functions::delete_forum_topic(self.id(), message_thread_id, client_id).await?;
+
+        // Deleted topics aren't announced by an update
+        self.emit_by_name::<()>("deleted-forum-topic", &[&message_thread_id]);
+
+        Ok(())
+    }
+
+    /// Searches the messages of this chat matching the filter, starting from the message with
+    /// the specified id. A negative `offset` can be used to also get newer messages.
+    pub(crate) async fn search_messages(
+        &self,
+        filter: tdlib::enums::SearchMessagesFilter,
+        from_id: MessageId,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<model::Message>, tdlib::types::Error> {
+        let client_id = self.session_().client_().id();
+        let tdlib::enums::FoundChatMessages::FoundChatMessages(data) =
+            tdlib::functions::search_chat_messages(
+                self.id(),
+                String::new(),
+                None,
+                from_id,
+                offset,
+                limit,
+                Some(filter),
+                0,
+                client_id,
+            )
+            .await?;
+
+        let mut messages = self.imp().messages.borrow_mut();
+        let found_messages = data
+            .messages
+            .into_iter()
+            .map(|m| {
+                messages
+                    .entry(m.id)
+                    .or_insert_with(|| model::Message::new(self, m))
+                    .clone()
+            })
+            .collect();
+
+        Ok(found_messages)
+    }
+
+    /// Deletes several messages of this chat at once.
+    pub(crate) async fn delete_messages(
+        &self,
+        message_ids: Vec<MessageId>,
+        revoke: bool,
+    ) -> Result<(), tdlib::types::Error> {
+        tdlib::functions::delete_messages(
+            self.id(),
+            message_ids,
+            revoke,
+            self.session_().client_().id(),
+        )
+        .await
+    }
+
     pub(crate) async fn mark_as_read(&self) -> Result<(), tdlib::types::Error> {
         if let Some(message) = self.last_message() {
             tdlib::functions::view_messages(
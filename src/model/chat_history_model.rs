@@ -27,7 +27,12 @@ mod imp {
     #[derive(Debug, Default)]
     pub(crate) struct ChatHistoryModel {
         pub(super) chat: glib::WeakRef<model::Chat>,
+        /// The id of the message thread, or 0 for the whole chat history
+        pub(super) message_thread_id: Cell<i64>,
+        /// The id of the newest message of the message thread
+        pub(super) thread_last_message_id: Cell<i64>,
         pub(super) is_loading: Cell<bool>,
+        pub(super) has_newer_messages: Cell<bool>,
         pub(super) list: RefCell<VecDeque<model::ChatHistoryItem>>,
     }
 
@@ -84,12 +89,31 @@ glib::wrapper! {
 
 impl ChatHistoryModel {
     pub(crate) fn new(chat: &model::Chat) -> Self {
+        Self::with_message_thread(chat, 0)
+    }
+
+    /// Creates the history of a message thread of the chat, like the comments of a channel
+    /// post or the replies to a message. A `message_thread_id` of 0 stands for the whole chat
+    /// history.
+    pub(crate) fn with_message_thread(chat: &model::Chat, message_thread_id: i64) -> Self {
         let obj: ChatHistoryModel = glib::Object::new();
 
         obj.imp().chat.set(Some(chat));
+        obj.imp().message_thread_id.set(message_thread_id);
 
         chat.connect_new_message(clone!(@weak obj => move |_, message| {
-            obj.push_front(message);
+            if obj.message_thread_id() != 0 {
+                if message.message_thread_id() != obj.message_thread_id() {
+                    return;
+                }
+                obj.update_thread_last_message_id(message.id());
+            }
+
+            // New messages would create a gap if the newest messages aren't loaded yet. They
+            // will be loaded together with the others by `load_newer_messages` instead.
+            if !obj.has_newer_messages() {
+                obj.push_front(message);
+            }
         }));
         chat.connect_deleted_message(clone!(@weak obj => move |_, message| {
             obj.remove(message);
@@ -119,7 +143,7 @@ impl ChatHistoryModel {
 
         imp.is_loading.set(true);
 
-        let result = self.chat().get_chat_history(oldest_message_id, limit).await;
+        let result = self.history(oldest_message_id, 0, limit).await;
 
         imp.is_loading.set(false);
 
@@ -129,10 +153,165 @@ impl ChatHistoryModel {
             return Ok(false);
         }
 
+        // The first messages that are loaded are the newest ones
+        if oldest_message_id == 0 {
+            self.update_thread_last_message_id(messages[0].id());
+        }
+
         self.append(messages);
         Ok(true)
     }
 
+    /// Loads newer messages from this chat history. This is only needed after jumping to a
+    /// message with `jump_to_message`, as the newest messages are loaded otherwise.
+    ///
+    /// Returns `true` when more messages can be loaded.
+    pub(crate) async fn load_newer_messages(&self, limit: i32) -> Result<bool, ChatHistoryError> {
+        let imp = self.imp();
+
+        if imp.is_loading.get() {
+            return Err(ChatHistoryError::AlreadyLoading);
+        }
+
+        if !self.has_newer_messages() {
+            return Ok(false);
+        }
+
+        let newest_message_id = self.newest_message_id().unwrap_or_default();
+
+        imp.is_loading.set(true);
+
+        // A negative offset makes TDLib return the messages that follow the given one. The
+        // message itself is also returned, so we need to request one more.
+        let result = self.history(newest_message_id, -limit, limit + 1).await;
+
+        imp.is_loading.set(false);
+
+        let messages: Vec<_> = result
+            .map_err(ChatHistoryError::Tdlib)?
+            .into_iter()
+            .filter(|message| message.id() > newest_message_id)
+            .collect();
+
+        if messages.is_empty() {
+            self.set_has_newer_messages(false);
+            return Ok(false);
+        }
+
+        self.prepend(messages);
+        self.update_has_newer_messages();
+
+        Ok(self.has_newer_messages())
+    }
+
+    /// Makes sure that the message with the given id is loaded. If it's not loaded yet, the
+    /// chat history is rebuilt around it.
+    ///
+    /// Returns the position of the message in this model, if it still exists.
+    pub(crate) async fn jump_to_message(
+        &self,
+        message_id: i64,
+        limit: i32,
+    ) -> Result<Option<u32>, ChatHistoryError> {
+        if let Some(position) = self.message_position(message_id) {
+            return Ok(Some(position));
+        }
+
+        let imp = self.imp();
+
+        if imp.is_loading.get() {
+            return Err(ChatHistoryError::AlreadyLoading);
+        }
+
+        imp.is_loading.set(true);
+
+        let result = self.history(message_id, -limit / 2, limit).await;
+
+        imp.is_loading.set(false);
+
+        let messages = result.map_err(ChatHistoryError::Tdlib)?;
+
+        self.replace(messages);
+        self.update_has_newer_messages();
+
+        Ok(self.message_position(message_id))
+    }
+
+    /// Returns the messages of the chat history or of the message thread, starting from the
+    /// message with the id `from_id`.
+    async fn history(
+        &self,
+        from_id: i64,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<model::Message>, tdlib::types::Error> {
+        match self.message_thread_id() {
+            0 => self.chat().get_chat_history(from_id, offset, limit).await,
+            message_thread_id => {
+                self.chat()
+                    .get_message_thread_history(message_thread_id, from_id, offset, limit)
+                    .await
+            }
+        }
+    }
+
+    /// Returns the position of the message with the given id, if it's loaded.
+    pub(crate) fn message_position(&self, message_id: i64) -> Option<u32> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .position(|item| {
+                item.message()
+                    .filter(|message| message.id() == message_id)
+                    .is_some()
+            })
+            .map(|position| position as u32)
+    }
+
+    /// Whether there are messages newer than the loaded ones. This is the case after jumping
+    /// to an older message.
+    pub(crate) fn has_newer_messages(&self) -> bool {
+        self.imp().has_newer_messages.get()
+    }
+
+    fn set_has_newer_messages(&self, has_newer_messages: bool) {
+        self.imp().has_newer_messages.set(has_newer_messages);
+    }
+
+    /// Returns the id of the newest message of the chat history or of the message thread.
+    pub(crate) fn last_message_id(&self) -> Option<i64> {
+        match self.message_thread_id() {
+            0 => self.chat().last_message().map(|message| message.id()),
+            _ => Some(self.imp().thread_last_message_id.get()).filter(|id| *id != 0),
+        }
+    }
+
+    fn update_thread_last_message_id(&self, message_id: i64) {
+        let imp = self.imp();
+        if self.message_thread_id() != 0 && message_id > imp.thread_last_message_id.get() {
+            imp.thread_last_message_id.set(message_id);
+        }
+    }
+
+    fn update_has_newer_messages(&self) {
+        let last_message_id = self.last_message_id();
+
+        self.set_has_newer_messages(match (self.newest_message_id(), last_message_id) {
+            (Some(newest_message_id), Some(last_message_id)) => newest_message_id < last_message_id,
+            _ => false,
+        });
+    }
+
+    fn newest_message_id(&self) -> Option<i64> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .find_map(|item| item.message())
+            .map(|message| message.id())
+    }
+
     fn items_changed(&self, position: u32, removed: u32, added: u32) {
         let imp = self.imp();
 
@@ -142,8 +321,8 @@ impl ChatHistoryModel {
             let added = added as usize;
 
             let mut list = imp.list.borrow_mut();
-            let mut previous_timestamp = if position + 1 < list.len() {
-                list.get(position + 1)
+            let mut previous_timestamp = if position + added < list.len() {
+                list.get(position + added)
                     .and_then(|item| item.message_timestamp())
             } else {
                 None
@@ -242,6 +421,21 @@ impl ChatHistoryModel {
         self.items_changed(0, 0, 1);
     }
 
+    fn prepend(&self, messages: Vec<model::Message>) {
+        let added = messages.len();
+
+        {
+            let mut list = self.imp().list.borrow_mut();
+
+            // The messages are ordered from the newest to the oldest one
+            for message in messages.into_iter().rev() {
+                list.push_front(model::ChatHistoryItem::for_message(message));
+            }
+        }
+
+        self.items_changed(0, 0, added as u32);
+    }
+
     fn append(&self, messages: Vec<model::Message>) {
         let imp = self.imp();
         let added = messages.len();
@@ -258,6 +452,20 @@ impl ChatHistoryModel {
         self.items_changed(index as u32, 0, added as u32);
     }
 
+    fn replace(&self, messages: Vec<model::Message>) {
+        let removed = {
+            let mut list = self.imp().list.borrow_mut();
+            let removed = list.len();
+            list.clear();
+            removed
+        };
+
+        self.upcast_ref::<gio::ListModel>()
+            .items_changed(0, removed as u32, 0);
+
+        self.append(messages);
+    }
+
     fn remove(&self, message: model::Message) {
         let imp = self.imp();
 
@@ -269,25 +477,28 @@ impl ChatHistoryModel {
             // The elements in this list are ordered. While the day dividers are ordered
             // only by their date time, the messages are additionally sorted by their id. We
             // can exploit this by applying a binary search.
-            let index = list
-                .binary_search_by(|m| match m.type_() {
-                    model::ChatHistoryItemType::Message(other_message) => {
-                        message.id().cmp(&other_message.id())
-                    }
-                    model::ChatHistoryItemType::DayDivider(date_time) => {
-                        let ordering = glib::DateTime::from_unix_utc(message.date() as i64)
-                            .unwrap()
-                            .cmp(date_time);
-                        if let Ordering::Equal = ordering {
-                            // We found the day divider of the message. Therefore, the message
-                            // must be among the following elements.
-                            Ordering::Greater
-                        } else {
-                            ordering
-                        }
+            let index = list.binary_search_by(|m| match m.type_() {
+                model::ChatHistoryItemType::Message(other_message) => {
+                    message.id().cmp(&other_message.id())
+                }
+                model::ChatHistoryItemType::DayDivider(date_time) => {
+                    let ordering = glib::DateTime::from_unix_utc(message.date() as i64)
+                        .unwrap()
+                        .cmp(date_time);
+                    if let Ordering::Equal = ordering {
+                        // We found the day divider of the message. Therefore, the message
+                        // must be among the following elements.
+                        Ordering::Greater
+                    } else {
+                        ordering
                     }
-                })
-                .unwrap();
+                }
+            });
+
+            // The message may not be loaded, e.g. after jumping to another message
+            let Ok(index) = index else {
+                return;
+            };
 
             list.remove(index);
             index as u32
@@ -299,4 +510,8 @@ impl ChatHistoryModel {
     pub(crate) fn chat(&self) -> model::Chat {
         self.imp().chat.upgrade().unwrap()
     }
+
+    pub(crate) fn message_thread_id(&self) -> i64 {
+        self.imp().message_thread_id.get()
+    }
 }
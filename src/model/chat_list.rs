@@ -141,6 +141,16 @@ impl ChatList {
         self.session().unwrap()
     }
 
+    /// Returns the ids of the chats in this list.
+    pub(crate) fn chat_ids(&self) -> Vec<ChatId> {
+        self.imp()
+            .list
+            .borrow()
+            .values()
+            .filter_map(|item| item.chat().map(|chat| chat.id()))
+            .collect()
+    }
+
     pub(crate) fn fetch(&self) {
         utils::spawn(clone!(@weak self as obj => async move {
             let result = tdlib::functions::load_chats(Some(obj.list_type().0), 20, obj.session_().client_().id())
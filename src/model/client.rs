@@ -132,17 +132,16 @@ impl Client {
             );
         }
 
-        // TODO: Hopefully we'll support animated emoji at some point
         if let Err(e) = tdlib::functions::set_option(
             "disable_animated_emoji".to_string(),
             Some(tdlib::enums::OptionValue::Boolean(
-                tdlib::types::OptionValueBoolean { value: true },
+                tdlib::types::OptionValueBoolean { value: false },
             )),
             self.id(),
         )
         .await
         {
-            log::warn!("Error disabling animated emoji: {:?}", e);
+            log::warn!("Error enabling animated emoji: {:?}", e);
         }
     }
 
@@ -28,6 +28,7 @@ mod imp {
         pub(super) secret_chats: RefCell<HashMap<i32, model::SecretChat>>,
         pub(super) downloading_files:
             RefCell<HashMap<i32, Vec<async_channel::Sender<tdlib::types::File>>>>,
+        pub(super) custom_emoji_stickers: RefCell<HashMap<i64, tdlib::types::Sticker>>,
 
         #[property(get, set, construct_only)]
         pub(super) client: glib::WeakRef<model::Client>,
@@ -39,6 +40,8 @@ mod imp {
         pub(super) archive_chat_list: OnceCell<model::ChatList>,
         #[property(get = Self::chat_folder_list)]
         pub(super) chat_folder_list: OnceCell<model::ChatFolderList>,
+        #[property(get = Self::audio_player)]
+        pub(super) audio_player: OnceCell<model::AudioPlayer>,
         #[property(get)]
         pub(super) private_chats_notification_settings:
             RefCell<model::BoxedScopeNotificationSettings>,
@@ -115,6 +118,11 @@ mod imp {
                 .get_or_init(|| model::ChatFolderList::from(&*self.obj()))
                 .to_owned()
         }
+
+        /// Returns the player of audio messages, which keeps playing while switching chats.
+        pub(crate) fn audio_player(&self) -> model::AudioPlayer {
+            self.audio_player.get_or_init(Default::default).to_owned()
+        }
     }
 }
 
@@ -235,6 +243,41 @@ impl ClientStateSession {
         })
     }
 
+    /// Fetches the stickers of the specified custom emoji, mapped by their custom emoji id.
+    /// Stickers that have been fetched before are taken from a cache.
+    pub(crate) async fn custom_emoji_stickers(
+        &self,
+        custom_emoji_ids: &[i64],
+    ) -> Result<HashMap<i64, tdlib::types::Sticker>, tdlib::types::Error> {
+        let mut missing_ids = custom_emoji_ids
+            .iter()
+            .filter(|id| !self.imp().custom_emoji_stickers.borrow().contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        missing_ids.sort_unstable();
+        missing_ids.dedup();
+
+        // TDLib accepts up to 200 custom emoji ids per request
+        for ids in missing_ids.chunks(200) {
+            let tdlib::enums::Stickers::Stickers(data) =
+                tdlib::functions::get_custom_emoji_stickers(ids.to_vec(), self.client_().id())
+                    .await?;
+
+            let mut cache = self.imp().custom_emoji_stickers.borrow_mut();
+            for sticker in data.stickers {
+                if let tdlib::enums::StickerFullType::CustomEmoji(ref type_) = sticker.full_type {
+                    cache.insert(type_.custom_emoji_id, sticker);
+                }
+            }
+        }
+
+        let cache = self.imp().custom_emoji_stickers.borrow();
+        Ok(custom_emoji_ids
+            .iter()
+            .filter_map(|id| cache.get(id).map(|sticker| (*id, sticker.clone())))
+            .collect())
+    }
+
     fn set_private_chats_notification_settings(
         &self,
         settings: model::BoxedScopeNotificationSettings,
@@ -300,10 +343,11 @@ impl ClientStateSession {
 
         glib::spawn_future_local(async move {
             while let Ok(file) = receiver.recv().await {
-                if !file.local.is_downloading_active {
+                let is_downloading_active = file.local.is_downloading_active;
+                f(file);
+                if !is_downloading_active {
                     break;
                 }
-                f(file);
             }
         });
 
@@ -367,17 +411,12 @@ impl ClientStateSession {
     fn handle_file_update(&self, file: tdlib::types::File) {
         let mut downloading_files = self.imp().downloading_files.borrow_mut();
         if let Entry::Occupied(mut entry) = downloading_files.entry(file.id) {
-            // Keep only the senders with which it was possible to send successfully.
-            // It is indeed possible that the object that created the sender and receiver and
-            // attached it to the default main context has been disposed in the meantime.
-            // This is problematic if it is now tried to upgrade a weak reference of this object in
-            // the receiver closure.
-            // It will either panic directly if `@default-panic` is used or the sender will return
-            // an error in the `SyncSender::send()` function if
-            // `default-return glib::Continue(false)` is used. In the latter case, the Receiver
-            // will be detached from the main context, which will cause the sending to fail.
-            entry.get_mut();
-            // .retain(|sender| sender.send(file.clone()).is_ok());
+            // Keep only the senders with which it was possible to send successfully. The
+            // receiving end is dropped as soon as the last update of a download has been
+            // delivered, so closed channels can be forgotten.
+            entry
+                .get_mut()
+                .retain(|sender| sender.try_send(file.clone()).is_ok());
 
             if !file.local.is_downloading_active || entry.get().is_empty() {
                 entry.remove();
@@ -423,9 +462,13 @@ impl ClientStateSession {
             ChatBlockList(ref data) => self.chat(data.chat_id).handle_update(update),
             ChatIsMarkedAsUnread(ref data) => self.chat(data.chat_id).handle_update(update),
             DeleteMessages(ref data) => self.chat(data.chat_id).handle_update(update),
+            ForumTopicInfo(ref data) => self.chat(data.chat_id).handle_update(update),
             ChatAction(ref data) => self.chat(data.chat_id).handle_update(update),
             MessageContent(ref data) => self.chat(data.chat_id).handle_update(update),
+            MessageContentOpened(ref data) => self.chat(data.chat_id).handle_update(update),
             MessageEdited(ref data) => self.chat(data.chat_id).handle_update(update),
+            MessageInteractionInfo(ref data) => self.chat(data.chat_id).handle_update(update),
+            MessageIsPinned(ref data) => self.chat(data.chat_id).handle_update(update),
             MessageMentionRead(ref data) => self.chat(data.chat_id).handle_update(update),
             MessageSendSucceeded(ref data) => self.chat(data.message.chat_id).handle_update(update),
             NewMessage(ref data) => self.chat(data.message.chat_id).handle_update(update),
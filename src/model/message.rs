@@ -61,14 +61,22 @@ mod imp {
         #[property(get, set, construct_only)]
         pub(super) is_outgoing: OnceCell<bool>,
         #[property(get, set, construct_only)]
+        pub(super) message_thread_id: OnceCell<i64>,
+        #[property(get, set, construct_only)]
+        pub(super) can_get_message_thread: OnceCell<bool>,
+        #[property(get, set, construct_only)]
         pub(super) can_be_edited: OnceCell<bool>,
         #[property(get, set, construct_only)]
+        pub(super) can_be_forwarded: OnceCell<bool>,
+        #[property(get, set, construct_only)]
         pub(super) can_be_deleted_only_for_self: OnceCell<bool>,
         #[property(get, set, construct_only)]
         pub(super) can_be_deleted_for_all_users: OnceCell<bool>,
         #[property(get, set, construct_only)]
         pub(super) sending_state: OnceCell<Option<model::BoxedMessageSendingState>>,
         #[property(get, set, construct_only)]
+        pub(super) scheduling_state: OnceCell<Option<model::BoxedMessageSchedulingState>>,
+        #[property(get, set, construct_only)]
         pub(super) date: OnceCell<i32>,
         #[property(get, set, construct_only)]
         pub(super) interaction_info: OnceCell<model::MessageInteractionInfo>,
@@ -80,6 +88,8 @@ mod imp {
         pub(super) content: RefCell<model::BoxedMessageContent>,
         #[property(get)]
         pub(super) is_edited: Cell<bool>,
+        #[property(get)]
+        pub(super) is_pinned: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -117,7 +127,10 @@ impl Message {
                 model::MessageSender::new(&chat.session_(), &td_message.sender_id),
             )
             .property("is-outgoing", td_message.is_outgoing)
+            .property("message-thread-id", td_message.message_thread_id)
+            .property("can-get-message-thread", td_message.can_get_message_thread)
             .property("can-be-edited", td_message.can_be_edited)
+            .property("can-be-forwarded", td_message.can_be_forwarded)
             .property(
                 "can-be-deleted-only-for-self",
                 td_message.can_be_deleted_only_for_self,
@@ -132,6 +145,12 @@ impl Message {
                     .sending_state
                     .map(model::BoxedMessageSendingState),
             )
+            .property(
+                "scheduling-state",
+                td_message
+                    .scheduling_state
+                    .map(model::BoxedMessageSchedulingState),
+            )
             .property("date", td_message.date)
             .property(
                 "interaction-info",
@@ -154,6 +173,7 @@ impl Message {
         imp.content
             .replace(model::BoxedMessageContent(td_message.content));
         imp.is_edited.set(td_message.edit_date > 0);
+        imp.is_pinned.set(td_message.is_pinned);
 
         obj
     }
@@ -170,23 +190,151 @@ impl Message {
                 let new_content = model::BoxedMessageContent(data.new_content);
                 self.set_content(new_content);
             }
+            MessageContentOpened(_) => {
+                use tdlib::enums::MessageContent::*;
+
+                let mut content = self.content().0;
+                match content {
+                    MessageVoiceNote(ref mut data) => data.is_listened = true,
+                    MessageVideoNote(ref mut data) => data.is_viewed = true,
+                    _ => return,
+                }
+                self.set_content(model::BoxedMessageContent(content));
+            }
             MessageEdited(data) => self.set_is_edited(data.edit_date > 0),
+            MessageIsPinned(data) => self.set_is_pinned(data.is_pinned),
             MessageInteractionInfo(data) => self.interaction_info().update(data.interaction_info),
             _ => {}
         }
     }
 
+    /// Whether the message is scheduled to be sent later.
+    pub(crate) fn is_scheduled(&self) -> bool {
+        self.scheduling_state().is_some()
+    }
+
+    /// Changes when a scheduled message will be sent. It's sent immediately if
+    /// `scheduling_state` is `None`.
+    pub(crate) async fn reschedule(
+        &self,
+        scheduling_state: Option<tdlib::enums::MessageSchedulingState>,
+    ) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::edit_message_scheduling_state(
+            chat.id(),
+            self.id(),
+            scheduling_state,
+            chat.session_().client_().id(),
+        )
+        .await
+    }
+
     pub(crate) async fn delete(&self, revoke: bool) -> Result<(), tdlib::types::Error> {
+        self.chat_().delete_messages(vec![self.id()], revoke).await
+    }
+
+    /// Pins the message in its chat, optionally notifying the members of the chat.
+    pub(crate) async fn pin(&self, notify: bool) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::pin_chat_message(
+            chat.id(),
+            self.id(),
+            !notify,
+            false,
+            chat.session_().client_().id(),
+        )
+        .await
+    }
+
+    pub(crate) async fn unpin(&self) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::unpin_chat_message(chat.id(), self.id(), chat.session_().client_().id())
+            .await
+    }
+
+    /// Votes for the options of the poll of this message. The vote is retracted if
+    /// `option_ids` is empty.
+    pub(crate) async fn set_poll_answer(
+        &self,
+        option_ids: Vec<i32>,
+    ) -> Result<(), tdlib::types::Error> {
         let chat = self.chat_();
-        tdlib::functions::delete_messages(
+        tdlib::functions::set_poll_answer(
             chat.id(),
-            vec![self.id()],
-            revoke,
+            self.id(),
+            option_ids,
             chat.session_().client_().id(),
         )
         .await
     }
 
+    /// Informs TDLib that the content of the message has been opened, e.g. that a voice note
+    /// has been listened.
+    pub(crate) async fn open_content(&self) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::open_message_content(chat.id(), self.id(), chat.session_().client_().id())
+            .await
+    }
+
+    /// Returns the reactions that can be added to this message.
+    pub(crate) async fn available_reactions(
+        &self,
+    ) -> Result<tdlib::types::AvailableReactions, tdlib::types::Error> {
+        let chat = self.chat_();
+        let tdlib::enums::AvailableReactions::AvailableReactions(reactions) =
+            tdlib::functions::get_message_available_reactions(
+                chat.id(),
+                self.id(),
+                8,
+                chat.session_().client_().id(),
+            )
+            .await?;
+
+        Ok(reactions)
+    }
+
+    pub(crate) async fn add_reaction(
+        &self,
+        reaction_type: tdlib::enums::ReactionType,
+    ) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::add_message_reaction(
+            chat.id(),
+            self.id(),
+            reaction_type,
+            false,
+            true,
+            chat.session_().client_().id(),
+        )
+        .await
+    }
+
+    pub(crate) async fn remove_reaction(
+        &self,
+        reaction_type: tdlib::enums::ReactionType,
+    ) -> Result<(), tdlib::types::Error> {
+        let chat = self.chat_();
+        tdlib::functions::remove_message_reaction(
+            chat.id(),
+            self.id(),
+            reaction_type,
+            chat.session_().client_().id(),
+        )
+        .await
+    }
+
+    /// Adds the reaction if it hasn't been chosen yet by the user, otherwise removes it.
+    pub(crate) async fn toggle_reaction(
+        &self,
+        reaction_type: tdlib::enums::ReactionType,
+    ) -> Result<(), tdlib::types::Error> {
+        if self.interaction_info().is_reaction_chosen(&reaction_type) {
+            self.remove_reaction(reaction_type).await
+        } else {
+            self.add_reaction(reaction_type).await
+        }
+    }
+
     fn set_content(&self, content: model::BoxedMessageContent) {
         if self.content() == content {
             return;
@@ -203,6 +351,14 @@ impl Message {
         self.notify_is_edited();
     }
 
+    fn set_is_pinned(&self, is_pinned: bool) {
+        if self.is_pinned() == is_pinned {
+            return;
+        }
+        self.imp().is_pinned.set(is_pinned);
+        self.notify_is_pinned();
+    }
+
     pub(crate) fn sender_name_expression(&self) -> gtk::Expression {
         match self.sender() {
             MessageSender::User(user) => {
@@ -1,10 +1,13 @@
 use std::cell::Cell;
+use std::cell::RefCell;
 
 use glib::Properties;
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
+use crate::model;
+
 mod imp {
     use super::*;
 
@@ -13,6 +16,8 @@ mod imp {
     pub(crate) struct MessageInteractionInfo {
         #[property(get)]
         pub(super) reply_count: Cell<u32>,
+        #[property(get)]
+        pub(super) reactions: RefCell<model::BoxedMessageReactions>,
     }
 
     #[glib::object_subclass]
@@ -38,16 +43,21 @@ glib::wrapper! {
 impl From<Option<tdlib::types::MessageInteractionInfo>> for MessageInteractionInfo {
     fn from(interaction_info: Option<tdlib::types::MessageInteractionInfo>) -> Self {
         let obj: Self = glib::Object::new();
-        obj.imp()
-            .reply_count
-            .set(extract_reply_count(interaction_info));
+        let imp = obj.imp();
+
+        imp.reply_count
+            .set(extract_reply_count(interaction_info.as_ref()));
+        imp.reactions
+            .replace(extract_reactions(interaction_info.as_ref()));
+
         obj
     }
 }
 
 impl MessageInteractionInfo {
     pub(crate) fn update(&self, interaction_info: Option<tdlib::types::MessageInteractionInfo>) {
-        self.set_reply_count(extract_reply_count(interaction_info));
+        self.set_reply_count(extract_reply_count(interaction_info.as_ref()));
+        self.set_reactions(extract_reactions(interaction_info.as_ref()));
     }
 
     fn set_reply_count(&self, reply_count: u32) {
@@ -57,11 +67,39 @@ impl MessageInteractionInfo {
         self.imp().reply_count.set(reply_count);
         self.notify_reply_count()
     }
+
+    fn set_reactions(&self, reactions: model::BoxedMessageReactions) {
+        if self.reactions() == reactions {
+            return;
+        }
+        self.imp().reactions.replace(reactions);
+        self.notify_reactions()
+    }
+
+    /// Returns whether the given reaction has been chosen by the current user.
+    pub(crate) fn is_reaction_chosen(&self, reaction_type: &tdlib::enums::ReactionType) -> bool {
+        self.imp()
+            .reactions
+            .borrow()
+            .0
+            .iter()
+            .any(|reaction| reaction.is_chosen && &reaction.r#type == reaction_type)
+    }
 }
 
-fn extract_reply_count(interaction_info: Option<tdlib::types::MessageInteractionInfo>) -> u32 {
+fn extract_reply_count(interaction_info: Option<&tdlib::types::MessageInteractionInfo>) -> u32 {
     interaction_info
-        .and_then(|interaction_info| interaction_info.reply_info)
+        .and_then(|interaction_info| interaction_info.reply_info.as_ref())
         .map(|reply_info| reply_info.reply_count)
         .unwrap_or(0) as u32
 }
+
+fn extract_reactions(
+    interaction_info: Option<&tdlib::types::MessageInteractionInfo>,
+) -> model::BoxedMessageReactions {
+    model::BoxedMessageReactions(
+        interaction_info
+            .map(|interaction_info| interaction_info.reactions.clone())
+            .unwrap_or_default(),
+    )
+}
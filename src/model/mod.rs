@@ -1,3 +1,4 @@
+mod audio_player;
 mod avatar;
 mod basic_group;
 mod chat;
@@ -24,6 +25,7 @@ mod message;
 mod message_forward_info;
 mod message_interaction_info;
 mod secret_chat;
+mod shared_media_list;
 mod sponsored_message;
 mod supergroup;
 mod user;
@@ -33,6 +35,7 @@ use tdlib::enums::BlockList;
 use tdlib::enums::ChatMemberStatus;
 use tdlib::enums::MessageContent;
 use tdlib::enums::MessageReplyTo;
+use tdlib::enums::MessageSchedulingState;
 use tdlib::enums::MessageSendingState;
 use tdlib::enums::UserStatus;
 use tdlib::enums::UserType;
@@ -47,6 +50,7 @@ use tdlib::types::FormattedText;
 use tdlib::types::ScopeNotificationSettings;
 use tdlib::types::UpdateNotificationGroup;
 
+pub(crate) use self::audio_player::AudioPlayer;
 pub(crate) use self::avatar::Avatar;
 pub(crate) use self::basic_group::BasicGroup;
 pub(crate) use self::chat::Chat;
@@ -82,6 +86,7 @@ pub(crate) use self::message_forward_info::MessageForwardOrigin;
 pub(crate) use self::message_interaction_info::MessageInteractionInfo;
 pub(crate) use self::secret_chat::SecretChat;
 pub(crate) use self::secret_chat::SecretChatState;
+pub(crate) use self::shared_media_list::SharedMediaList;
 pub(crate) use self::sponsored_message::SponsoredMessage;
 pub(crate) use self::supergroup::Supergroup;
 pub(crate) use self::user::User;
@@ -175,6 +180,10 @@ impl Default for BoxedMessageContent {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "BoxedMessageReactions")]
+pub(crate) struct BoxedMessageReactions(pub(crate) Vec<tdlib::types::MessageReaction>);
+
 #[derive(Clone, Debug, PartialEq, glib::Boxed)]
 #[boxed_type(name = "BoxedMessageReplyTo", nullable)]
 pub(crate) struct BoxedMessageReplyTo(pub(crate) MessageReplyTo);
@@ -183,6 +192,10 @@ pub(crate) struct BoxedMessageReplyTo(pub(crate) MessageReplyTo);
 #[boxed_type(name = "BoxedScopeNotificationSettings", nullable)]
 pub(crate) struct BoxedScopeNotificationSettings(pub(crate) ScopeNotificationSettings);
 
+#[derive(Clone, Debug, PartialEq, glib::Boxed)]
+#[boxed_type(name = "BoxedSticker")]
+pub(crate) struct BoxedSticker(pub(crate) tdlib::types::Sticker);
+
 #[derive(Clone, Debug, PartialEq, glib::Boxed)]
 #[boxed_type(name = "BoxedUserStatus")]
 pub(crate) struct BoxedUserStatus(pub(crate) UserStatus);
@@ -204,3 +217,7 @@ impl Default for BoxedUserType {
 #[derive(Clone, Debug, glib::Boxed)]
 #[boxed_type(name = "BoxedMessageSendingState", nullable)]
 pub(crate) struct BoxedMessageSendingState(pub(crate) MessageSendingState);
+
+#[derive(Clone, Debug, glib::Boxed)]
+#[boxed_type(name = "BoxedMessageSchedulingState", nullable)]
+pub(crate) struct BoxedMessageSchedulingState(pub(crate) MessageSchedulingState);
@@ -0,0 +1,182 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use gio::prelude::*;
+use gio::subclass::prelude::*;
+use glib::clone;
+use gtk::gio;
+use gtk::glib;
+
+use crate::model;
+
+/// The number of messages requested at once.
+const PAGE_SIZE: i32 = 50;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct SharedMediaList {
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) filter: OnceCell<tdlib::enums::SearchMessagesFilter>,
+        pub(super) is_loading: Cell<bool>,
+        pub(super) is_fully_loaded: Cell<bool>,
+        /// The found messages, from the newest to the oldest
+        pub(super) list: RefCell<Vec<model::Message>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SharedMediaList {
+        const NAME: &'static str = "SharedMediaList";
+        type Type = super::SharedMediaList;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for SharedMediaList {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecBoolean::builder("is-loading")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("is-fully-loaded")
+                        .read_only()
+                        .build(),
+                ]
+            })
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "is-loading" => obj.is_loading().to_value(),
+                "is-fully-loaded" => obj.is_fully_loaded().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl ListModelImpl for SharedMediaList {
+        fn item_type(&self) -> glib::Type {
+            model::Message::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.list.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.list
+                .borrow()
+                .get(position as usize)
+                .map(glib::object::Cast::upcast_ref::<glib::Object>)
+                .cloned()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A list of the messages of a chat matching a search filter, e.g. its photos or its links,
+    /// that is loaded on demand.
+    pub(crate) struct SharedMediaList(ObjectSubclass<imp::SharedMediaList>)
+        @implements gio::ListModel;
+}
+
+impl SharedMediaList {
+    pub(crate) fn new(chat: &model::Chat, filter: tdlib::enums::SearchMessagesFilter) -> Self {
+        let obj: SharedMediaList = glib::Object::new();
+        let imp = obj.imp();
+
+        imp.chat.set(Some(chat));
+        imp.filter.set(filter).unwrap();
+
+        chat.connect_deleted_message(clone!(@weak obj => move |_, message| {
+            obj.remove(message);
+        }));
+
+        obj
+    }
+
+    pub(crate) fn is_loading(&self) -> bool {
+        self.imp().is_loading.get()
+    }
+
+    fn set_is_loading(&self, is_loading: bool) {
+        if self.is_loading() == is_loading {
+            return;
+        }
+        self.imp().is_loading.set(is_loading);
+        self.notify("is-loading");
+    }
+
+    /// Whether all the messages matching the filter have been loaded.
+    pub(crate) fn is_fully_loaded(&self) -> bool {
+        self.imp().is_fully_loaded.get()
+    }
+
+    fn set_is_fully_loaded(&self, is_fully_loaded: bool) {
+        if self.is_fully_loaded() == is_fully_loaded {
+            return;
+        }
+        self.imp().is_fully_loaded.set(is_fully_loaded);
+        self.notify("is-fully-loaded");
+    }
+
+    /// Loads the next older messages matching the filter, if they aren't already being loaded.
+    pub(crate) async fn load_more(&self) -> Result<(), tdlib::types::Error> {
+        let imp = self.imp();
+
+        if self.is_loading() || self.is_fully_loaded() {
+            return Ok(());
+        }
+        let Some(chat) = imp.chat.upgrade() else {
+            return Ok(());
+        };
+
+        let from_message_id = imp
+            .list
+            .borrow()
+            .last()
+            .map(model::Message::id)
+            .unwrap_or_default();
+
+        self.set_is_loading(true);
+        let result = chat
+            .search_messages(
+                imp.filter.get().unwrap().clone(),
+                from_message_id,
+                0,
+                PAGE_SIZE,
+            )
+            .await;
+        self.set_is_loading(false);
+
+        let messages = result?;
+        if messages.is_empty() {
+            self.set_is_fully_loaded(true);
+            return Ok(());
+        }
+
+        let position = imp.list.borrow().len();
+        imp.list.borrow_mut().extend(messages);
+        let added = imp.list.borrow().len() - position;
+
+        self.items_changed(position as u32, 0, added as u32);
+
+        Ok(())
+    }
+
+    fn remove(&self, message: &model::Message) {
+        let imp = self.imp();
+
+        let position = imp.list.borrow().iter().position(|m| m == message);
+        if let Some(position) = position {
+            imp.list.borrow_mut().remove(position);
+            self.items_changed(position as u32, 1, 0);
+        }
+    }
+}
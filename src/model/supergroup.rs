@@ -20,6 +20,8 @@ mod imp {
         #[property(get, set, construct_only)]
         pub(super) is_channel: OnceCell<bool>,
         #[property(get)]
+        pub(super) is_forum: Cell<bool>,
+        #[property(get)]
         pub(super) username: RefCell<String>,
         #[property(get)]
         pub(super) member_count: Cell<i32>,
@@ -68,6 +70,7 @@ impl From<tdlib::types::Supergroup> for Supergroup {
                 .unwrap_or_default(),
         );
         imp.member_count.set(td_supergroup.member_count);
+        imp.is_forum.set(td_supergroup.is_forum);
         imp.status
             .replace(model::BoxedChatMemberStatus(td_supergroup.status));
 
@@ -92,6 +95,14 @@ impl Supergroup {
         self.notify_member_count();
     }
 
+    fn set_is_forum(&self, is_forum: bool) {
+        if self.is_forum() == is_forum {
+            return;
+        }
+        self.imp().is_forum.set(is_forum);
+        self.notify_is_forum();
+    }
+
     fn set_status(&self, status: model::BoxedChatMemberStatus) {
         if self.status() == status {
             return;
@@ -108,6 +119,7 @@ impl Supergroup {
                 .unwrap_or_default(),
         );
         self.set_member_count(td_supergroup.member_count);
+        self.set_is_forum(td_supergroup.is_forum);
         self.set_status(model::BoxedChatMemberStatus(td_supergroup.status));
     }
 }
@@ -508,6 +508,7 @@ pub(crate) fn message_content(message: &model::Message) -> String {
         MessageGameScore(data) => message_game_score(&data, &chat, &sender),
         MessageContactRegistered => message_contact_registered(&sender),
         MessageVenue(data) => gettext_f("Location, {venue}", &[("venue", &data.venue.title)]),
+        MessagePoll(data) => message_poll(&data.poll),
         _ => gettext("Unsupported Message"),
     }
 }
@@ -560,6 +561,16 @@ fn message_photo(caption: &str) -> String {
     }
 }
 
+fn message_poll(poll: &tdlib::types::Poll) -> String {
+    if matches!(poll.r#type, tdlib::enums::PollType::Quiz(_)) {
+        // Translators: This is a quiz with its question
+        gettext_f("Quiz, {question}", &[("question", &poll.question)])
+    } else {
+        // Translators: This is a poll with its question
+        gettext_f("Poll, {question}", &[("question", &poll.question)])
+    }
+}
+
 fn message_sticker(emoji: &str) -> String {
     // Translators: This is a sticker with the associated emoji
     gettext_f("{emoji} Sticker", &[("emoji", emoji)])
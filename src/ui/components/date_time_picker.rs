@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::glib;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/components/date_time_picker.ui")]
+    pub(crate) struct DateTimePicker {
+        #[template_child]
+        pub(super) calendar: TemplateChild<gtk::Calendar>,
+        #[template_child]
+        pub(super) hour_spin_button: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub(super) minute_spin_button: TemplateChild<gtk::SpinButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for DateTimePicker {
+        const NAME: &'static str = "PaplDateTimePicker";
+        type Type = super::DateTimePicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("datetimepicker");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for DateTimePicker {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecBoxed::builder::<glib::DateTime>("date-time")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "date-time" => obj.set_date_time(&value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "date-time" => obj.date_time().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for DateTimePicker {}
+}
+
+glib::wrapper! {
+    /// A widget to choose a local date and a time, with a precision of minutes.
+    pub(crate) struct DateTimePicker(ObjectSubclass<imp::DateTimePicker>)
+        @extends gtk::Widget;
+}
+
+impl Default for DateTimePicker {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+#[gtk::template_callbacks]
+impl DateTimePicker {
+    /// Shows a dialog to choose a date and a time in the future, starting from `date_time`.
+    /// Returns `None` if the dialog has been cancelled.
+    pub(crate) async fn choose(
+        parent: &impl IsA<gtk::Window>,
+        heading: &str,
+        response_label: &str,
+        date_time: &glib::DateTime,
+    ) -> Option<glib::DateTime> {
+        let picker = Self::default();
+        picker.set_date_time(date_time);
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(heading)
+            .extra_child(&picker)
+            .transient_for(parent)
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("choose", response_label)]);
+        dialog.set_default_response(Some("choose"));
+        dialog.set_response_appearance("choose", adw::ResponseAppearance::Suggested);
+
+        let update_response = clone!(@weak dialog => move |picker: &Self| {
+            let is_future = glib::DateTime::now_local()
+                .map(|now| picker.date_time() > now)
+                .unwrap_or_default();
+            dialog.set_response_enabled("choose", is_future);
+        });
+        update_response(&picker);
+        picker.connect_notify_local(Some("date-time"), move |picker, _| {
+            update_response(picker);
+        });
+
+        (dialog.choose_future().await == "choose").then(|| picker.date_time())
+    }
+
+    pub(crate) fn date_time(&self) -> glib::DateTime {
+        let imp = self.imp();
+        let date = imp.calendar.date();
+
+        glib::DateTime::from_local(
+            date.year(),
+            date.month(),
+            date.day_of_month(),
+            imp.hour_spin_button.value_as_int(),
+            imp.minute_spin_button.value_as_int(),
+            0.0,
+        )
+        .unwrap_or(date)
+    }
+
+    pub(crate) fn set_date_time(&self, date_time: &glib::DateTime) {
+        if self.date_time() == *date_time {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.calendar.select_day(date_time);
+        imp.hour_spin_button.set_value(date_time.hour() as f64);
+        imp.minute_spin_button.set_value(date_time.minute() as f64);
+
+        self.notify("date-time");
+    }
+
+    #[template_callback]
+    fn on_changed(&self) {
+        self.notify("date-time");
+    }
+
+    #[template_callback]
+    fn on_spin_button_output(spin_button: &gtk::SpinButton) -> bool {
+        // Always show two digits, like in a clock
+        spin_button.set_text(&format!("{:02}", spin_button.value_as_int()));
+        true
+    }
+}
@@ -1,15 +1,76 @@
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::sync::OnceLock;
 
+use adw::prelude::*;
+use gettextrs::gettext;
 use glib::clone;
 use glib::subclass::Signal;
 use gtk::gdk;
 use gtk::glib;
-use gtk::prelude::*;
+use gtk::graphene;
+use gtk::pango;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
 use crate::model;
+use crate::strings;
+use crate::ui;
+use crate::utils;
+
+/// The formats that can be toggled on the selected text, with their action names, shortcuts and
+/// the names of their text tags.
+const FORMATS: &[(&str, gdk::Key, gdk::ModifierType, &str)] = &[
+    (
+        "message-entry.format-bold",
+        gdk::Key::b,
+        gdk::ModifierType::CONTROL_MASK,
+        "bold",
+    ),
+    (
+        "message-entry.format-italic",
+        gdk::Key::i,
+        gdk::ModifierType::CONTROL_MASK,
+        "italic",
+    ),
+    (
+        "message-entry.format-underline",
+        gdk::Key::u,
+        gdk::ModifierType::CONTROL_MASK,
+        "underline",
+    ),
+    (
+        "message-entry.format-strikethrough",
+        gdk::Key::x,
+        gdk::ModifierType::CONTROL_MASK.union(gdk::ModifierType::SHIFT_MASK),
+        "strikethrough",
+    ),
+    (
+        "message-entry.format-monospace",
+        gdk::Key::m,
+        gdk::ModifierType::CONTROL_MASK.union(gdk::ModifierType::SHIFT_MASK),
+        "code",
+    ),
+    (
+        "message-entry.format-spoiler",
+        gdk::Key::p,
+        gdk::ModifierType::CONTROL_MASK.union(gdk::ModifierType::SHIFT_MASK),
+        "spoiler",
+    ),
+];
+
+/// The maximum number of chat members suggested for a mention.
+const MAX_MEMBER_COMPLETIONS: i32 = 20;
+/// The minimum length of an emoji shortcode, without the colon, to suggest emoji.
+const MIN_EMOJI_SHORTCODE_LENGTH: usize = 2;
+
+/// A suggestion to complete the word at the cursor.
+#[derive(Debug)]
+enum Completion {
+    Member(model::User),
+    BotCommand(tdlib::types::BotCommand),
+    Emoji(String),
+}
 
 mod imp {
     use super::*;
@@ -19,6 +80,8 @@ mod imp {
     pub(crate) struct MessageEntry {
         pub(super) chat: glib::WeakRef<model::Chat>,
         pub(super) formatted_text: RefCell<Option<model::BoxedFormattedText>>,
+        /// Whether the text is the one of a sent message, whose markdown characters are literal
+        pub(super) is_sent_text: Cell<bool>,
         #[template_child]
         pub(super) overlay: TemplateChild<gtk::Overlay>,
         #[template_child]
@@ -27,6 +90,19 @@ mod imp {
         pub(super) emoji_button: TemplateChild<gtk::Image>,
         #[template_child]
         pub(super) text_view: TemplateChild<gtk::TextView>,
+        #[template_child]
+        pub(super) format_popover: TemplateChild<gtk::Popover>,
+        /// The offset, in characters, of the word being completed
+        pub(super) completion_offset: Cell<i32>,
+        pub(super) completions: RefCell<Vec<Completion>>,
+        /// Used to discard the completions of outdated requests
+        pub(super) completions_generation: Cell<u32>,
+        /// The id of the chat and its bot commands, which are loaded once per chat
+        pub(super) bot_commands: RefCell<Option<(i64, Vec<tdlib::types::BotCommand>)>>,
+        #[template_child]
+        pub(super) completion_popover: TemplateChild<gtk::Popover>,
+        #[template_child]
+        pub(super) completion_list_box: TemplateChild<gtk::ListBox>,
     }
 
     #[glib::object_subclass]
@@ -38,6 +114,35 @@ mod imp {
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
             klass.bind_template_callbacks();
+
+            for (action_name, key, modifiers, tag_name) in FORMATS {
+                klass.install_action(action_name, None, move |widget, _, _| {
+                    widget.toggle_format(tag_name);
+                });
+                klass.add_binding_action(*key, *modifiers, action_name);
+            }
+
+            klass.install_action_async(
+                "message-entry.edit-link",
+                None,
+                |widget, _, _| async move {
+                    widget.edit_link().await;
+                },
+            );
+            klass.add_binding_action(
+                gdk::Key::k,
+                gdk::ModifierType::CONTROL_MASK,
+                "message-entry.edit-link",
+            );
+
+            klass.install_action("message-entry.clear-formatting", None, |widget, _, _| {
+                widget.clear_formatting();
+            });
+            klass.add_binding_action(
+                gdk::Key::n,
+                gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK,
+                "message-entry.clear-formatting",
+            );
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -111,6 +216,10 @@ mod imp {
             let key_events = gtk::EventControllerKey::new();
             key_events.connect_key_pressed(
                 clone!(@weak obj => @default-return glib::Propagation::Proceed, move |_, key, _, modifier| {
+                    if obj.imp().completion_popover.is_visible() && obj.handle_completion_key(key) {
+                        return glib::Propagation::Stop;
+                    }
+
                     if !modifier.contains(gdk::ModifierType::CONTROL_MASK)
                         && !modifier.contains(gdk::ModifierType::SHIFT_MASK)
                         && (key == gdk::Key::Return || key == gdk::Key::KP_Enter)
@@ -130,11 +239,33 @@ mod imp {
             }));
             self.emoji_button.add_controller(press);
 
-            self.text_view
-                .buffer()
-                .connect_changed(clone!(@weak obj => move |_| {
-                    obj.text_buffer_changed();
-                }));
+            let buffer = self.text_view.buffer();
+            buffer.connect_changed(clone!(@weak obj => move |_| {
+                obj.text_buffer_changed();
+                obj.update_completion();
+            }));
+
+            // Show the formatting popover above the selected text
+            self.format_popover.set_parent(&*obj);
+            buffer.connect_mark_set(clone!(@weak obj => move |buffer, _, mark| {
+                if mark == &buffer.get_insert() || mark == &buffer.selection_bound() {
+                    obj.update_format_popover();
+                    obj.update_completion();
+                }
+            }));
+
+            // Show the completions above the word being typed
+            self.completion_popover.set_parent(&*obj);
+
+            let focus_events = gtk::EventControllerFocus::new();
+            focus_events.connect_enter(clone!(@weak obj => move |_| {
+                obj.update_format_popover();
+            }));
+            focus_events.connect_leave(clone!(@weak obj => move |_| {
+                obj.imp().format_popover.popdown();
+                obj.hide_completion();
+            }));
+            self.text_view.add_controller(focus_events);
 
             self.text_view
                 .connect_paste_clipboard(clone!(@weak obj => move |_| {
@@ -143,6 +274,8 @@ mod imp {
         }
 
         fn dispose(&self) {
+            self.format_popover.unparent();
+            self.completion_popover.unparent();
             self.overlay.unparent();
         }
     }
@@ -151,6 +284,12 @@ mod imp {
         fn grab_focus(&self) -> bool {
             self.text_view.grab_focus()
         }
+
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+            self.format_popover.present();
+            self.completion_popover.present();
+        }
     }
 
     #[gtk::template_callbacks]
@@ -159,6 +298,11 @@ mod imp {
         fn on_gesture_click_pressed(gesture_click: &gtk::GestureClick) {
             gesture_click.set_state(gtk::EventSequenceState::Claimed);
         }
+
+        #[template_callback]
+        fn on_completion_row_activated(&self, row: &gtk::ListBoxRow) {
+            self.obj().apply_completion(row.index());
+        }
     }
 }
 
@@ -180,12 +324,13 @@ impl MessageEntry {
             .into();
 
         if text.is_empty() {
+            imp.is_sent_text.set(false);
             imp.formatted_text.replace(None);
             imp.placeholder.set_visible(true);
         } else {
             let formatted_text = tdlib::types::FormattedText {
+                entities: self.entities(&text),
                 text,
-                entities: vec![],
             };
             imp.formatted_text
                 .replace(Some(model::BoxedFormattedText(formatted_text)));
@@ -196,6 +341,399 @@ impl MessageEntry {
         self.notify("formatted-text");
     }
 
+    /// Returns the text entities of the text tags of the buffer, whose text is given.
+    fn entities(&self, text: &str) -> Vec<tdlib::types::TextEntity> {
+        let buffer = self.imp().text_view.buffer();
+
+        // The offsets of the characters in UTF-16 code units
+        let mut utf16_offsets = Vec::with_capacity(text.len() + 1);
+        let mut code_units = 0;
+        for c in text.chars() {
+            utf16_offsets.push(code_units);
+            code_units += c.len_utf16();
+        }
+        let utf16_offset = |iter: &gtk::TextIter| {
+            utf16_offsets
+                .get(iter.offset() as usize)
+                .copied()
+                .unwrap_or(code_units)
+        };
+
+        let mut entities = Vec::new();
+        buffer.tag_table().foreach(|tag| {
+            let Some(entity_type) = tag.name().and_then(|name| entity_type(&name)) else {
+                return;
+            };
+
+            let mut iter = buffer.start_iter();
+            loop {
+                if !iter.starts_tag(Some(tag)) && !iter.forward_to_tag_toggle(Some(tag)) {
+                    break;
+                }
+                let start = utf16_offset(&iter);
+                iter.forward_to_tag_toggle(Some(tag));
+                let end = utf16_offset(&iter);
+
+                if start < end {
+                    entities.push(tdlib::types::TextEntity {
+                        offset: start as i32,
+                        length: (end - start) as i32,
+                        r#type: entity_type.clone(),
+                    });
+                }
+            }
+        });
+
+        entities.sort_by_key(|entity| entity.offset);
+        entities
+    }
+
+    /// Returns the text tag with the name, creating it if needed.
+    fn tag(&self, name: &str) -> gtk::TextTag {
+        let tag_table = self.imp().text_view.buffer().tag_table();
+        if let Some(tag) = tag_table.lookup(name) {
+            return tag;
+        }
+
+        let tag = gtk::TextTag::new(Some(name));
+        match name.split_once(':').map_or(name, |(kind, _)| kind) {
+            "bold" => tag.set_weight(700),
+            "italic" | "quote" => tag.set_style(pango::Style::Italic),
+            "underline" | "link" | "mention-name" => tag.set_underline(pango::Underline::Single),
+            "strikethrough" => tag.set_strikethrough(true),
+            "code" | "pre" => tag.set_family(Some("monospace")),
+            "spoiler" => tag.set_background_rgba(Some(&gdk::RGBA::new(0.5, 0.5, 0.5, 0.3))),
+            _ => {}
+        }
+        tag_table.add(&tag);
+
+        tag
+    }
+
+    /// Applies the format to the selected text, or removes it if the whole selection has it.
+    fn toggle_format(&self, tag_name: &str) {
+        let buffer = self.imp().text_view.buffer();
+        let Some((start, end)) = buffer.selection_bounds() else {
+            return;
+        };
+
+        // Text spanning multiple lines is formatted as a block of code
+        let tag_name = if tag_name == "code" && buffer.text(&start, &end, true).contains('\n') {
+            "pre"
+        } else {
+            tag_name
+        };
+        let tag = self.tag(tag_name);
+
+        let mut iter = start.clone();
+        let is_formatted = iter.has_tag(&tag) && {
+            iter.forward_to_tag_toggle(Some(&tag));
+            iter.offset() >= end.offset()
+        };
+
+        buffer.begin_user_action();
+        if is_formatted {
+            buffer.remove_tag(&tag, &start, &end);
+        } else {
+            buffer.apply_tag(&tag, &start, &end);
+        }
+        buffer.end_user_action();
+
+        self.text_buffer_changed();
+    }
+
+    fn clear_formatting(&self) {
+        let buffer = self.imp().text_view.buffer();
+        if let Some((start, end)) = buffer.selection_bounds() {
+            buffer.remove_all_tags(&start, &end);
+            self.text_buffer_changed();
+        }
+    }
+
+    /// Asks for the URL of a link on the selected text, removing the link if it's empty.
+    async fn edit_link(&self) {
+        let buffer = self.imp().text_view.buffer();
+        let Some((start, end)) = buffer.selection_bounds() else {
+            return;
+        };
+        let (start_offset, end_offset) = (start.offset(), end.offset());
+
+        let url = start
+            .tags()
+            .into_iter()
+            .find_map(|tag| Some(tag.name()?.strip_prefix("link:")?.to_owned()))
+            .unwrap_or_default();
+
+        let url_entry = gtk::Entry::builder()
+            .text(url)
+            .placeholder_text(gettext("URL"))
+            .input_purpose(gtk::InputPurpose::Url)
+            .activates_default(true)
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Link"))
+            .extra_child(&url_entry)
+            .build();
+        dialog.set_transient_for(self.root().and_downcast_ref::<gtk::Window>());
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("save", &gettext("_Save"))]);
+        dialog.set_default_response(Some("save"));
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+        if dialog.choose_future().await != "save" {
+            return;
+        }
+
+        let url = url_entry.text().trim().to_owned();
+        let start = buffer.iter_at_offset(start_offset);
+        let end = buffer.iter_at_offset(end_offset);
+
+        let mut link_tags = Vec::new();
+        buffer.tag_table().foreach(|tag| {
+            if tag.name().is_some_and(|name| name.starts_with("link:")) {
+                link_tags.push(tag.clone());
+            }
+        });
+
+        buffer.begin_user_action();
+        for tag in link_tags {
+            buffer.remove_tag(&tag, &start, &end);
+        }
+        if !url.is_empty() {
+            buffer.apply_tag(
+                &self.tag(&format!("link:{}", utils::linkify(&url))),
+                &start,
+                &end,
+            );
+        }
+        buffer.end_user_action();
+
+        self.text_buffer_changed();
+        self.grab_focus();
+    }
+
+    fn update_format_popover(&self) {
+        let imp = self.imp();
+        let buffer = imp.text_view.buffer();
+
+        let Some((start, end)) = buffer.selection_bounds() else {
+            imp.format_popover.popdown();
+            return;
+        };
+
+        self.popup_at(&imp.format_popover, &start, &end);
+    }
+
+    /// Shows the popover pointing to the text between the iters, or to the part of it in the
+    /// first line.
+    fn popup_at(&self, popover: &gtk::Popover, start: &gtk::TextIter, end: &gtk::TextIter) {
+        let text_view = &*self.imp().text_view;
+
+        let start_rect = text_view.iter_location(start);
+        let end_rect = text_view.iter_location(end);
+        let (x, y) = text_view.buffer_to_window_coords(
+            gtk::TextWindowType::Widget,
+            start_rect.x(),
+            start_rect.y(),
+        );
+        let Some(point) = text_view.compute_point(self, &graphene::Point::new(x as f32, y as f32))
+        else {
+            return;
+        };
+        let width = if start_rect.y() == end_rect.y() {
+            (end_rect.x() - start_rect.x()).max(1)
+        } else {
+            1
+        };
+
+        popover.set_pointing_to(Some(&gdk::Rectangle::new(
+            point.x() as i32,
+            point.y() as i32,
+            width,
+            start_rect.height(),
+        )));
+        if popover.is_visible() {
+            popover.present();
+        } else {
+            popover.popup();
+        }
+    }
+
+    /// Suggests completions for the word at the cursor, if it's a mention, a bot command at the
+    /// start of the text or an emoji shortcode.
+    fn update_completion(&self) {
+        let imp = self.imp();
+        let buffer = imp.text_view.buffer();
+
+        let generation = imp.completions_generation.get().wrapping_add(1);
+        imp.completions_generation.set(generation);
+
+        let Some(chat) = self.chat() else {
+            self.hide_completion();
+            return;
+        };
+        if buffer.has_selection() {
+            self.hide_completion();
+            return;
+        }
+
+        let cursor = buffer.iter_at_mark(&buffer.get_insert());
+        let mut line_start = cursor.clone();
+        line_start.set_line_offset(0);
+        let line = buffer.text(&line_start, &cursor, true);
+        let word = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let word_offset = cursor.offset() - word.chars().count() as i32;
+
+        let mut chars = word.chars();
+        let kind = chars.next();
+        let query = chars.as_str().to_owned();
+        let kind = match kind {
+            Some('@') if can_mention_members(&chat) => '@',
+            Some('/') if word_offset == 0 => '/',
+            Some(':') if query.chars().count() >= MIN_EMOJI_SHORTCODE_LENGTH => ':',
+            _ => {
+                self.hide_completion();
+                return;
+            }
+        };
+
+        imp.completion_offset.set(word_offset);
+
+        utils::spawn(clone!(@weak self as obj, @weak chat => async move {
+            let completions = match kind {
+                '@' => member_completions(&chat, query).await,
+                '/' => obj.bot_command_completions(&chat, &query).await,
+                _ => emoji_completions(&chat, query).await,
+            };
+
+            if obj.imp().completions_generation.get() == generation {
+                obj.show_completions(completions);
+            }
+        }));
+    }
+
+    async fn bot_command_completions(&self, chat: &model::Chat, query: &str) -> Vec<Completion> {
+        let imp = self.imp();
+
+        let is_loaded = imp
+            .bot_commands
+            .borrow()
+            .as_ref()
+            .is_some_and(|(chat_id, _)| *chat_id == chat.id());
+        if !is_loaded {
+            let bot_commands = bot_commands(chat).await;
+            imp.bot_commands.replace(Some((chat.id(), bot_commands)));
+        }
+
+        imp.bot_commands
+            .borrow()
+            .iter()
+            .flat_map(|(_, bot_commands)| bot_commands)
+            .filter(|bot_command| bot_command.command.starts_with(query))
+            .cloned()
+            .map(Completion::BotCommand)
+            .collect()
+    }
+
+    fn show_completions(&self, completions: Vec<Completion>) {
+        let imp = self.imp();
+
+        imp.completion_list_box.remove_all();
+        for completion in &completions {
+            imp.completion_list_box
+                .append(&new_completion_row(completion));
+        }
+        imp.completion_list_box
+            .select_row(imp.completion_list_box.row_at_index(0).as_ref());
+
+        let is_empty = completions.is_empty();
+        imp.completions.replace(completions);
+
+        if is_empty {
+            imp.completion_popover.popdown();
+        } else {
+            let buffer = imp.text_view.buffer();
+            let start = buffer.iter_at_offset(imp.completion_offset.get());
+            self.popup_at(&imp.completion_popover, &start, &start);
+        }
+    }
+
+    fn hide_completion(&self) {
+        let imp = self.imp();
+        imp.completions.replace(Vec::new());
+        imp.completion_popover.popdown();
+    }
+
+    /// Moves through the completions or applies the selected one, returning whether the key
+    /// has been handled.
+    fn handle_completion_key(&self, key: gdk::Key) -> bool {
+        let list_box = &self.imp().completion_list_box;
+        let index = list_box
+            .selected_row()
+            .map(|row| row.index())
+            .unwrap_or_default();
+
+        match key {
+            gdk::Key::Up | gdk::Key::Down => {
+                let index = if key == gdk::Key::Up {
+                    index - 1
+                } else {
+                    index + 1
+                };
+                if let Some(row) = list_box.row_at_index(index) {
+                    list_box.select_row(Some(&row));
+                }
+            }
+            gdk::Key::Return | gdk::Key::KP_Enter | gdk::Key::Tab => self.apply_completion(index),
+            gdk::Key::Escape => self.hide_completion(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Replaces the word at the cursor with the completion at the index.
+    fn apply_completion(&self, index: i32) {
+        let imp = self.imp();
+        let buffer = imp.text_view.buffer();
+
+        let Some(completion) = imp.completions.take().into_iter().nth(index as usize) else {
+            return;
+        };
+        imp.completion_popover.popdown();
+
+        let (text, mention_user_id) = match completion {
+            Completion::Member(user) if user.username().is_empty() => {
+                (strings::user_display_name(&user, true), Some(user.id()))
+            }
+            Completion::Member(user) => (format!("@{}", user.username()), None),
+            Completion::BotCommand(bot_command) => (format!("/{}", bot_command.command), None),
+            Completion::Emoji(emoji) => (emoji, None),
+        };
+
+        buffer.begin_user_action();
+
+        let mut start = buffer.iter_at_offset(imp.completion_offset.get());
+        let mut end = buffer.iter_at_mark(&buffer.get_insert());
+        buffer.delete(&mut start, &mut end);
+        buffer.insert(&mut start, &text);
+
+        // Users without a username are mentioned by a link to their id
+        if let Some(user_id) = mention_user_id {
+            let mention_start = buffer.iter_at_offset(imp.completion_offset.get());
+            buffer.apply_tag(
+                &self.tag(&format!("mention-name:{user_id}")),
+                &mention_start,
+                &start,
+            );
+        }
+        buffer.insert(&mut start, " ");
+
+        buffer.end_user_action();
+
+        self.text_buffer_changed();
+    }
+
     /// Insert text inside the message entry at the cursor position,
     /// deleting eventual selected text
     pub(crate) fn insert_at_cursor(&self, text: &str) {
@@ -215,12 +753,51 @@ impl MessageEntry {
             return;
         }
 
-        let text = formatted_text.map(|f| f.0.text).unwrap_or_default();
-        self.imp().text_view.buffer().set_text(&text);
+        let imp = self.imp();
+        imp.is_sent_text.set(false);
+
+        let buffer = imp.text_view.buffer();
+        let Some(formatted_text) = formatted_text.map(|f| f.0) else {
+            buffer.set_text("");
+            return;
+        };
+
+        buffer.set_text(&formatted_text.text);
+
+        // Restore the formatting of the text from its entities
+        let text = &formatted_text.text;
+        for entity in &formatted_text.entities {
+            let Some(tag_name) = tag_name(&entity.r#type) else {
+                continue;
+            };
+            let start = char_offset(text, entity.offset);
+            let end = char_offset(text, entity.offset + entity.length);
+            buffer.apply_tag(
+                &self.tag(&tag_name),
+                &buffer.iter_at_offset(start),
+                &buffer.iter_at_offset(end),
+            );
+        }
+
+        self.text_buffer_changed();
     }
 
+    /// Sets the text of a sent message, to be edited. Its markdown characters are kept literal.
+    pub(crate) fn set_sent_text(&self, formatted_text: tdlib::types::FormattedText) {
+        self.set_formatted_text(Some(model::BoxedFormattedText(formatted_text)));
+        self.imp().is_sent_text.set(true);
+    }
+
+    /// Returns the formatted text. The markdown typed in the text is parsed only if the text
+    /// has no formatting yet and isn't the one of a sent message.
     pub(crate) async fn as_markdown(&self) -> Option<tdlib::types::FormattedText> {
-        let text = self.imp().formatted_text.borrow().clone().map(|f| f.0)?;
+        let imp = self.imp();
+
+        let text = imp.formatted_text.borrow().clone().map(|f| f.0)?;
+        if !text.entities.is_empty() || imp.is_sent_text.get() {
+            return Some(text);
+        }
+
         let client_id = self.chat().unwrap().session_().client_().id();
 
         tdlib::functions::parse_markdown(text.clone(), client_id)
@@ -301,3 +878,230 @@ impl Default for MessageEntry {
         Self::new()
     }
 }
+
+/// Returns the name of the text tag of an entity, if it can be sent.
+fn tag_name(entity_type: &tdlib::enums::TextEntityType) -> Option<String> {
+    use tdlib::enums::TextEntityType::*;
+
+    Some(match entity_type {
+        Bold => "bold".to_owned(),
+        Italic => "italic".to_owned(),
+        Underline => "underline".to_owned(),
+        Strikethrough => "strikethrough".to_owned(),
+        Spoiler => "spoiler".to_owned(),
+        Code => "code".to_owned(),
+        Pre => "pre".to_owned(),
+        PreCode(data) => format!("pre:{}", data.language),
+        BlockQuote => "quote".to_owned(),
+        TextUrl(data) => format!("link:{}", data.url),
+        MentionName(data) => format!("mention-name:{}", data.user_id),
+        CustomEmoji(data) => format!("custom-emoji:{}", data.custom_emoji_id),
+        _ => return None,
+    })
+}
+
+/// Returns the entity of a text tag, given its name.
+fn entity_type(tag_name: &str) -> Option<tdlib::enums::TextEntityType> {
+    use tdlib::enums::TextEntityType::*;
+
+    Some(match tag_name.split_once(':') {
+        None => match tag_name {
+            "bold" => Bold,
+            "italic" => Italic,
+            "underline" => Underline,
+            "strikethrough" => Strikethrough,
+            "spoiler" => Spoiler,
+            "code" => Code,
+            "pre" => Pre,
+            "quote" => BlockQuote,
+            _ => return None,
+        },
+        Some(("pre", language)) => PreCode(tdlib::types::TextEntityTypePreCode {
+            language: language.to_owned(),
+        }),
+        Some(("link", url)) => TextUrl(tdlib::types::TextEntityTypeTextUrl {
+            url: url.to_owned(),
+        }),
+        Some(("mention-name", user_id)) => MentionName(tdlib::types::TextEntityTypeMentionName {
+            user_id: user_id.parse().ok()?,
+        }),
+        Some(("custom-emoji", custom_emoji_id)) => {
+            CustomEmoji(tdlib::types::TextEntityTypeCustomEmoji {
+                custom_emoji_id: custom_emoji_id.parse().ok()?,
+            })
+        }
+        _ => return None,
+    })
+}
+
+/// Converts an offset in UTF-16 code units of the text to an offset in characters.
+fn char_offset(text: &str, utf16_offset: i32) -> i32 {
+    let mut code_units = 0;
+
+    for (index, c) in text.chars().enumerate() {
+        if code_units >= utf16_offset.max(0) as usize {
+            return index as i32;
+        }
+        code_units += c.len_utf16();
+    }
+
+    text.chars().count() as i32
+}
+
+/// Returns whether the chat has members that can be mentioned.
+fn can_mention_members(chat: &model::Chat) -> bool {
+    match chat.chat_type() {
+        model::ChatType::BasicGroup(_) => true,
+        model::ChatType::Supergroup(supergroup) => !supergroup.is_channel(),
+        model::ChatType::Private(_) | model::ChatType::Secret(_) => false,
+    }
+}
+
+async fn member_completions(chat: &model::Chat, query: String) -> Vec<Completion> {
+    let session = chat.session_();
+    let filter = tdlib::enums::ChatMembersFilter::Mention(tdlib::types::ChatMembersFilterMention {
+        message_thread_id: 0,
+    });
+
+    match tdlib::functions::search_chat_members(
+        chat.id(),
+        query,
+        MAX_MEMBER_COMPLETIONS,
+        Some(filter),
+        session.client_().id(),
+    )
+    .await
+    {
+        Ok(tdlib::enums::ChatMembers::ChatMembers(data)) => data
+            .members
+            .into_iter()
+            .filter_map(|member| match member.member_id {
+                tdlib::enums::MessageSender::User(data) => {
+                    Some(Completion::Member(session.user(data.user_id)))
+                }
+                tdlib::enums::MessageSender::Chat(_) => None,
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to search chat members: {e:?}");
+            Vec::new()
+        }
+    }
+}
+
+/// Returns the commands of the bots of the chat.
+async fn bot_commands(chat: &model::Chat) -> Vec<tdlib::types::BotCommand> {
+    let client_id = chat.session_().client_().id();
+
+    let result = match chat.chat_type() {
+        model::ChatType::Private(user) => {
+            if !matches!(user.user_type().0, tdlib::enums::UserType::Bot(_)) {
+                return Vec::new();
+            }
+            tdlib::functions::get_user_full_info(user.id(), client_id)
+                .await
+                .map(|tdlib::enums::UserFullInfo::UserFullInfo(data)| {
+                    data.bot_info
+                        .map(|bot_info| bot_info.commands)
+                        .unwrap_or_default()
+                })
+        }
+        model::ChatType::BasicGroup(basic_group) => {
+            tdlib::functions::get_basic_group_full_info(basic_group.id(), client_id)
+                .await
+                .map(
+                    |tdlib::enums::BasicGroupFullInfo::BasicGroupFullInfo(data)| {
+                        data.bot_commands
+                            .into_iter()
+                            .flat_map(|bot_commands| bot_commands.commands)
+                            .collect()
+                    },
+                )
+        }
+        model::ChatType::Supergroup(supergroup) => {
+            tdlib::functions::get_supergroup_full_info(supergroup.id(), client_id)
+                .await
+                .map(
+                    |tdlib::enums::SupergroupFullInfo::SupergroupFullInfo(data)| {
+                        data.bot_commands
+                            .into_iter()
+                            .flat_map(|bot_commands| bot_commands.commands)
+                            .collect()
+                    },
+                )
+        }
+        model::ChatType::Secret(_) => return Vec::new(),
+    };
+
+    result.unwrap_or_else(|e| {
+        log::warn!("Failed to load the bot commands of a chat: {e:?}");
+        Vec::new()
+    })
+}
+
+async fn emoji_completions(chat: &model::Chat, query: String) -> Vec<Completion> {
+    let client_id = chat.session_().client_().id();
+
+    match tdlib::functions::search_emojis(query, false, Vec::new(), client_id).await {
+        Ok(tdlib::enums::Emojis::Emojis(data)) => {
+            data.emojis.into_iter().map(Completion::Emoji).collect()
+        }
+        Err(e) => {
+            log::warn!("Failed to search emoji: {e:?}");
+            Vec::new()
+        }
+    }
+}
+
+fn new_completion_row(completion: &Completion) -> gtk::ListBoxRow {
+    let row = gtk::Box::builder().spacing(12).build();
+
+    let (title, subtitle) = match completion {
+        Completion::Member(user) => {
+            let avatar = ui::Avatar::new();
+            avatar.set_size(32);
+            avatar.set_item(Some(user.clone().upcast()));
+            row.append(&avatar);
+
+            let username = user.username();
+            (
+                strings::user_display_name(user, true),
+                (!username.is_empty()).then(|| format!("@{username}")),
+            )
+        }
+        Completion::BotCommand(bot_command) => (
+            format!("/{}", bot_command.command),
+            Some(bot_command.description.clone()),
+        ),
+        Completion::Emoji(emoji) => (emoji.clone(), None),
+    };
+
+    let labels = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .valign(gtk::Align::Center)
+        .build();
+    labels.append(
+        &gtk::Label::builder()
+            .label(title)
+            .xalign(0.0)
+            .ellipsize(pango::EllipsizeMode::End)
+            .build(),
+    );
+    if let Some(subtitle) = subtitle.filter(|subtitle| !subtitle.is_empty()) {
+        labels.append(
+            &gtk::Label::builder()
+                .label(subtitle)
+                .xalign(0.0)
+                .ellipsize(pango::EllipsizeMode::End)
+                .css_classes(["caption", "dim-label"])
+                .build(),
+        );
+    }
+    row.append(&labels);
+
+    // Keep the focus in the text view while choosing a completion
+    gtk::ListBoxRow::builder()
+        .child(&row)
+        .focusable(false)
+        .build()
+}
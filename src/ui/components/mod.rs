@@ -3,6 +3,7 @@ mod avatar;
 mod avatar_map_marker;
 mod avatar_with_selection;
 mod circular_progress_bar;
+mod date_time_picker;
 mod icon_map_marker;
 mod map;
 mod map_marker;
@@ -11,12 +12,14 @@ mod message_entry;
 mod phone_number_input;
 mod snow;
 mod sticker;
+mod zoomable_picture;
 
 pub(crate) use self::animated_bin::AnimatedBin;
 pub(crate) use self::avatar::Avatar;
 pub(crate) use self::avatar_map_marker::AvatarMapMarker;
 pub(crate) use self::avatar_with_selection::AvatarWithSelection;
 pub(crate) use self::circular_progress_bar::CircularProgressBar;
+pub(crate) use self::date_time_picker::DateTimePicker;
 pub(crate) use self::icon_map_marker::IconMapMarker;
 pub(crate) use self::map::Map;
 pub(crate) use self::map_marker::MapMarker;
@@ -25,3 +28,4 @@ pub(crate) use self::message_entry::MessageEntry;
 pub(crate) use self::phone_number_input::PhoneNumberInput;
 pub(crate) use self::snow::Snow;
 pub(crate) use self::sticker::Sticker;
+pub(crate) use self::zoomable_picture::ZoomablePicture;
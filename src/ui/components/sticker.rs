@@ -126,11 +126,22 @@ impl Sticker {
     }
 
     pub(crate) fn play_animation(&self) {
-        if let Some(animation) = &*self.imp().child.borrow() {
-            if let Some(animation) = animation.downcast_ref::<rlt::Animation>() {
+        if let Some(child) = &*self.imp().child.borrow() {
+            if let Some(animation) = child.downcast_ref::<rlt::Animation>() {
                 if !animation.is_playing() {
                     animation.play();
                 }
+            } else if let Some(stream) = child
+                .downcast_ref::<gtk::Picture>()
+                .and_then(|picture| picture.paintable())
+                .and_downcast::<gtk::MediaStream>()
+            {
+                if !stream.is_playing() {
+                    if stream.is_ended() {
+                        stream.seek(0);
+                    }
+                    stream.play();
+                }
             }
         }
     }
@@ -185,7 +196,16 @@ impl Sticker {
                     }
                 }
             }
-            _ => unimplemented!(),
+            tdlib::enums::StickerFormat::Webm => {
+                let media = gtk::MediaFile::for_filename(&path);
+                media.set_muted(true);
+                media.set_loop(looped);
+                media.play();
+
+                let picture = gtk::Picture::new();
+                picture.set_paintable(Some(&media));
+                picture.upcast()
+            }
         };
 
         // Skip if widget was recycled by ListView
@@ -0,0 +1,302 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::graphene;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.5;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct ZoomablePicture {
+        pub(super) texture: RefCell<Option<gdk::Texture>>,
+        /// The zoom level, relative to the size at which the texture fits the widget
+        pub(super) zoom: Cell<f64>,
+        /// The rotation, in quarter turns clockwise
+        pub(super) rotation: Cell<u32>,
+        /// The offset of the texture from the center of the widget
+        pub(super) offset: Cell<(f64, f64)>,
+        pub(super) drag_start_offset: Cell<(f64, f64)>,
+        pub(super) zoom_start: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ZoomablePicture {
+        const NAME: &'static str = "PaplZoomablePicture";
+        type Type = super::ZoomablePicture;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("zoomablepicture");
+        }
+    }
+
+    impl ObjectImpl for ZoomablePicture {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<gdk::Texture>("texture")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecDouble::builder("zoom")
+                        .minimum(MIN_ZOOM)
+                        .maximum(MAX_ZOOM)
+                        .default_value(MIN_ZOOM)
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "texture" => obj.set_texture(value.get::<Option<&gdk::Texture>>().unwrap()),
+                "zoom" => obj.set_zoom(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "texture" => obj.texture().to_value(),
+                "zoom" => obj.zoom().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            self.zoom.set(MIN_ZOOM);
+            obj.set_overflow(gtk::Overflow::Hidden);
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_begin(clone!(@weak obj => move |_, _, _| {
+                let imp = obj.imp();
+                imp.drag_start_offset.set(imp.offset.get());
+            }));
+            drag.connect_drag_update(clone!(@weak obj => move |_, x, y| {
+                let (start_x, start_y) = obj.imp().drag_start_offset.get();
+                obj.set_offset(start_x + x, start_y + y);
+            }));
+            obj.add_controller(drag);
+
+            let zoom = gtk::GestureZoom::new();
+            zoom.connect_begin(clone!(@weak obj => move |_, _| {
+                obj.imp().zoom_start.set(obj.zoom());
+            }));
+            zoom.connect_scale_changed(clone!(@weak obj => move |_, scale| {
+                obj.set_zoom(obj.imp().zoom_start.get() * scale);
+            }));
+            obj.add_controller(zoom);
+
+            let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+            scroll.connect_scroll(
+                clone!(@weak obj => @default-return glib::Propagation::Proceed, move |_, _, dy| {
+                    if dy < 0.0 {
+                        obj.zoom_in();
+                    } else if dy > 0.0 {
+                        obj.zoom_out();
+                    }
+                    glib::Propagation::Stop
+                }),
+            );
+            obj.add_controller(scroll);
+
+            let click = gtk::GestureClick::new();
+            click.connect_pressed(clone!(@weak obj => move |_, n_press, _, _| {
+                if n_press == 2 {
+                    if obj.zoom() > MIN_ZOOM {
+                        obj.set_zoom(MIN_ZOOM);
+                    } else {
+                        obj.zoom_in();
+                    }
+                }
+            }));
+            obj.add_controller(click);
+        }
+    }
+
+    impl WidgetImpl for ZoomablePicture {
+        fn measure(&self, _orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            (0, 0, -1, -1)
+        }
+
+        fn size_allocate(&self, _width: i32, _height: i32, _baseline: i32) {
+            // Keep the offset valid for the new size
+            let (x, y) = self.offset.get();
+            self.obj().set_offset(x, y);
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+
+            let Some(texture) = &*self.texture.borrow() else {
+                return;
+            };
+
+            let scale = obj.scale() as f32;
+            let width = texture.width() as f32 * scale;
+            let height = texture.height() as f32 * scale;
+            let (x, y) = self.offset.get();
+
+            snapshot.translate(&graphene::Point::new(
+                obj.width() as f32 / 2.0 + x as f32,
+                obj.height() as f32 / 2.0 + y as f32,
+            ));
+            snapshot.rotate(self.rotation.get() as f32 * 90.0);
+            snapshot.append_texture(
+                texture,
+                &graphene::Rect::new(-width / 2.0, -height / 2.0, width, height),
+            );
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A picture that can be zoomed, panned and rotated.
+    pub(crate) struct ZoomablePicture(ObjectSubclass<imp::ZoomablePicture>)
+        @extends gtk::Widget;
+}
+
+impl Default for ZoomablePicture {
+    fn default() -> Self {
+        glib::Object::builder().build()
+    }
+}
+
+impl ZoomablePicture {
+    pub(crate) fn texture(&self) -> Option<gdk::Texture> {
+        self.imp().texture.borrow().clone()
+    }
+
+    /// Sets the texture to show, resetting the zoom and the rotation.
+    pub(crate) fn set_texture(&self, texture: Option<&gdk::Texture>) {
+        let imp = self.imp();
+
+        if self.texture().as_ref() == texture {
+            return;
+        }
+
+        imp.texture.replace(texture.cloned());
+        imp.rotation.set(0);
+        imp.offset.set((0.0, 0.0));
+        self.set_zoom(MIN_ZOOM);
+        self.queue_draw();
+
+        self.notify("texture");
+    }
+
+    pub(crate) fn zoom(&self) -> f64 {
+        self.imp().zoom.get()
+    }
+
+    pub(crate) fn set_zoom(&self, zoom: f64) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if self.zoom() == zoom {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.zoom.set(zoom);
+
+        let (x, y) = imp.offset.get();
+        self.set_offset(x, y);
+        self.queue_draw();
+
+        self.notify("zoom");
+    }
+
+    pub(crate) fn can_zoom_in(&self) -> bool {
+        self.texture().is_some() && self.zoom() < MAX_ZOOM
+    }
+
+    pub(crate) fn can_zoom_out(&self) -> bool {
+        self.texture().is_some() && self.zoom() > MIN_ZOOM
+    }
+
+    pub(crate) fn zoom_in(&self) {
+        self.set_zoom(self.zoom() * ZOOM_STEP);
+    }
+
+    pub(crate) fn zoom_out(&self) {
+        self.set_zoom(self.zoom() / ZOOM_STEP);
+    }
+
+    /// Rotates the texture by a quarter turn.
+    pub(crate) fn rotate(&self, clockwise: bool) {
+        let imp = self.imp();
+
+        let rotation = if clockwise {
+            imp.rotation.get() + 1
+        } else {
+            imp.rotation.get() + 3
+        };
+        imp.rotation.set(rotation % 4);
+
+        let (x, y) = imp.offset.get();
+        self.set_offset(x, y);
+        self.queue_draw();
+    }
+
+    /// Returns the size of the texture once rotated.
+    fn rotated_size(&self) -> (f64, f64) {
+        let imp = self.imp();
+
+        let Some(texture) = &*imp.texture.borrow() else {
+            return (0.0, 0.0);
+        };
+
+        let (width, height) = (texture.width() as f64, texture.height() as f64);
+        if imp.rotation.get() % 2 == 0 {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    /// Returns the scale at which the texture is drawn. Textures smaller than the widget are
+    /// never enlarged, unless zoomed.
+    fn scale(&self) -> f64 {
+        let (width, height) = self.rotated_size();
+        if width == 0.0 || height == 0.0 {
+            return 0.0;
+        }
+
+        let fit_scale = (self.width() as f64 / width)
+            .min(self.height() as f64 / height)
+            .min(1.0);
+
+        fit_scale * self.zoom()
+    }
+
+    /// Sets the offset of the texture, keeping it from going past the edges of the widget.
+    fn set_offset(&self, x: f64, y: f64) {
+        let (width, height) = self.rotated_size();
+        let scale = self.scale();
+
+        let max_x = ((width * scale - self.width() as f64) / 2.0).max(0.0);
+        let max_y = ((height * scale - self.height() as f64) / 2.0).max(0.0);
+
+        self.imp()
+            .offset
+            .set((x.clamp(-max_x, max_x), y.clamp(-max_y, max_y)));
+        self.queue_draw();
+    }
+}
@@ -14,6 +14,7 @@ pub(crate) use self::components::Avatar;
 pub(crate) use self::components::AvatarMapMarker;
 pub(crate) use self::components::AvatarWithSelection;
 pub(crate) use self::components::CircularProgressBar;
+pub(crate) use self::components::DateTimePicker;
 pub(crate) use self::components::IconMapMarker;
 pub(crate) use self::components::Map;
 pub(crate) use self::components::MapMarker;
@@ -22,22 +23,31 @@ pub(crate) use self::components::MessageEntry;
 pub(crate) use self::components::PhoneNumberInput;
 pub(crate) use self::components::Snow;
 pub(crate) use self::components::Sticker;
+pub(crate) use self::components::ZoomablePicture;
 pub(crate) use self::login::Code as LoginCode;
 pub(crate) use self::login::Login;
 pub(crate) use self::login::OtherDevice as LoginOtherDevice;
 pub(crate) use self::login::Password as LoginPassword;
 pub(crate) use self::login::PhoneNumber as LoginPhoneNumber;
 pub(crate) use self::login::Registration as LoginRegistration;
+pub(crate) use self::session::AnimationPicker;
+pub(crate) use self::session::AnimationThumbnail;
+pub(crate) use self::session::AudioPlayerBar;
 pub(crate) use self::session::Background;
 pub(crate) use self::session::ChatActionBar;
 pub(crate) use self::session::ChatHistory;
 pub(crate) use self::session::ChatHistoryRow;
 pub(crate) use self::session::ChatInfoWindow;
+pub(crate) use self::session::ChatSearchBar;
 pub(crate) use self::session::ContactRow;
 pub(crate) use self::session::ContactsWindow;
 pub(crate) use self::session::Content;
 pub(crate) use self::session::EventRow;
+pub(crate) use self::session::ForumTopicList;
+pub(crate) use self::session::ForwardWindow;
 pub(crate) use self::session::MediaPicture;
+pub(crate) use self::session::MediaViewer;
+pub(crate) use self::session::MessageAudio;
 pub(crate) use self::session::MessageBase;
 pub(crate) use self::session::MessageBaseExt;
 pub(crate) use self::session::MessageBaseImpl;
@@ -48,16 +58,28 @@ pub(crate) use self::session::MessageIndicators;
 pub(crate) use self::session::MessageLabel;
 pub(crate) use self::session::MessageLocation;
 pub(crate) use self::session::MessagePhoto;
+pub(crate) use self::session::MessagePoll;
+pub(crate) use self::session::MessageReactionPicker;
+pub(crate) use self::session::MessageReactions;
 pub(crate) use self::session::MessageReply;
 pub(crate) use self::session::MessageRow;
 pub(crate) use self::session::MessageSticker;
 pub(crate) use self::session::MessageText;
 pub(crate) use self::session::MessageVenue;
 pub(crate) use self::session::MessageVideo;
+pub(crate) use self::session::MessageVideoNote;
+pub(crate) use self::session::MessageVoiceNote;
+pub(crate) use self::session::MessageWaveform;
+pub(crate) use self::session::MessageWebPage;
+pub(crate) use self::session::PinnedMessagesBar;
 pub(crate) use self::session::PreferencesWindow;
 pub(crate) use self::session::Row as SessionRow;
+pub(crate) use self::session::ScheduledMessagesWindow;
 pub(crate) use self::session::SendMediaWindow;
+pub(crate) use self::session::SendPollWindow;
 pub(crate) use self::session::Session;
+pub(crate) use self::session::SharedMediaRow;
+pub(crate) use self::session::SharedMediaThumbnail;
 pub(crate) use self::session::Sidebar;
 pub(crate) use self::session::SidebarAvatar;
 pub(crate) use self::session::SidebarChatFolderBar;
@@ -69,16 +91,22 @@ pub(crate) use self::session::SidebarMiniThumbnail;
 pub(crate) use self::session::SidebarRow;
 pub(crate) use self::session::SidebarSearch;
 pub(crate) use self::session::SidebarSearchItemRow;
+pub(crate) use self::session::SidebarSearchMessageRow;
 pub(crate) use self::session::SidebarSearchRow;
 pub(crate) use self::session::SidebarSearchSection;
 pub(crate) use self::session::SidebarSearchSectionRow;
 pub(crate) use self::session::SidebarSearchSectionType;
 pub(crate) use self::session::SidebarSelection;
+pub(crate) use self::session::StickerPicker;
+pub(crate) use self::session::StickerSetWindow;
 pub(crate) use self::session::Switcher as SessionSwitcher;
 pub(crate) use self::window::Window;
 
 pub(crate) fn init() {
     AnimatedBin::static_type();
+    AnimationPicker::static_type();
+    AnimationThumbnail::static_type();
+    AudioPlayerBar::static_type();
     Avatar::static_type();
     AvatarMapMarker::static_type();
     AvatarWithSelection::static_type();
@@ -87,13 +115,17 @@ pub(crate) fn init() {
     ChatHistory::static_type();
     ChatHistoryRow::static_type();
     ChatInfoWindow::static_type();
+    ChatSearchBar::static_type();
     CircularProgressBar::static_type();
     ClientManagerView::static_type();
     ClientView::static_type();
     ContactRow::static_type();
     ContactsWindow::static_type();
     Content::static_type();
+    DateTimePicker::static_type();
     EventRow::static_type();
+    ForumTopicList::static_type();
+    ForwardWindow::static_type();
     IconMapMarker::static_type();
     Login::static_type();
     LoginCode::static_type();
@@ -105,6 +137,8 @@ pub(crate) fn init() {
     MapMarker::static_type();
     MapWindow::static_type();
     MediaPicture::static_type();
+    MediaViewer::static_type();
+    MessageAudio::static_type();
     MessageBase::static_type();
     MessageBubble::static_type();
     MessageDocument::static_type();
@@ -114,18 +148,30 @@ pub(crate) fn init() {
     MessageLabel::static_type();
     MessageLocation::static_type();
     MessagePhoto::static_type();
+    MessagePoll::static_type();
+    MessageReactionPicker::static_type();
+    MessageReactions::static_type();
     MessageReply::static_type();
     MessageRow::static_type();
     MessageSticker::static_type();
     MessageText::static_type();
     MessageVenue::static_type();
     MessageVideo::static_type();
+    MessageVideoNote::static_type();
+    MessageVoiceNote::static_type();
+    MessageWaveform::static_type();
+    MessageWebPage::static_type();
     PhoneNumberInput::static_type();
+    PinnedMessagesBar::static_type();
     PreferencesWindow::static_type();
+    ScheduledMessagesWindow::static_type();
     SendMediaWindow::static_type();
+    SendPollWindow::static_type();
     Session::static_type();
     SessionRow::static_type();
     SessionSwitcher::static_type();
+    SharedMediaRow::static_type();
+    SharedMediaThumbnail::static_type();
     Sidebar::static_type();
     SidebarAvatar::static_type();
     SidebarChatFolderBar::static_type();
@@ -137,11 +183,15 @@ pub(crate) fn init() {
     SidebarRow::static_type();
     SidebarSearch::static_type();
     SidebarSearchItemRow::static_type();
+    SidebarSearchMessageRow::static_type();
     SidebarSearchRow::static_type();
     SidebarSearchSection::static_type();
     SidebarSearchSectionRow::static_type();
     SidebarSearchSectionType::static_type();
     Snow::static_type();
     Sticker::static_type();
+    StickerPicker::static_type();
+    StickerSetWindow::static_type();
     Window::static_type();
+    ZoomablePicture::static_type();
 }
@@ -0,0 +1,364 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use gettextrs::gettext;
+use glib::clone;
+use glib::subclass::Signal;
+use glib::Properties;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::utils;
+
+/// The time to wait after the last change of the query before searching it.
+const SEARCH_DELAY: Duration = Duration::from_millis(500);
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::AnimationPicker)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/animation_picker.ui")]
+    pub(crate) struct AnimationPicker {
+        #[property(get, set = Self::set_chat, explicit_notify, nullable)]
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) model: OnceCell<gio::ListStore>,
+        pub(super) saved_animations: RefCell<Vec<tdlib::types::Animation>>,
+        /// The user id and the chat id of the bot used to search animations
+        pub(super) search_bot: Cell<Option<(i64, i64)>>,
+        /// Whether the saved animations must be reloaded the next time the picker is shown
+        pub(super) is_outdated: Cell<bool>,
+        /// Used to discard the results of outdated requests
+        pub(super) generation: Cell<u32>,
+        pub(super) search_source_id: RefCell<Option<glib::SourceId>>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) grid_view: TemplateChild<gtk::GridView>,
+        #[template_child]
+        pub(super) empty_status_page: TemplateChild<adw::StatusPage>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AnimationPicker {
+        const NAME: &'static str = "PaplAnimationPicker";
+        type Type = super::AnimationPicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("animationpicker");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AnimationPicker {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("animation-selected")
+                    .param_types([glib::BoxedAnyObject::static_type()])
+                    .build()]
+            })
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            self.is_outdated.set(true);
+
+            let model = gio::ListStore::new::<glib::BoxedAnyObject>();
+            self.grid_view
+                .set_model(Some(&gtk::NoSelection::new(Some(model.clone()))));
+            self.model.set(model).unwrap();
+
+            let factory = gtk::SignalListItemFactory::new();
+            factory.connect_setup(|_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                list_item.set_child(Some(&ui::AnimationThumbnail::default()));
+            });
+            factory.connect_bind(clone!(@weak obj => move |_, list_item| {
+                let Some(chat) = obj.chat() else {
+                    return;
+                };
+
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                let thumbnail = list_item
+                    .child()
+                    .and_downcast::<ui::AnimationThumbnail>()
+                    .unwrap();
+                let animation = list_item
+                    .item()
+                    .and_downcast::<glib::BoxedAnyObject>()
+                    .map(|item| item.borrow::<tdlib::types::Animation>().clone());
+
+                thumbnail.set_animation(animation, &chat.session_());
+            }));
+            self.grid_view.set_factory(Some(&factory));
+        }
+
+        fn dispose(&self) {
+            if let Some(source_id) = self.search_source_id.take() {
+                source_id.remove();
+            }
+
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for AnimationPicker {
+        fn map(&self) {
+            self.parent_map();
+
+            if self.is_outdated.get() {
+                self.obj().load_saved_animations();
+            }
+        }
+    }
+
+    impl AnimationPicker {
+        fn set_chat(&self, chat: Option<&model::Chat>) {
+            let obj = &*self.obj();
+            if obj.chat().as_ref() == chat {
+                return;
+            }
+
+            // The saved animations and the search bot depend on the session of the chat
+            let is_same_session = obj
+                .chat()
+                .zip(chat)
+                .map(|(old, new)| old.session_() == new.session_())
+                .unwrap_or_default();
+            if !is_same_session {
+                self.search_bot.set(None);
+                self.is_outdated.set(true);
+            }
+
+            self.chat.set(chat);
+            obj.notify_chat();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A panel to choose a GIF from the saved ones or from the results of a search.
+    pub(crate) struct AnimationPicker(ObjectSubclass<imp::AnimationPicker>)
+        @extends gtk::Widget;
+}
+
+impl Default for AnimationPicker {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+#[gtk::template_callbacks]
+impl AnimationPicker {
+    pub(crate) fn connect_animation_selected<F: Fn(&Self, tdlib::types::Animation) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("animation-selected", true, move |values| {
+            let obj = values[0].get::<Self>().unwrap();
+            let item = values[1].get::<glib::BoxedAnyObject>().unwrap();
+            f(&obj, item.borrow::<tdlib::types::Animation>().clone());
+            None
+        })
+    }
+
+    /// Marks the saved animations as outdated, to reload them the next time the picker is shown.
+    pub(crate) fn invalidate(&self) {
+        self.imp().is_outdated.set(true);
+    }
+
+    fn model(&self) -> &gio::ListStore {
+        self.imp().model.get().unwrap()
+    }
+
+    fn load_saved_animations(&self) {
+        let imp = self.imp();
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let client_id = chat.session_().client_().id();
+
+        imp.is_outdated.set(false);
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            match tdlib::functions::get_saved_animations(client_id).await {
+                Ok(tdlib::enums::Animations::Animations(data)) => {
+                    obj.imp().saved_animations.replace(data.animations);
+                }
+                Err(e) => log::warn!("Failed to load the saved animations: {e:?}"),
+            }
+
+            if obj.imp().search_entry.text().trim().is_empty() {
+                let animations = obj.imp().saved_animations.borrow().clone();
+                obj.show_animations(animations, &gettext("No Saved GIFs"));
+            }
+        }));
+    }
+
+    fn show_animations(&self, animations: Vec<tdlib::types::Animation>, empty_title: &str) {
+        let imp = self.imp();
+
+        let items = animations
+            .into_iter()
+            .map(glib::BoxedAnyObject::new)
+            .collect::<Vec<_>>();
+        let model = self.model();
+        model.splice(0, model.n_items(), &items);
+
+        if items.is_empty() {
+            imp.empty_status_page.set_title(empty_title);
+            imp.stack.set_visible_child_name("empty");
+        } else {
+            imp.stack.set_visible_child_name("animations");
+        }
+    }
+
+    /// Returns the user id and the chat id of the inline bot used to search animations.
+    async fn search_bot(&self, client_id: i32) -> anyhow::Result<(i64, i64)> {
+        if let Some(search_bot) = self.imp().search_bot.get() {
+            return Ok(search_bot);
+        }
+
+        let username = match tdlib::functions::get_option(
+            "animation_search_bot_username".to_owned(),
+            client_id,
+        )
+        .await
+        .map_err(|e| anyhow!("{e:?}"))?
+        {
+            tdlib::enums::OptionValue::String(data) => data.value,
+            _ => return Err(anyhow!("The animation search bot is unknown")),
+        };
+
+        let tdlib::enums::Chat::Chat(chat) =
+            tdlib::functions::search_public_chat(username, client_id)
+                .await
+                .map_err(|e| anyhow!("{e:?}"))?;
+        let tdlib::enums::ChatType::Private(data) = chat.r#type else {
+            return Err(anyhow!("The animation search bot isn't a user"));
+        };
+
+        let search_bot = (data.user_id, chat.id);
+        self.imp().search_bot.set(Some(search_bot));
+
+        Ok(search_bot)
+    }
+
+    async fn search(&self, query: String) -> anyhow::Result<Vec<tdlib::types::Animation>> {
+        let Some(chat) = self.chat() else {
+            return Ok(Vec::new());
+        };
+        let client_id = chat.session_().client_().id();
+
+        let (bot_user_id, bot_chat_id) = self.search_bot(client_id).await?;
+
+        let tdlib::enums::InlineQueryResults::InlineQueryResults(data) =
+            tdlib::functions::get_inline_query_results(
+                bot_user_id,
+                bot_chat_id,
+                None,
+                query,
+                String::new(),
+                client_id,
+            )
+            .await
+            .map_err(|e| anyhow!("{e:?}"))?;
+
+        Ok(data
+            .results
+            .into_iter()
+            .filter_map(|result| match result {
+                tdlib::enums::InlineQueryResult::Animation(data) => Some(data.animation),
+                _ => None,
+            })
+            .collect())
+    }
+
+    #[template_callback]
+    fn on_search_changed(&self) {
+        let imp = self.imp();
+
+        // The results of the previous query are outdated
+        let generation = imp.generation.get().wrapping_add(1);
+        imp.generation.set(generation);
+
+        if let Some(source_id) = imp.search_source_id.take() {
+            source_id.remove();
+        }
+
+        let query = imp.search_entry.text().trim().to_owned();
+        if query.is_empty() {
+            let animations = imp.saved_animations.borrow().clone();
+            self.show_animations(animations, &gettext("No Saved GIFs"));
+            return;
+        }
+
+        imp.stack.set_visible_child_name("loading");
+
+        let source_id = glib::timeout_add_local_once(
+            SEARCH_DELAY,
+            clone!(@weak self as obj => move || {
+                obj.imp().search_source_id.take();
+                obj.search_query(query, generation);
+            }),
+        );
+        imp.search_source_id.replace(Some(source_id));
+    }
+
+    fn search_query(&self, query: String, generation: u32) {
+        utils::spawn(clone!(@weak self as obj => async move {
+            let result = obj.search(query).await;
+
+            if obj.imp().generation.get() != generation {
+                return;
+            }
+
+            let animations = result.unwrap_or_else(|e| {
+                log::warn!("Failed to search animations: {e:?}");
+                Vec::new()
+            });
+            obj.show_animations(animations, &gettext("No GIFs Found"));
+        }));
+    }
+
+    #[template_callback]
+    fn on_grid_view_activated(&self, position: u32) {
+        if let Some(item) = self.model().item(position) {
+            self.emit_by_name::<()>("animation-selected", &[&item]);
+        }
+    }
+}
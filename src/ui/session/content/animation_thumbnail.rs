@@ -0,0 +1,129 @@
+use std::cell::Cell;
+
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use crate::model;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct AnimationThumbnail {
+        /// The id of the shown file, used to skip the results of outdated downloads
+        pub(super) file_id: Cell<i32>,
+        pub(super) picture: gtk::Picture,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AnimationThumbnail {
+        const NAME: &'static str = "PaplAnimationThumbnail";
+        type Type = super::AnimationThumbnail;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("animationthumbnail");
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+    }
+
+    impl ObjectImpl for AnimationThumbnail {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.picture.set_content_fit(gtk::ContentFit::Cover);
+            self.picture.set_parent(&*self.obj());
+        }
+
+        fn dispose(&self) {
+            self.picture.unparent();
+        }
+    }
+
+    impl WidgetImpl for AnimationThumbnail {}
+}
+
+glib::wrapper! {
+    /// An autoplaying, muted and looping thumbnail of an animation.
+    pub(crate) struct AnimationThumbnail(ObjectSubclass<imp::AnimationThumbnail>)
+        @extends gtk::Widget;
+}
+
+impl Default for AnimationThumbnail {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl AnimationThumbnail {
+    pub(crate) fn set_animation(
+        &self,
+        animation: Option<tdlib::types::Animation>,
+        session: &model::ClientStateSession,
+    ) {
+        let imp = self.imp();
+
+        let Some(animation) = animation else {
+            imp.file_id.set(0);
+            imp.picture.set_paintable(gdk::Paintable::NONE);
+            return;
+        };
+
+        // Prefer the animated thumbnail, as it's much smaller than the animation
+        let file = animation
+            .thumbnail
+            .as_ref()
+            .filter(|t| {
+                matches!(
+                    t.format,
+                    tdlib::enums::ThumbnailFormat::Mpeg4 | tdlib::enums::ThumbnailFormat::Gif
+                )
+            })
+            .map(|t| t.file.clone())
+            .unwrap_or_else(|| animation.animation.clone());
+
+        let minithumbnail = animation.minithumbnail;
+
+        if imp.file_id.replace(file.id) == file.id {
+            return;
+        }
+
+        imp.picture.set_paintable(
+            minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                })
+                .as_ref(),
+        );
+
+        if file.local.is_downloading_completed {
+            self.play(&file.local.path);
+        } else {
+            utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                match session.download_file(file.id).await {
+                    Ok(file) => {
+                        // The thumbnail may have been recycled in the meantime
+                        if obj.imp().file_id.get() == file.id {
+                            obj.play(&file.local.path);
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to download an animation: {e:?}"),
+                }
+            }));
+        }
+    }
+
+    fn play(&self, path: &str) {
+        let media = gtk::MediaFile::for_filename(path);
+        media.set_muted(true);
+        media.set_loop(true);
+        media.play();
+
+        self.imp().picture.set_paintable(Some(&media));
+    }
+}
@@ -0,0 +1,227 @@
+use std::cell::OnceCell;
+
+use gettextrs::gettext;
+use glib::clone;
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::strings;
+use crate::ui;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::AudioPlayerBar)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/audio_player_bar.ui")]
+    pub(crate) struct AudioPlayerBar {
+        #[property(get, set = Self::set_session, explicit_notify, nullable)]
+        pub(super) session: glib::WeakRef<model::ClientStateSession>,
+        pub(super) player_signal_group: OnceCell<glib::SignalGroup>,
+        #[template_child]
+        pub(super) revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) subtitle_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) position_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) next_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) progress_bar: TemplateChild<gtk::ProgressBar>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AudioPlayerBar {
+        const NAME: &'static str = "PaplAudioPlayerBar";
+        type Type = super::AudioPlayerBar;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("audioplayerbar");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AudioPlayerBar {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let player_signal_group = glib::SignalGroup::new::<model::AudioPlayer>();
+            player_signal_group.connect_notify_local(
+                Some("message"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_message();
+                }),
+            );
+            for property in ["playing", "position", "duration", "has-next"] {
+                player_signal_group.connect_notify_local(
+                    Some(property),
+                    clone!(@weak obj => move |_, _| {
+                        obj.update_playback_state();
+                    }),
+                );
+            }
+            self.player_signal_group.set(player_signal_group).unwrap();
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for AudioPlayerBar {}
+
+    impl AudioPlayerBar {
+        fn set_session(&self, session: Option<&model::ClientStateSession>) {
+            let obj = &*self.obj();
+            if obj.session().as_ref() == session {
+                return;
+            }
+
+            self.session.set(session);
+            self.player_signal_group.get().unwrap().set_target(
+                session
+                    .map(model::ClientStateSession::audio_player)
+                    .as_ref(),
+            );
+            obj.update_message();
+
+            obj.notify_session();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A bar showing the audio message that is being played by the session, if any.
+    pub(crate) struct AudioPlayerBar(ObjectSubclass<imp::AudioPlayerBar>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl AudioPlayerBar {
+    fn player(&self) -> Option<model::AudioPlayer> {
+        self.imp()
+            .player_signal_group
+            .get()
+            .unwrap()
+            .target()
+            .and_downcast()
+    }
+
+    fn update_message(&self) {
+        use tdlib::enums::MessageContent::*;
+
+        let imp = self.imp();
+
+        let Some(message) = self.player().and_then(|player| player.message()) else {
+            imp.revealer.set_reveal_child(false);
+            return;
+        };
+
+        match message.content().0 {
+            MessageAudio(data) => {
+                let audio = data.audio;
+                imp.title_label.set_label(if audio.title.is_empty() {
+                    &audio.file_name
+                } else {
+                    &audio.title
+                });
+                if audio.performer.is_empty() {
+                    imp.subtitle_label.set_label(&gettext("Unknown Artist"));
+                } else {
+                    imp.subtitle_label.set_label(&audio.performer);
+                }
+            }
+            _ => {
+                imp.title_label
+                    .set_label(&strings::message_sender(&message.sender(), true));
+                imp.subtitle_label.set_label(&gettext("Voice Message"));
+            }
+        }
+
+        imp.revealer.set_reveal_child(true);
+        self.update_playback_state();
+    }
+
+    fn update_playback_state(&self) {
+        let imp = self.imp();
+
+        let Some(player) = self.player() else {
+            return;
+        };
+
+        if player.playing() {
+            imp.play_button
+                .set_icon_name("media-playback-pause-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Pause")));
+        } else {
+            imp.play_button
+                .set_icon_name("media-playback-start-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Play")));
+        }
+
+        imp.position_label.set_label(&format!(
+            "{} / {}",
+            utils::format_duration(player.position() / 1000),
+            utils::format_duration(player.duration() / 1000)
+        ));
+        imp.progress_bar.set_fraction(player.progress());
+        imp.next_button.set_sensitive(player.has_next());
+    }
+
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        if let Some(player) = self.player() {
+            player.toggle_playback();
+        }
+    }
+
+    #[template_callback]
+    fn on_title_button_clicked(&self) {
+        if let Some(message) = self.player().and_then(|player| player.message()) {
+            utils::ancestor::<_, ui::Session>(self).select_message(&message);
+        }
+    }
+
+    #[template_callback]
+    fn on_next_button_clicked(&self) {
+        if let Some(player) = self.player() {
+            player.play_next();
+        }
+    }
+
+    #[template_callback]
+    fn on_close_button_clicked(&self) {
+        if let Some(player) = self.player() {
+            player.stop();
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::cell::Cell;
 use std::cell::OnceCell;
 use std::cell::RefCell;
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use gettextrs::gettext;
@@ -12,6 +13,8 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
+use super::voice_recorder::RecordedVoiceNote;
+use super::voice_recorder::VoiceRecorder;
 use crate::expressions;
 use crate::i18n::gettext_f;
 use crate::model;
@@ -22,6 +25,21 @@ use crate::utils;
 
 const PHOTO_MIME_TYPES: &[&str] = &["image/png", "image/jpeg"];
 
+/// Recordings shorter than this are considered accidental taps of the record button.
+const MIN_VOICE_NOTE_DURATION: Duration = Duration::from_secs(1);
+
+/// The distance, in pixels, the pointer has to be dragged away from the record button to
+/// cancel the recording.
+const RECORD_CANCEL_DISTANCE: f64 = 100.0;
+
+/// The time to wait after the last change of the message before previewing its first link.
+const LINK_PREVIEW_DELAY: Duration = Duration::from_millis(500);
+
+/// The maximum number of stickers suggested for an emoji.
+const MAX_STICKER_SUGGESTIONS: i32 = 20;
+/// The size of the longer side of the suggested stickers.
+const STICKER_SUGGESTION_SIZE: i32 = 64;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 enum ChatActionBarState {
     #[default]
@@ -46,6 +64,8 @@ mod imp {
     #[template(resource = "/app/drey/paper-plane/ui/session/content/chat_action_bar.ui")]
     pub(crate) struct ChatActionBar {
         pub(super) chat: glib::WeakRef<model::Chat>,
+        /// The id of the message thread the messages are sent to, or 0 to send them to the chat
+        pub(super) message_thread_id: Cell<i64>,
         pub(super) chat_action_in_cooldown: Cell<bool>,
         pub(super) state: Cell<ChatActionBarState>,
         pub(super) emoji_chooser: RefCell<Option<gtk::EmojiChooser>>,
@@ -53,6 +73,18 @@ mod imp {
         pub(super) basic_group_signal_group: OnceCell<glib::SignalGroup>,
         pub(super) supergroup_signal_group: OnceCell<glib::SignalGroup>,
         pub(super) bindings: RefCell<Vec<gtk::ExpressionWatch>>,
+        pub(super) voice_recorder: RefCell<Option<VoiceRecorder>>,
+        pub(super) recording_source_id: RefCell<Option<glib::SourceId>>,
+        /// The URL of the previewed link of the message
+        pub(super) link_preview_url: RefCell<Option<String>>,
+        /// The URL of the link whose preview has been dismissed
+        pub(super) dismissed_link_preview_url: RefCell<Option<String>>,
+        pub(super) link_preview_source_id: RefCell<Option<glib::SourceId>>,
+        /// Used to discard the link previews of outdated requests
+        pub(super) link_preview_generation: Cell<u32>,
+        /// Used to discard the sticker suggestions of outdated requests
+        pub(super) sticker_suggestions_generation: Cell<u32>,
+        pub(super) settings: utils::PaperPlaneSettings,
         #[template_child]
         pub(super) top_bar_revealer: TemplateChild<gtk::Revealer>,
         #[template_child]
@@ -62,11 +94,37 @@ mod imp {
         #[template_child]
         pub(super) top_bar_message_label: TemplateChild<gtk::Inscription>,
         #[template_child]
+        pub(super) link_preview_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) link_preview_title_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) link_preview_description_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) sticker_suggestions_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) sticker_suggestions_box: TemplateChild<gtk::Box>,
+        #[template_child]
         pub(super) message_entry: TemplateChild<ui::MessageEntry>,
         #[template_child]
+        pub(super) recording_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) recording_duration_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) media_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub(super) media_popover: TemplateChild<gtk::Popover>,
+        #[template_child]
+        pub(super) sticker_picker: TemplateChild<ui::StickerPicker>,
+        #[template_child]
+        pub(super) animation_picker: TemplateChild<ui::AnimationPicker>,
+        #[template_child]
         pub(super) send_message_button: TemplateChild<gtk::Button>,
         #[template_child]
-        pub(super) select_file_button: TemplateChild<gtk::Button>,
+        pub(super) send_options_menu: TemplateChild<gtk::PopoverMenu>,
+        #[template_child]
+        pub(super) record_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) attach_button: TemplateChild<gtk::MenuButton>,
         #[template_child]
         pub(super) restriction_label: TemplateChild<gtk::Label>,
         #[template_child]
@@ -83,6 +141,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_instance_callbacks();
 
             klass.install_action(
                 "chat-action-bar.cancel-action",
@@ -91,6 +150,13 @@ mod imp {
                     widget.cancel_action();
                 },
             );
+            klass.install_action(
+                "chat-action-bar.dismiss-link-preview",
+                None,
+                move |widget, _, _| {
+                    widget.dismiss_link_preview();
+                },
+            );
             klass.install_action_async(
                 "chat-action-bar.select-file",
                 None,
@@ -98,6 +164,9 @@ mod imp {
                     widget.select_file().await;
                 },
             );
+            klass.install_action("chat-action-bar.create-poll", None, move |widget, _, _| {
+                widget.create_poll();
+            });
             klass.install_action_async(
                 "chat-action-bar.send-message",
                 None,
@@ -105,10 +174,41 @@ mod imp {
                     if let ChatActionBarState::Editing(_) = widget.imp().state.get() {
                         widget.edit_message().await;
                     } else {
-                        widget.send_text_message().await;
+                        widget.send_text_message(Default::default()).await;
                     }
                 },
             );
+            klass.install_action_async(
+                "chat-action-bar.send-message-silently",
+                None,
+                |widget, _, _| async move {
+                    let options = tdlib::types::MessageSendOptions {
+                        disable_notification: true,
+                        ..Default::default()
+                    };
+                    widget.send_text_message(options).await;
+                },
+            );
+            klass.install_action_async(
+                "chat-action-bar.schedule-message",
+                None,
+                |widget, _, _| async move {
+                    widget.schedule_text_message().await;
+                },
+            );
+            klass.install_action_async(
+                "chat-action-bar.send-message-when-online",
+                None,
+                |widget, _, _| async move {
+                    let options = tdlib::types::MessageSendOptions {
+                        scheduling_state: Some(
+                            tdlib::enums::MessageSchedulingState::SendWhenOnline,
+                        ),
+                        ..Default::default()
+                    };
+                    widget.send_text_message(options).await;
+                },
+            );
             klass.install_action_async(
                 "chat-action-bar.join-chat",
                 None,
@@ -202,6 +302,14 @@ mod imp {
                 .unwrap()
                 .set_orientation(gtk::Orientation::Vertical);
 
+            self.send_options_menu
+                .set_parent(&*self.send_message_button);
+
+            // Voice notes can't be recorded without GStreamer
+            if gst::init().is_err() {
+                self.record_button.set_sensitive(false);
+            }
+
             self.message_entry.connect_formatted_text_notify(
                 clone!(@weak obj => move |message_entry, _| {
                     // Enable the send-message action only when the message entry contains
@@ -211,6 +319,9 @@ mod imp {
                         .map(|f| f.0.text.contains(|c: char| !c.is_whitespace()))
                         .unwrap_or_default();
                     obj.action_set_enabled("chat-action-bar.send-message", should_enable);
+                    obj.update_send_button();
+                    obj.queue_link_preview_update();
+                    obj.update_sticker_suggestions();
 
                     // Send typing action
                     utils::spawn(clone!(@weak obj => async move {
@@ -229,9 +340,27 @@ mod imp {
                     obj.show_emoji_chooser(&button);
                 }));
 
+            self.sticker_picker
+                .connect_sticker_selected(clone!(@weak obj => move |_, sticker| {
+                    obj.imp().media_popover.popdown();
+                    utils::spawn(clone!(@weak obj => async move {
+                        obj.send_sticker(sticker).await;
+                    }));
+                }));
+
+            self.animation_picker.connect_animation_selected(
+                clone!(@weak obj => move |_, animation| {
+                    obj.imp().media_popover.popdown();
+                    utils::spawn(clone!(@weak obj => async move {
+                        obj.send_animation(animation).await;
+                    }));
+                }),
+            );
+
             // The message entry is always empty at this point, so disable the
             // send-message action
             obj.action_set_enabled("chat-action-bar.send-message", false);
+            obj.update_send_button();
 
             self.message_entry
                 .connect_activate(clone!(@weak obj => move |_| {
@@ -242,7 +371,20 @@ mod imp {
         }
 
         fn dispose(&self) {
+            if let Some(voice_recorder) = self.voice_recorder.take() {
+                voice_recorder.cancel();
+            }
+            if let Some(source_id) = self.recording_source_id.take() {
+                source_id.remove();
+            }
+            if let Some(source_id) = self.link_preview_source_id.take() {
+                source_id.remove();
+            }
+
+            self.send_options_menu.unparent();
             self.top_bar_revealer.unparent();
+            self.link_preview_revealer.unparent();
+            self.sticker_suggestions_revealer.unparent();
             self.action_bar_stack.unparent();
             if let Some(emoji_chooser) = self.emoji_chooser.take() {
                 emoji_chooser.unparent();
@@ -264,6 +406,7 @@ impl Default for ChatActionBar {
     }
 }
 
+#[gtk::template_callbacks]
 impl ChatActionBar {
     pub(crate) fn new() -> Self {
         glib::Object::new()
@@ -285,6 +428,12 @@ impl ChatActionBar {
                 obj.update_stack_page();
             }),
         );
+        chat_signal_group.connect_notify_local(
+            Some("permissions"),
+            clone!(@weak self as obj => move |_, _| {
+                obj.update_attach_menu();
+            }),
+        );
         imp.chat_signal_group.set(chat_signal_group).unwrap();
 
         let basic_group_signal_group = glib::SignalGroup::new::<model::BasicGroup>();
@@ -292,6 +441,7 @@ impl ChatActionBar {
             Some("status"),
             clone!(@weak self as obj => move |_, _| {
                 obj.update_stack_page();
+                obj.update_attach_menu();
             }),
         );
         imp.basic_group_signal_group
@@ -303,6 +453,7 @@ impl ChatActionBar {
             Some("status"),
             clone!(@weak self as obj => move |_, _| {
                 obj.update_stack_page();
+                obj.update_attach_menu();
             }),
         );
         imp.supergroup_signal_group
@@ -317,7 +468,11 @@ impl ChatActionBar {
             // If we were editing, go back to the previous state by loading the
             // draft message if we have one, otherwise just reset everything
             // and go back to the "Composing" state.
-            if let Some(draft_message) = self.chat().and_then(|c| c.draft_message()) {
+            if let Some(draft_message) = self
+                .chat()
+                .and_then(|c| c.draft_message())
+                .filter(|_| self.message_thread_id() == 0)
+            {
                 self.load_draft_message(draft_message);
             } else {
                 self.reset();
@@ -408,33 +563,337 @@ impl ChatActionBar {
         use ChatActionBarState::*;
         let imp = self.imp();
 
-        match imp.state.get() {
+        let is_editing = match imp.state.get() {
             Editing(_) => {
                 imp.send_message_button.set_icon_name("done-symbolic");
+                true
             }
             _ => {
                 imp.send_message_button.set_icon_name("go-up-symbolic");
+                false
+            }
+        };
+
+        // Show the record button instead of the send button when there's nothing to send
+        let has_text = imp
+            .message_entry
+            .formatted_text()
+            .map(|f| f.0.text.contains(|c: char| !c.is_whitespace()))
+            .unwrap_or_default();
+        let show_send_button = is_editing || has_text;
+
+        imp.send_message_button.set_visible(show_send_button);
+        imp.record_button.set_visible(!show_send_button);
+        imp.media_button.set_visible(!is_editing);
+
+        // Messages can't be scheduled in secret chats, and they can be sent when the other user
+        // comes online only in private chats
+        let chat = self.chat();
+        let can_schedule = chat
+            .as_ref()
+            .map(|chat| !matches!(chat.chat_type(), model::ChatType::Secret(_)))
+            .unwrap_or_default();
+        let can_send_when_online = chat
+            .map(|chat| match chat.chat_type() {
+                model::ChatType::Private(user) => {
+                    !chat.is_own_chat()
+                        && !matches!(user.user_type().0, tdlib::enums::UserType::Bot(_))
+                }
+                _ => false,
+            })
+            .unwrap_or_default();
+
+        let can_send = !is_editing && has_text;
+        self.action_set_enabled("chat-action-bar.send-message-silently", can_send);
+        self.action_set_enabled("chat-action-bar.schedule-message", can_send && can_schedule);
+        self.action_set_enabled(
+            "chat-action-bar.send-message-when-online",
+            can_send && can_send_when_online,
+        );
+    }
+
+    fn update_attach_menu(&self) {
+        let can_send_polls = self
+            .chat()
+            .map(|chat| chat.can_send_polls())
+            .unwrap_or_default();
+        self.action_set_enabled("chat-action-bar.create-poll", can_send_polls);
+    }
+
+    #[template_callback]
+    fn on_send_message_button_secondary_pressed(&self) {
+        let imp = self.imp();
+        if !matches!(imp.state.get(), ChatActionBarState::Editing(_)) {
+            imp.send_options_menu.popup();
+        }
+    }
+
+    #[template_callback]
+    fn on_record_button_drag_begin(&self) {
+        let imp = self.imp();
+        if imp.voice_recorder.borrow().is_some() {
+            return;
+        }
+
+        let voice_recorder = match VoiceRecorder::start() {
+            Ok(voice_recorder) => voice_recorder,
+            Err(e) => {
+                log::warn!("Failed to start recording a voice note: {e:?}");
+                utils::show_toast(self, gettext("Failed to start recording"));
+                return;
             }
+        };
+        imp.voice_recorder.replace(Some(voice_recorder));
+
+        imp.message_entry.set_visible(false);
+        imp.attach_button.set_sensitive(false);
+        imp.media_button.set_sensitive(false);
+        imp.recording_box.set_visible(true);
+        imp.record_button.add_css_class("recording");
+
+        self.update_recording_state();
+        let source_id = glib::timeout_add_local(
+            Duration::from_millis(200),
+            clone!(@weak self as obj => @default-return glib::ControlFlow::Break, move || {
+                obj.update_recording_state();
+                glib::ControlFlow::Continue
+            }),
+        );
+        imp.recording_source_id.replace(Some(source_id));
+    }
+
+    #[template_callback]
+    fn on_record_button_drag_update(&self, offset_x: f64, offset_y: f64) {
+        // The pointer was dragged away from the button while recording
+        if offset_x.hypot(offset_y) > RECORD_CANCEL_DISTANCE {
+            self.cancel_recording();
         }
     }
 
-    fn load_message_to_edit(&self, id: MessageId) {
+    #[template_callback]
+    fn on_record_button_drag_end(&self) {
+        if let Some(voice_recorder) = self.stop_recording() {
+            if voice_recorder.duration() < MIN_VOICE_NOTE_DURATION {
+                voice_recorder.cancel();
+                self.cancel_chat_action();
+                utils::show_toast(self, gettext("Hold to record a voice message"));
+                return;
+            }
+
+            utils::spawn(clone!(@weak self as obj => async move {
+                match voice_recorder.finish().await {
+                    Ok(voice_note) => obj.send_voice_note(voice_note).await,
+                    Err(e) => {
+                        log::warn!("Failed to record a voice note: {e:?}");
+                        utils::show_toast(&obj, gettext("Failed to record the voice message"));
+                        obj.cancel_chat_action();
+                    }
+                }
+            }));
+        }
+    }
+
+    #[template_callback]
+    fn on_record_button_cancel(&self) {
+        self.cancel_recording();
+    }
+
+    fn cancel_recording(&self) {
+        if let Some(voice_recorder) = self.stop_recording() {
+            voice_recorder.cancel();
+            self.cancel_chat_action();
+        }
+    }
+
+    /// Restores the message entry and returns the recorder, if recording.
+    fn stop_recording(&self) -> Option<VoiceRecorder> {
+        let imp = self.imp();
+
+        let voice_recorder = imp.voice_recorder.take()?;
+
+        if let Some(source_id) = imp.recording_source_id.take() {
+            source_id.remove();
+        }
+
+        imp.recording_box.set_visible(false);
+        imp.message_entry.set_visible(true);
+        imp.attach_button.set_sensitive(true);
+        imp.media_button.set_sensitive(true);
+        imp.record_button.remove_css_class("recording");
+
+        Some(voice_recorder)
+    }
+
+    fn update_recording_state(&self) {
+        let imp = self.imp();
+
+        if let Some(voice_recorder) = &*imp.voice_recorder.borrow() {
+            imp.recording_duration_label
+                .set_label(&utils::format_duration(voice_recorder.duration().as_secs()));
+        }
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            obj.send_chat_action(tdlib::enums::ChatAction::RecordingVoiceNote).await;
+        }));
+    }
+
+    async fn send_voice_note(&self, voice_note: RecordedVoiceNote) {
+        if let Some(chat) = self.chat() {
+            let client_id = chat.session_().client_().id();
+            let chat_id = chat.id();
+            let reply_to = Some(tdlib::enums::MessageReplyTo::Message(
+                tdlib::types::MessageReplyToMessage {
+                    chat_id,
+                    message_id: self.imp().state.get().replying(),
+                },
+            ));
+
+            let content = tdlib::enums::InputMessageContent::InputMessageVoiceNote(
+                tdlib::types::InputMessageVoiceNote {
+                    voice_note: tdlib::enums::InputFile::Local(tdlib::types::InputFileLocal {
+                        path: voice_note.path,
+                    }),
+                    duration: voice_note.duration,
+                    waveform: voice_note.waveform,
+                    caption: None,
+                },
+            );
+
+            let result = tdlib::functions::send_message(
+                chat_id,
+                self.message_thread_id(),
+                reply_to,
+                None,
+                content,
+                client_id,
+            )
+            .await;
+            if let Err(e) = result {
+                log::warn!("Error sending a voice note: {:?}", e);
+                utils::show_toast(self, gettext("Failed to send the voice message"));
+            } else if let ChatActionBarState::Replying(_) = self.imp().state.get() {
+                self.set_state(ChatActionBarState::Composing);
+            }
+        }
+    }
+
+    async fn send_sticker(&self, sticker: tdlib::types::Sticker) {
+        if let Some(chat) = self.chat() {
+            let client_id = chat.session_().client_().id();
+            let chat_id = chat.id();
+            let reply_to = Some(tdlib::enums::MessageReplyTo::Message(
+                tdlib::types::MessageReplyToMessage {
+                    chat_id,
+                    message_id: self.imp().state.get().replying(),
+                },
+            ));
+
+            let content = tdlib::enums::InputMessageContent::InputMessageSticker(
+                tdlib::types::InputMessageSticker {
+                    sticker: tdlib::enums::InputFile::Remote(tdlib::types::InputFileRemote {
+                        id: sticker.sticker.remote.id,
+                    }),
+                    thumbnail: None,
+                    width: sticker.width,
+                    height: sticker.height,
+                    emoji: sticker.emoji,
+                },
+            );
+
+            let result = tdlib::functions::send_message(
+                chat_id,
+                self.message_thread_id(),
+                reply_to,
+                None,
+                content,
+                client_id,
+            )
+            .await;
+            if let Err(e) = result {
+                log::warn!("Error sending a sticker: {:?}", e);
+                utils::show_toast(self, gettext("Failed to send the sticker"));
+            } else {
+                // The sticker is now among the recent ones
+                self.imp().sticker_picker.invalidate();
+
+                if let ChatActionBarState::Replying(_) = self.imp().state.get() {
+                    self.set_state(ChatActionBarState::Composing);
+                }
+            }
+        }
+    }
+
+    async fn send_animation(&self, animation: tdlib::types::Animation) {
         if let Some(chat) = self.chat() {
             let client_id = chat.session_().client_().id();
+            let chat_id = chat.id();
+            let reply_to = Some(tdlib::enums::MessageReplyTo::Message(
+                tdlib::types::MessageReplyToMessage {
+                    chat_id,
+                    message_id: self.imp().state.get().replying(),
+                },
+            ));
+
+            let content = tdlib::enums::InputMessageContent::InputMessageAnimation(
+                tdlib::types::InputMessageAnimation {
+                    animation: tdlib::enums::InputFile::Remote(tdlib::types::InputFileRemote {
+                        id: animation.animation.remote.id,
+                    }),
+                    thumbnail: None,
+                    added_sticker_file_ids: Vec::new(),
+                    duration: animation.duration,
+                    width: animation.width,
+                    height: animation.height,
+                    caption: None,
+                    has_spoiler: false,
+                },
+            );
+
+            let result = tdlib::functions::send_message(
+                chat_id,
+                self.message_thread_id(),
+                reply_to,
+                None,
+                content,
+                client_id,
+            )
+            .await;
+            if let Err(e) = result {
+                log::warn!("Error sending an animation: {:?}", e);
+                utils::show_toast(self, gettext("Failed to send the GIF"));
+            } else if let ChatActionBarState::Replying(_) = self.imp().state.get() {
+                self.set_state(ChatActionBarState::Composing);
+            }
+        }
+    }
+
+    fn cancel_chat_action(&self) {
+        if let Some(chat) = self.chat() {
+            let client_id = chat.session_().client_().id();
+            let chat_id = chat.id();
+            let message_thread_id = self.message_thread_id();
+
+            utils::spawn(async move {
+                let result = tdlib::functions::send_chat_action(
+                    chat_id,
+                    message_thread_id,
+                    Some(tdlib::enums::ChatAction::Cancel),
+                    client_id,
+                )
+                .await;
+                if let Err(e) = result {
+                    log::warn!("Error cancelling a chat action: {:?}", e);
+                }
+            });
+        }
+    }
 
+    fn load_message_to_edit(&self, id: MessageId) {
+        if let Some(chat) = self.chat() {
             if let Some(message) = chat.message(id) {
                 match message.content().0 {
                     tdlib::enums::MessageContent::MessageText(data) => {
-                        utils::block_on(async move {
-                            let tdlib::enums::FormattedText::FormattedText(markdown_text) =
-                                tdlib::functions::get_markdown_text(data.text, client_id)
-                                    .await
-                                    .unwrap();
-
-                            self.imp()
-                                .message_entry
-                                .set_formatted_text(Some(model::BoxedFormattedText(markdown_text)));
-                        });
+                        self.imp().message_entry.set_sent_text(data.text);
                     }
                     _ => unimplemented!(),
                 }
@@ -445,13 +904,14 @@ impl ChatActionBar {
     fn reset(&self) {
         self.set_state(ChatActionBarState::Composing);
         self.imp().message_entry.set_formatted_text(None);
+        self.imp().dismissed_link_preview_url.replace(None);
     }
 
     async fn compose_text_message(&self) -> Option<tdlib::enums::InputMessageContent> {
         if let Some(formatted_text) = self.imp().message_entry.as_markdown().await {
             let content = tdlib::types::InputMessageText {
                 text: formatted_text,
-                disable_web_page_preview: false,
+                disable_web_page_preview: self.is_link_preview_dismissed(),
                 clear_draft: true,
             };
 
@@ -461,6 +921,202 @@ impl ChatActionBar {
         }
     }
 
+    /// Suggests the stickers matching the emoji the message consists of, if enabled.
+    fn update_sticker_suggestions(&self) {
+        let imp = self.imp();
+
+        let generation = imp.sticker_suggestions_generation.get().wrapping_add(1);
+        imp.sticker_suggestions_generation.set(generation);
+
+        let emoji = imp
+            .message_entry
+            .formatted_text()
+            .map(|f| f.0.text.trim().to_owned())
+            .filter(|text| utils::emoji_count(text) == Some(1));
+        // Like the link previews, nothing typed in secret chats is looked up
+        let chat = self.chat().filter(|chat| {
+            imp.settings.boolean("suggest-stickers-by-emoji")
+                && !matches!(chat.chat_type(), model::ChatType::Secret(_))
+        });
+        let (Some(chat), Some(emoji)) = (chat, emoji) else {
+            self.set_sticker_suggestions(Vec::new(), None);
+            return;
+        };
+        let session = chat.session_();
+        let client_id = session.client_().id();
+        let chat_id = chat.id();
+
+        utils::spawn(clone!(@weak self as obj, @weak session => async move {
+            let result = tdlib::functions::get_stickers(
+                tdlib::enums::StickerType::Regular,
+                emoji,
+                MAX_STICKER_SUGGESTIONS,
+                chat_id,
+                client_id,
+            )
+            .await;
+
+            if obj.imp().sticker_suggestions_generation.get() != generation {
+                return;
+            }
+
+            let stickers = match result {
+                Ok(tdlib::enums::Stickers::Stickers(data)) => data.stickers,
+                Err(e) => {
+                    log::warn!("Failed to load the stickers of an emoji: {e:?}");
+                    Vec::new()
+                }
+            };
+            obj.set_sticker_suggestions(stickers, Some(&session));
+        }));
+    }
+
+    fn set_sticker_suggestions(
+        &self,
+        stickers: Vec<tdlib::types::Sticker>,
+        session: Option<&model::ClientStateSession>,
+    ) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.sticker_suggestions_box.first_child() {
+            imp.sticker_suggestions_box.remove(&child);
+        }
+        imp.sticker_suggestions_revealer
+            .set_reveal_child(!stickers.is_empty());
+
+        let Some(session) = session else {
+            return;
+        };
+
+        for sticker in stickers {
+            let sticker_widget: ui::Sticker = glib::Object::builder()
+                .property("longer-side-size", STICKER_SUGGESTION_SIZE)
+                .build();
+            sticker_widget.update_sticker(sticker.clone(), false, session.clone());
+
+            let button = gtk::Button::builder()
+                .child(&sticker_widget)
+                .tooltip_text(&sticker.emoji)
+                .build();
+            button.add_css_class("flat");
+            button.add_css_class("sticker");
+            button.connect_clicked(clone!(@weak self as obj => move |_| {
+                // The sticker is sent in place of the emoji
+                obj.imp().message_entry.set_formatted_text(None);
+
+                let sticker = sticker.clone();
+                utils::spawn(clone!(@weak obj => async move {
+                    obj.send_sticker(sticker).await;
+                }));
+            }));
+
+            imp.sticker_suggestions_box.append(&button);
+        }
+    }
+
+    /// Updates the link preview once the message hasn't changed for a while.
+    fn queue_link_preview_update(&self) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.link_preview_source_id.take() {
+            source_id.remove();
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            LINK_PREVIEW_DELAY,
+            clone!(@weak self as obj => move || {
+                obj.imp().link_preview_source_id.take();
+                obj.update_link_preview();
+            }),
+        );
+        imp.link_preview_source_id.replace(Some(source_id));
+    }
+
+    fn update_link_preview(&self) {
+        let imp = self.imp();
+
+        let generation = imp.link_preview_generation.get().wrapping_add(1);
+        imp.link_preview_generation.set(generation);
+
+        let text = imp
+            .message_entry
+            .formatted_text()
+            .map(|f| f.0)
+            .filter(|text| text.text.contains(|c: char| !c.is_whitespace()));
+        // Drafts of secret chats must not be sent to the server
+        let chat = self
+            .chat()
+            .filter(|chat| !matches!(chat.chat_type(), model::ChatType::Secret(_)));
+        let (Some(chat), Some(text)) = (chat, text) else {
+            self.set_link_preview(None);
+            return;
+        };
+        let client_id = chat.session_().client_().id();
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            let web_page = match tdlib::functions::get_web_page_preview(text, client_id).await {
+                Ok(tdlib::enums::WebPage::WebPage(data)) => Some(data),
+                Err(e) => {
+                    // The message has no link to preview
+                    if e.code != 404 {
+                        log::warn!("Failed to load a link preview: {e:?}");
+                    }
+                    None
+                }
+            };
+
+            if obj.imp().link_preview_generation.get() == generation {
+                obj.set_link_preview(web_page);
+            }
+        }));
+    }
+
+    fn set_link_preview(&self, web_page: Option<tdlib::types::WebPage>) {
+        let imp = self.imp();
+
+        match web_page {
+            Some(web_page) => {
+                let title = [&web_page.title, &web_page.site_name, &web_page.display_url]
+                    .into_iter()
+                    .find(|s| !s.is_empty())
+                    .cloned()
+                    .unwrap_or_default();
+                let description = [&web_page.description.text, &web_page.display_url]
+                    .into_iter()
+                    .find(|s| !s.is_empty())
+                    .cloned()
+                    .unwrap_or_default();
+
+                imp.link_preview_title_label.set_text(Some(&title));
+                imp.link_preview_description_label
+                    .set_text(Some(&description));
+                imp.link_preview_url.replace(Some(web_page.url));
+            }
+            None => {
+                imp.link_preview_url.replace(None);
+            }
+        }
+
+        let show_preview =
+            imp.link_preview_url.borrow().is_some() && !self.is_link_preview_dismissed();
+        imp.link_preview_revealer.set_reveal_child(show_preview);
+    }
+
+    fn dismiss_link_preview(&self) {
+        let imp = self.imp();
+
+        let url = imp.link_preview_url.borrow().clone();
+        imp.dismissed_link_preview_url.replace(url);
+        imp.link_preview_revealer.set_reveal_child(false);
+    }
+
+    /// Whether the preview of the link of the message has been dismissed, to send it without it.
+    fn is_link_preview_dismissed(&self) -> bool {
+        let imp = self.imp();
+        let url = imp.link_preview_url.borrow();
+        url.is_some() && *url == *imp.dismissed_link_preview_url.borrow()
+    }
+
     fn show_emoji_chooser(&self, parent: &impl IsA<gtk::Widget>) {
         let imp = self.imp();
         let mut emoji_chooser = imp.emoji_chooser.borrow_mut();
@@ -496,11 +1152,32 @@ impl ChatActionBar {
             let path = file.path().unwrap().to_str().unwrap().to_string();
             let chat = self.chat().unwrap();
 
-            ui::SendMediaWindow::new(&parent, &chat, path, self.imp().state.get().replying())
-                .present();
+            ui::SendMediaWindow::new(
+                &parent,
+                &chat,
+                path,
+                self.message_thread_id(),
+                self.imp().state.get().replying(),
+            )
+            .present();
         }
     }
 
+    fn create_poll(&self) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let parent = self.root().and_downcast::<gtk::Window>().unwrap();
+
+        ui::SendPollWindow::new(
+            &parent,
+            &chat,
+            self.message_thread_id(),
+            self.imp().state.get().replying(),
+        )
+        .present();
+    }
+
     async fn edit_message(&self) {
         if let Some(chat) = self.chat() {
             if let ChatActionBarState::Editing(message_id) = self.imp().state.get() {
@@ -522,7 +1199,38 @@ impl ChatActionBar {
         }
     }
 
-    async fn send_text_message(&self) {
+    async fn schedule_text_message(&self) {
+        let Some(parent) = self.root().and_downcast::<gtk::Window>() else {
+            return;
+        };
+
+        // Suggest sending the message in an hour
+        let date_time = glib::DateTime::now_local()
+            .and_then(|now| now.add_hours(1))
+            .unwrap();
+
+        let date_time = ui::DateTimePicker::choose(
+            &parent,
+            &gettext("Schedule Message"),
+            &gettext("_Schedule"),
+            &date_time,
+        )
+        .await;
+
+        if let Some(date_time) = date_time {
+            let options = tdlib::types::MessageSendOptions {
+                scheduling_state: Some(tdlib::enums::MessageSchedulingState::SendAtDate(
+                    tdlib::types::MessageSchedulingStateSendAtDate {
+                        send_date: date_time.to_unix() as i32,
+                    },
+                )),
+                ..Default::default()
+            };
+            self.send_text_message(options).await;
+        }
+    }
+
+    async fn send_text_message(&self, options: tdlib::types::MessageSendOptions) {
         if let Some(chat) = self.chat() {
             if let Some(message) = self.compose_text_message().await {
                 let client_id = chat.session_().client_().id();
@@ -538,12 +1246,22 @@ impl ChatActionBar {
                     },
                 ));
 
+                let is_scheduled = options.scheduling_state.is_some();
+
                 // Send the message
-                let result =
-                    tdlib::functions::send_message(chat_id, 0, reply_to, None, message, client_id)
-                        .await;
+                let result = tdlib::functions::send_message(
+                    chat_id,
+                    self.message_thread_id(),
+                    reply_to,
+                    Some(options),
+                    message,
+                    client_id,
+                )
+                .await;
                 if let Err(e) = result {
                     log::warn!("Error sending a message: {:?}", e);
+                } else if is_scheduled {
+                    utils::show_toast(self, gettext("Message scheduled"));
                 }
 
                 self.reset();
@@ -612,9 +1330,13 @@ impl ChatActionBar {
                     });
 
             // Save draft message
-            let result =
-                tdlib::functions::set_chat_draft_message(chat_id, 0, draft_message, client_id)
-                    .await;
+            let result = tdlib::functions::set_chat_draft_message(
+                chat_id,
+                self.message_thread_id(),
+                draft_message,
+                client_id,
+            )
+            .await;
             if let Err(e) = result {
                 log::warn!("Error setting a draft message: {:?}", e);
             }
@@ -658,8 +1380,13 @@ impl ChatActionBar {
             imp.chat_action_in_cooldown.set(true);
 
             // Send typing action
-            let result =
-                tdlib::functions::send_chat_action(chat_id, 0, Some(action), client_id).await;
+            let result = tdlib::functions::send_chat_action(
+                chat_id,
+                self.message_thread_id(),
+                Some(action),
+                client_id,
+            )
+            .await;
             if result.is_ok() {
                 glib::timeout_add_seconds_local_once(
                     5,
@@ -703,8 +1430,14 @@ impl ChatActionBar {
 
             let parent = self.root().and_downcast().unwrap();
             let path = path.to_str().unwrap().to_string();
-            ui::SendMediaWindow::new(&parent, &chat, path, self.imp().state.get().replying())
-                .present();
+            ui::SendMediaWindow::new(
+                &parent,
+                &chat,
+                path,
+                self.message_thread_id(),
+                self.imp().state.get().replying(),
+            )
+            .present();
         }
 
         Ok(())
@@ -729,8 +1462,14 @@ impl ChatActionBar {
             binding.unwatch();
         }
 
+        imp.dismissed_link_preview_url.replace(None);
+
         if let Some(chat) = chat {
-            if let Some(draft_message) = chat.draft_message() {
+            // The draft of the chat doesn't belong to its message threads
+            if let Some(draft_message) = chat
+                .draft_message()
+                .filter(|_| self.message_thread_id() == 0)
+            {
                 self.load_draft_message(draft_message);
             } else {
                 self.reset();
@@ -751,11 +1490,22 @@ impl ChatActionBar {
         imp.chat.set(chat);
 
         self.update_stack_page();
+        self.update_send_button();
+        self.update_attach_menu();
         self.update_signal_groups();
 
         self.notify("chat");
     }
 
+    pub(crate) fn message_thread_id(&self) -> i64 {
+        self.imp().message_thread_id.get()
+    }
+
+    /// Sets the message thread the messages are sent to. It must be set before the chat.
+    pub(crate) fn set_message_thread_id(&self, message_thread_id: i64) {
+        self.imp().message_thread_id.set(message_thread_id);
+    }
+
     pub(crate) fn reply_to_message_id(&self, id: MessageId) {
         self.set_state(ChatActionBarState::Replying(id));
     }
@@ -764,6 +1514,10 @@ impl ChatActionBar {
         self.set_state(ChatActionBarState::Editing(id));
     }
 
+    pub(crate) fn invalidate_saved_animations(&self) {
+        self.imp().animation_picker.invalidate();
+    }
+
     fn update_stack_page(&self) {
         if let Some(chat) = self.chat() {
             let imp = self.imp();
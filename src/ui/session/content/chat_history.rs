@@ -2,22 +2,31 @@ use std::cell::Cell;
 use std::cell::OnceCell;
 use std::cell::RefCell;
 use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use futures::Future;
 use gettextrs::gettext;
 use glib::clone;
+use gtk::gdk;
 use gtk::gio;
 use gtk::glib;
 use gtk::CompositeTemplate;
 
 use crate::expressions;
+use crate::i18n::ngettext_f;
 use crate::model;
+use crate::strings;
 use crate::ui;
 use crate::utils;
 
+/// The number of messages loaded around a message when jumping to it.
+const JUMP_MESSAGES_LIMIT: i32 = 20;
+
 mod imp {
     use super::*;
 
@@ -26,16 +35,34 @@ mod imp {
     pub(crate) struct ChatHistory {
         pub(super) chat: glib::WeakRef<model::Chat>,
         pub(super) chat_handler: RefCell<Option<glib::SignalHandlerId>>,
+        /// The id of the shown message thread, or 0 for the whole chat history
+        pub(super) message_thread_id: Cell<i64>,
+        pub(super) title_watch: RefCell<Option<gtk::ExpressionWatch>>,
         pub(super) model: RefCell<Option<model::ChatHistoryModel>>,
+        pub(super) selection: RefCell<Option<gtk::MultiSelection>>,
+        pub(super) selection_mode: Cell<bool>,
         pub(super) message_menu: OnceCell<gtk::PopoverMenu>,
+        pub(super) message_reaction_picker: OnceCell<ui::MessageReactionPicker>,
         pub(super) is_auto_scrolling: Cell<bool>,
         pub(super) is_loading_messages: Cell<bool>,
         pub(super) sticky: Cell<bool>,
+        /// The message to scroll to once the initial messages have been loaded
+        pub(super) pending_scroll_message_id: Cell<Option<i64>>,
         pub(super) viewed_message_ids: RefCell<HashSet<i64>>,
         pub(super) viewed_message_ids_changed: Cell<bool>,
         #[template_child]
+        pub(super) header_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) thread_back_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) thread_back_button_content: TemplateChild<adw::ButtonContent>,
+        #[template_child]
         pub(super) window_title: TemplateChild<adw::WindowTitle>,
         #[template_child]
+        pub(super) selection_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub(super) search_bar: TemplateChild<ui::ChatSearchBar>,
+        #[template_child]
         pub(super) background: TemplateChild<ui::Background>,
         #[template_child]
         pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
@@ -57,9 +84,24 @@ mod imp {
             klass.install_action("chat-history.view-info", None, move |widget, _, _| {
                 widget.open_info_dialog();
             });
+            klass.install_action(
+                "chat-history.view-scheduled-messages",
+                None,
+                move |widget, _, _| {
+                    widget.open_scheduled_messages_window();
+                },
+            );
             klass.install_action("chat-history.scroll-down", None, move |widget, _, _| {
                 widget.scroll_down();
             });
+            klass.add_binding_action(
+                gdk::Key::f,
+                gdk::ModifierType::CONTROL_MASK,
+                "chat-history.search",
+            );
+            klass.install_action("chat-history.search", None, move |widget, _, _| {
+                widget.imp().search_bar.begin_search();
+            });
             klass.install_action(
                 "chat-history.reply",
                 Some(glib::VariantTy::INT64),
@@ -68,6 +110,14 @@ mod imp {
                     widget.imp().chat_action_bar.reply_to_message_id(message_id);
                 },
             );
+            klass.install_action(
+                "chat-history.scroll-to-message",
+                Some(glib::VariantTy::INT64),
+                move |widget, _, variant| {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.scroll_to_message(message_id);
+                },
+            );
             klass.install_action(
                 "chat-history.edit",
                 Some(glib::VariantTy::INT64),
@@ -76,6 +126,85 @@ mod imp {
                     widget.imp().chat_action_bar.edit_message_id(message_id);
                 },
             );
+            klass.add_binding_action(
+                gdk::Key::Escape,
+                gdk::ModifierType::empty(),
+                "chat-history.cancel-selection",
+            );
+            klass.install_action(
+                "chat-history.cancel-selection",
+                None,
+                move |widget, _, _| {
+                    widget.set_selection_mode(false);
+                },
+            );
+            klass.install_action(
+                "chat-history.delete-selection",
+                None,
+                move |widget, _, _| {
+                    widget.show_delete_selection_dialog();
+                },
+            );
+            klass.install_action(
+                "chat-history.forward-selection",
+                None,
+                move |widget, _, _| {
+                    widget.forward_selection();
+                },
+            );
+            klass.install_action("chat-history.copy-selection", None, move |widget, _, _| {
+                widget.copy_selection();
+            });
+            klass.install_action_async(
+                "chat-history.save-selection",
+                None,
+                |widget, _, _| async move {
+                    widget.save_selection().await;
+                },
+            );
+            klass.install_action_async(
+                "chat-history.open-user",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let user_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.open_user(user_id).await;
+                },
+            );
+            klass.install_action_async(
+                "chat-history.open-username",
+                Some(glib::VariantTy::STRING),
+                |widget, _, variant| async move {
+                    let username = variant.and_then(|v| v.get()).unwrap();
+                    widget.open_username(username).await;
+                },
+            );
+            klass.install_action(
+                "chat-history.search-text",
+                Some(glib::VariantTy::STRING),
+                move |widget, _, variant| {
+                    let query = variant.and_then(|v| v.get::<String>()).unwrap();
+                    widget.imp().search_bar.search_text(&query);
+                },
+            );
+            klass.install_action_async(
+                "chat-history.send-bot-command",
+                Some(glib::VariantTy::STRING),
+                |widget, _, variant| async move {
+                    let command = variant.and_then(|v| v.get()).unwrap();
+                    widget.send_bot_command(command).await;
+                },
+            );
+            klass.install_action_async(
+                "chat-history.open-thread",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.open_thread(message_id).await;
+                },
+            );
+            klass.install_action("chat-history.close-thread", None, move |widget, _, _| {
+                utils::ancestor::<_, ui::Content>(widget).close_thread();
+            });
             klass.install_action_async(
                 "chat-history.leave-chat",
                 None,
@@ -101,6 +230,9 @@ mod imp {
                     glib::ParamSpecBoolean::builder("sticky")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("selection-mode")
+                        .read_only()
+                        .build(),
                 ]
             })
         }
@@ -124,6 +256,7 @@ mod imp {
             match pspec.name() {
                 "chat" => obj.chat().to_value(),
                 "sticky" => obj.sticky().to_value(),
+                "selection-mode" => obj.selection_mode().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -134,6 +267,7 @@ mod imp {
             let obj = self.obj();
 
             obj.setup_expressions();
+            obj.action_set_enabled("chat-history.cancel-selection", false);
 
             let adj = self.list_view.vadjustment().unwrap();
             adj.connect_value_changed(clone!(@weak obj => move |adj| {
@@ -151,25 +285,47 @@ mod imp {
                         obj.set_sticky(true);
                     }
                 } else {
-                    obj.set_sticky(adj.value() + adj.page_size() >= adj.upper());
-
-                    if adj.value() >= adj.page_size() * 2.0 && adj.upper() > adj.page_size() * 2.0 {
+                    let Some(model) = imp.model.borrow().clone() else {
                         return;
-                    }
+                    };
 
-                    if let Some(model) = imp.model.borrow().as_ref() {
+                    obj.set_sticky(
+                        !model.has_newer_messages() && adj.value() + adj.page_size() >= adj.upper(),
+                    );
+
+                    if model.has_newer_messages()
+                        && adj.upper() - adj.value() - adj.page_size() < adj.page_size() * 2.0
+                    {
                         imp.is_loading_messages.set(true);
 
                         utils::spawn(clone!(@weak obj, @weak model => async move {
                             obj.imp().is_loading_messages.set(false);
 
                             if let Err(model::ChatHistoryError::Tdlib(e)) =
-                                model.load_older_messages(2).await
+                                model.load_newer_messages(2).await
                             {
-                                log::warn!("Couldn't load more chat messages: {:?}", e);
+                                log::warn!("Couldn't load newer chat messages: {:?}", e);
                             }
                         }));
+
+                        return;
+                    }
+
+                    if adj.value() >= adj.page_size() * 2.0 && adj.upper() > adj.page_size() * 2.0 {
+                        return;
                     }
+
+                    imp.is_loading_messages.set(true);
+
+                    utils::spawn(clone!(@weak obj, @weak model => async move {
+                        obj.imp().is_loading_messages.set(false);
+
+                        if let Err(model::ChatHistoryError::Tdlib(e)) =
+                            model.load_older_messages(2).await
+                        {
+                            log::warn!("Couldn't load more chat messages: {:?}", e);
+                        }
+                    }));
                 }
             }));
 
@@ -181,7 +337,12 @@ mod imp {
         }
 
         fn dispose(&self) {
-            if let Some(chat) = self.obj().chat() {
+            // Message threads are opened and closed together with their chat
+            if let Some(chat) = self
+                .obj()
+                .chat()
+                .filter(|_| self.message_thread_id.get() == 0)
+            {
                 perform_chat_action(&chat, tdlib::functions::close_chat);
             }
         }
@@ -228,16 +389,132 @@ impl ChatHistory {
         let chat_expression = Self::this_expression("chat");
 
         // Chat title
-        expressions::chat_display_name(&chat_expression).bind(
+        let title_watch = expressions::chat_display_name(&chat_expression).bind(
             &*self.imp().window_title,
             "title",
             Some(self),
         );
+        self.imp().title_watch.replace(Some(title_watch));
     }
 
     fn open_info_dialog(&self) {
         if let Some(chat) = self.chat() {
-            ui::ChatInfoWindow::new(&self.parent_window(), &chat).present();
+            let window = ui::ChatInfoWindow::new(&self.parent_window(), &chat);
+            window.connect_message_activated(clone!(@weak self as obj => move |window, message| {
+                window.close();
+                obj.scroll_to_message(message.id());
+            }));
+            window.present();
+        }
+    }
+
+    async fn open_user(&self, user_id: i64) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let client_id = chat.session_().client_().id();
+
+        match tdlib::functions::create_private_chat(user_id, true, client_id).await {
+            Ok(tdlib::enums::Chat::Chat(data)) => self.open_chat_info(&chat.session_(), data.id),
+            Err(e) => {
+                log::warn!("Failed to open the chat of a user: {e:?}");
+                utils::show_toast(self, gettext("Failed to open the profile"));
+            }
+        }
+    }
+
+    async fn open_username(&self, username: String) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let client_id = chat.session_().client_().id();
+
+        match tdlib::functions::search_public_chat(username, client_id).await {
+            Ok(tdlib::enums::Chat::Chat(data)) => self.open_chat_info(&chat.session_(), data.id),
+            Err(e) => {
+                log::warn!("Failed to find a public chat: {e:?}");
+                utils::show_toast(self, gettext("No user or chat with this username"));
+            }
+        }
+    }
+
+    fn open_chat_info(&self, session: &model::ClientStateSession, chat_id: i64) {
+        let Some(chat) = session.try_chat(chat_id) else {
+            return;
+        };
+        ui::ChatInfoWindow::new(&self.parent_window(), &chat).present();
+    }
+
+    async fn send_bot_command(&self, command: String) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let client_id = chat.session_().client_().id();
+
+        let content =
+            tdlib::enums::InputMessageContent::InputMessageText(tdlib::types::InputMessageText {
+                text: tdlib::types::FormattedText {
+                    text: command,
+                    entities: Vec::new(),
+                },
+                disable_web_page_preview: true,
+                clear_draft: false,
+            });
+
+        let result = tdlib::functions::send_message(
+            chat.id(),
+            self.imp().message_thread_id.get(),
+            None,
+            None,
+            content,
+            client_id,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to send a bot command: {e:?}");
+            utils::show_toast(self, gettext("Failed to send the command"));
+        }
+    }
+
+    async fn open_thread(&self, message_id: i64) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let session = chat.session_();
+
+        match tdlib::functions::get_message_thread(chat.id(), message_id, session.client_().id())
+            .await
+        {
+            Ok(tdlib::enums::MessageThreadInfo::MessageThreadInfo(data)) => {
+                let is_channel = matches!(
+                    chat.chat_type(),
+                    model::ChatType::Supergroup(supergroup) if supergroup.is_channel()
+                );
+                let title = if is_channel {
+                    gettext("Comments")
+                } else {
+                    gettext("Replies")
+                };
+
+                // The comments of a channel post belong to its discussion group
+                let thread_chat = session.chat(data.chat_id);
+                utils::ancestor::<_, ui::Content>(self).open_thread(
+                    &thread_chat,
+                    data.message_thread_id,
+                    &chat,
+                    &title,
+                );
+            }
+            Err(e) => {
+                log::warn!("Failed to get a message thread: {e:?}");
+                utils::show_toast(self, gettext("Failed to open the thread"));
+            }
+        }
+    }
+
+    fn open_scheduled_messages_window(&self) {
+        if let Some(chat) = self.chat() {
+            ui::ScheduledMessagesWindow::new(self.parent_window().as_ref(), &chat).present();
         }
     }
 
@@ -320,28 +597,81 @@ impl ChatHistory {
         })
     }
 
+    pub(crate) fn message_reaction_picker(&self) -> &ui::MessageReactionPicker {
+        self.imp().message_reaction_picker.get_or_init(|| {
+            let picker = ui::MessageReactionPicker::default();
+            self.message_menu().add_child(&picker, "reactions");
+            picker
+        })
+    }
+
     pub(crate) fn handle_paste_action(&self) {
         self.imp().chat_action_bar.handle_paste_action();
     }
 
+    pub(crate) fn invalidate_saved_animations(&self) {
+        self.imp().chat_action_bar.invalidate_saved_animations();
+    }
+
     pub(crate) fn chat(&self) -> Option<model::Chat> {
         self.imp().chat.upgrade()
     }
 
     pub(crate) fn set_chat(&self, chat: Option<&model::Chat>) {
+        self.update_chat(chat, 0);
+    }
+
+    /// Shows the message thread with the given id instead of the whole chat history. The
+    /// `parent_chat` is the chat where the thread was opened from, which differs from `chat`
+    /// for the comments of channel posts.
+    pub(crate) fn set_message_thread(
+        &self,
+        chat: &model::Chat,
+        message_thread_id: i64,
+        parent_chat: &model::Chat,
+        title: &str,
+    ) {
+        let imp = self.imp();
+
+        if let Some(title_watch) = imp.title_watch.take() {
+            title_watch.unwatch();
+        }
+        imp.window_title.set_title(title);
+
+        imp.thread_back_button_content
+            .set_label(&parent_chat.title());
+        imp.thread_back_button.set_visible(true);
+
+        // Nested threads aren't supported
+        self.action_set_enabled("chat-history.open-thread", false);
+
+        self.update_chat(Some(chat), message_thread_id);
+    }
+
+    fn update_chat(&self, chat: Option<&model::Chat>, message_thread_id: i64) {
         let old_chat = self.chat();
-        if chat == old_chat.as_ref() {
+        let imp = self.imp();
+
+        if chat == old_chat.as_ref() && message_thread_id == imp.message_thread_id.get() {
             return;
         }
 
-        let imp = self.imp();
+        self.set_selection_mode(false);
 
         if let Some(chat) = old_chat {
             chat.disconnect(imp.chat_handler.replace(None).unwrap());
-            perform_chat_action(chat.as_ref(), tdlib::functions::close_chat);
+            if imp.message_thread_id.get() == 0 {
+                perform_chat_action(chat.as_ref(), tdlib::functions::close_chat);
+            }
         }
 
+        imp.message_thread_id.set(message_thread_id);
+
         if let Some(chat) = chat {
+            // The thread must be known before the chat action bar loads the draft of the chat.
+            // It's kept when unsetting the chat, so that the draft is saved to the right thread.
+            imp.chat_action_bar.set_message_thread_id(message_thread_id);
+
             self.action_set_enabled(
                 "chat-history.leave-chat",
                 match chat.chat_type() {
@@ -354,11 +684,17 @@ impl ChatHistory {
                     _ => false,
                 },
             );
+            // Messages can't be scheduled in secret chats
+            self.action_set_enabled(
+                "chat-history.view-scheduled-messages",
+                !matches!(chat.chat_type(), model::ChatType::Secret(_)),
+            );
 
-            let model = model::ChatHistoryModel::new(chat);
+            let model = model::ChatHistoryModel::with_message_thread(chat, message_thread_id);
 
             // Request sponsored message, if needed
-            let list_view_model: gio::ListModel = if matches!(chat.chat_type(), model::ChatType::Supergroup(supergroup) if supergroup.is_channel())
+            let list_view_model: gio::ListModel = if message_thread_id == 0
+                && matches!(chat.chat_type(), model::ChatType::Supergroup(supergroup) if supergroup.is_channel())
             {
                 let list = gio::ListStore::new::<gio::ListModel>();
 
@@ -375,6 +711,8 @@ impl ChatHistory {
                 model.clone().upcast()
             };
 
+            imp.pending_scroll_message_id.set(None);
+
             utils::spawn(clone!(@weak self as obj, @weak model => async move {
                 let imp = obj.imp();
 
@@ -401,7 +739,12 @@ impl ChatHistory {
                 scrollbar.set_visible(true);
 
                 imp.is_loading_messages.set(false);
-                obj.set_sticky(true);
+
+                if let Some(message_id) = imp.pending_scroll_message_id.take() {
+                    obj.scroll_to_message(message_id);
+                } else {
+                    obj.set_sticky(true);
+                }
 
                 obj.view_messages();
             }));
@@ -413,12 +756,19 @@ impl ChatHistory {
             }));
             imp.chat_handler.replace(Some(handler));
 
-            let selection = gtk::NoSelection::new(Some(list_view_model));
+            // Rows aren't selectable by clicking them, but only in selection mode
+            let selection = gtk::MultiSelection::new(Some(list_view_model));
+            selection.connect_selection_changed(clone!(@weak self as obj => move |_, _, _| {
+                obj.update_selection();
+            }));
             imp.list_view.set_model(Some(&selection));
+            imp.selection.replace(Some(selection));
 
             imp.model.replace(Some(model));
 
-            perform_chat_action(chat, tdlib::functions::open_chat);
+            if message_thread_id == 0 {
+                perform_chat_action(chat, tdlib::functions::open_chat);
+            }
         }
 
         imp.chat.set(chat);
@@ -441,12 +791,376 @@ impl ChatHistory {
     fn scroll_down(&self) {
         let imp = self.imp();
 
+        // Go back to the newest messages first, if we jumped to an older one before
+        let model = imp.model.borrow().clone();
+        if let Some(model) = model.filter(model::ChatHistoryModel::has_newer_messages) {
+            if let Some(last_message_id) = model.last_message_id() {
+                utils::spawn(clone!(@weak self as obj, @weak model => async move {
+                    match model.jump_to_message(last_message_id, JUMP_MESSAGES_LIMIT).await {
+                        Ok(_) => obj.scroll_down(),
+                        Err(model::ChatHistoryError::Tdlib(e)) => {
+                            log::warn!("Couldn't load the newest chat messages: {:?}", e)
+                        }
+                        Err(model::ChatHistoryError::AlreadyLoading) => {}
+                    }
+                }));
+
+                return;
+            }
+        }
+
         imp.is_auto_scrolling.set(true);
 
         imp.scrolled_window
             .emit_by_name::<bool>("scroll-child", &[&gtk::ScrollType::End, &false]);
     }
 
+    /// Scrolls to the message with the given id and highlights it, loading the chat history
+    /// around it if needed.
+    pub(crate) fn scroll_to_message(&self, message_id: i64) {
+        let Some(model) = self.imp().model.borrow().clone() else {
+            return;
+        };
+
+        utils::spawn(clone!(@weak self as obj, @weak model => async move {
+            let imp = obj.imp();
+
+            imp.is_loading_messages.set(true);
+            let result = model.jump_to_message(message_id, JUMP_MESSAGES_LIMIT).await;
+            imp.is_loading_messages.set(false);
+
+            match result {
+                Ok(Some(position)) => obj.scroll_to_position(&model, position, message_id),
+                Ok(None) => utils::show_toast(&obj, gettext("The message has been deleted")),
+                Err(model::ChatHistoryError::Tdlib(e)) => {
+                    log::warn!("Couldn't jump to message: {:?}", e);
+                    utils::show_toast(&obj, gettext("Couldn't load the message"));
+                }
+                Err(model::ChatHistoryError::AlreadyLoading) => {
+                    // Try again as soon as the initial messages are loaded
+                    imp.pending_scroll_message_id.set(Some(message_id));
+                }
+            }
+        }));
+    }
+
+    fn scroll_to_position(&self, model: &model::ChatHistoryModel, position: u32, message_id: i64) {
+        let imp = self.imp();
+
+        imp.is_auto_scrolling.set(false);
+        self.set_sticky(false);
+
+        let offset = self.history_offset(model);
+
+        let scroll_info = gtk::ScrollInfo::new();
+        scroll_info.set_center_vertical(true);
+
+        imp.list_view.scroll_to(
+            position + offset,
+            gtk::ListScrollFlags::NONE,
+            Some(scroll_info),
+        );
+
+        // Wait for the list view to allocate the rows before looking for the message row
+        glib::idle_add_local_once(clone!(@weak self as obj => move || {
+            obj.highlight_message(message_id);
+        }));
+    }
+
+    /// Returns the number of items that are placed before the history in the list view, like
+    /// the sponsored message of channels.
+    fn history_offset(&self, model: &model::ChatHistoryModel) -> u32 {
+        self.imp()
+            .list_view
+            .model()
+            .map(|list_view_model| list_view_model.n_items() - model.n_items())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn selection_mode(&self) -> bool {
+        self.imp().selection_mode.get()
+    }
+
+    fn set_selection_mode(&self, selection_mode: bool) {
+        if self.selection_mode() == selection_mode {
+            return;
+        }
+
+        let imp = self.imp();
+
+        imp.selection_mode.set(selection_mode);
+        imp.header_stack.set_visible_child_name(if selection_mode {
+            "selection"
+        } else {
+            "default"
+        });
+
+        if !selection_mode {
+            if let Some(selection) = imp.selection.borrow().as_ref() {
+                selection.unselect_all();
+            }
+        }
+
+        self.action_set_enabled("chat-history.cancel-selection", selection_mode);
+        self.notify("selection-mode");
+    }
+
+    /// Selects or unselects the message with the given id. The selection mode is entered
+    /// if needed.
+    pub(crate) fn toggle_message_selection(&self, message_id: i64) {
+        let imp = self.imp();
+
+        let Some(model) = imp.model.borrow().clone() else {
+            return;
+        };
+        let Some(selection) = imp.selection.borrow().clone() else {
+            return;
+        };
+        let Some(position) = model.message_position(message_id) else {
+            return;
+        };
+
+        let position = position + self.history_offset(&model);
+
+        self.set_selection_mode(true);
+
+        if selection.is_selected(position) {
+            selection.unselect_item(position);
+        } else {
+            selection.select_item(position, false);
+        }
+    }
+
+    /// Returns the selected messages, ordered from the oldest to the newest one.
+    fn selected_messages(&self) -> Vec<model::Message> {
+        let Some(selection) = self.imp().selection.borrow().clone() else {
+            return vec![];
+        };
+
+        let bitset = selection.selection();
+        let mut messages: Vec<model::Message> = (0..bitset.size())
+            .filter_map(|index| selection.item(bitset.nth(index as u32)))
+            .filter_map(|item| item.downcast::<model::ChatHistoryItem>().ok())
+            .filter_map(|item| item.message().cloned())
+            .collect();
+
+        messages.sort_by_key(|message| message.id());
+        messages
+    }
+
+    fn update_selection(&self) {
+        let messages = self.selected_messages();
+
+        if messages.is_empty() {
+            self.set_selection_mode(false);
+            return;
+        }
+
+        let n = messages.len() as u32;
+        self.imp().selection_title.set_title(&ngettext_f(
+            "{num} Selected",
+            "{num} Selected",
+            n,
+            &[("num", &n.to_string())],
+        ));
+
+        // The same conditions as the responses of the deletion dialog
+        let can_delete_for_self = messages
+            .iter()
+            .all(model::Message::can_be_deleted_only_for_self);
+        let can_revoke = messages
+            .iter()
+            .all(model::Message::can_be_deleted_for_all_users);
+        self.action_set_enabled(
+            "chat-history.delete-selection",
+            can_delete_for_self || can_revoke,
+        );
+        self.action_set_enabled(
+            "chat-history.forward-selection",
+            messages.iter().all(model::Message::can_be_forwarded),
+        );
+        self.action_set_enabled(
+            "chat-history.save-selection",
+            messages
+                .iter()
+                .any(|message| message_attachment(message).is_some()),
+        );
+    }
+
+    fn show_delete_selection_dialog(&self) {
+        let messages = self.selected_messages();
+        let Some(chat) = messages.first().map(model::Message::chat_) else {
+            return;
+        };
+
+        let can_delete_for_self = messages
+            .iter()
+            .all(model::Message::can_be_deleted_only_for_self);
+        let can_revoke = messages
+            .iter()
+            .all(model::Message::can_be_deleted_for_all_users);
+
+        let n = messages.len() as u32;
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Confirm Message Deletion"))
+            .body(ngettext_f(
+                "Do you want to delete this message?",
+                "Do you want to delete these {num} messages?",
+                n,
+                &[("num", &n.to_string())],
+            ))
+            .transient_for(&self.parent_window().unwrap())
+            .build();
+
+        dialog.add_response("cancel", &gettext("_Cancel"));
+        if can_delete_for_self {
+            dialog.add_response("delete", &gettext("_Delete for Me"));
+            dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+        }
+        if can_revoke {
+            dialog.add_response("revoke", &gettext("Delete for Ever_yone"));
+            dialog.set_response_appearance("revoke", adw::ResponseAppearance::Destructive);
+        }
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let message_ids: Vec<i64> = messages.iter().map(model::Message::id).collect();
+
+        dialog.choose(
+            gio::Cancellable::NONE,
+            clone!(@weak self as obj, @weak chat => move |response| {
+                let revoke = match response.as_str() {
+                    "delete" => false,
+                    "revoke" => true,
+                    _ => return,
+                };
+
+                obj.set_selection_mode(false);
+
+                utils::spawn(async move {
+                    if let Err(e) = chat.delete_messages(message_ids, revoke).await {
+                        log::warn!("Error deleting messages (revoke = {}): {:?}", revoke, e);
+                    }
+                });
+            }),
+        );
+    }
+
+    fn forward_selection(&self) {
+        let messages = self.selected_messages();
+        if let Some(chat) = messages.first().map(model::Message::chat_) {
+            let message_ids = messages.iter().map(model::Message::id).collect();
+            ui::ForwardWindow::new(self.parent_window().as_ref(), &chat, message_ids).present();
+
+            self.set_selection_mode(false);
+        }
+    }
+
+    fn copy_selection(&self) {
+        let text = self
+            .selected_messages()
+            .iter()
+            .map(|message| {
+                format!(
+                    "{}: {}",
+                    strings::message_sender(&message.sender(), true),
+                    strings::message_content(message)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.clipboard().set_text(&text);
+        utils::show_toast(self, gettext("Copied to clipboard"));
+
+        self.set_selection_mode(false);
+    }
+
+    async fn save_selection(&self) {
+        let attachments: Vec<_> = self
+            .selected_messages()
+            .iter()
+            .filter_map(message_attachment)
+            .collect();
+        let Some(session) = self.chat().map(|chat| chat.session_()) else {
+            return;
+        };
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Save Attachments"))
+            .modal(true)
+            .build();
+
+        let Ok(folder) = dialog
+            .select_folder_future(self.parent_window().as_ref())
+            .await
+        else {
+            return;
+        };
+        let Some(folder) = folder.path() else {
+            return;
+        };
+
+        self.set_selection_mode(false);
+
+        let mut failed = false;
+        for (file_id, file_name) in attachments {
+            let result = match session.download_file(file_id).await {
+                Ok(file) => {
+                    let folder = folder.clone();
+                    gio::spawn_blocking(move || {
+                        std::fs::copy(file.local.path, unique_file_path(&folder, &file_name))
+                    })
+                    .await
+                    .map_err(|_| "The copying thread panicked".to_string())
+                    .and_then(|result| result.map_err(|e| e.to_string()))
+                }
+                Err(e) => Err(e.message),
+            };
+
+            if let Err(e) = result {
+                log::warn!("Error saving an attachment: {}", e);
+                failed = true;
+            }
+        }
+
+        utils::show_toast(
+            self,
+            if failed {
+                gettext("Some attachments couldn't be saved")
+            } else {
+                gettext("Attachments saved")
+            },
+        );
+    }
+
+    fn highlight_message(&self, message_id: i64) {
+        let mut child = self.imp().list_view.first_child();
+        while let Some(row) = child {
+            child = row.next_sibling();
+
+            let is_message = row
+                .first_child()
+                .and_downcast::<ui::ChatHistoryRow>()
+                .and_then(|history_row| history_row.item())
+                .and_downcast::<model::ChatHistoryItem>()
+                .and_then(|item| item.message().map(|message| message.id() == message_id))
+                .unwrap_or_default();
+
+            if is_message {
+                row.add_css_class("highlighted");
+                glib::timeout_add_local_once(
+                    Duration::from_millis(1500),
+                    clone!(@weak row => move || {
+                        row.remove_css_class("highlighted");
+                    }),
+                );
+
+                break;
+            }
+        }
+    }
+
     pub(crate) fn view_messages(&self) {
         let imp = self.imp();
 
@@ -468,6 +1182,56 @@ impl ChatHistory {
     }
 }
 
+/// Returns the id and name of the file attached to the message, if any.
+fn message_attachment(message: &model::Message) -> Option<(i32, String)> {
+    use tdlib::enums::MessageContent::*;
+
+    let (file, file_name) = match message.content().0 {
+        MessageAnimation(data) => (data.animation.animation, data.animation.file_name),
+        MessageAudio(data) => (data.audio.audio, data.audio.file_name),
+        MessageDocument(data) => (data.document.document, data.document.file_name),
+        MessagePhoto(data) => (
+            data.photo.sizes.into_iter().last()?.photo,
+            format!("photo_{}.jpg", message.id()),
+        ),
+        MessageVideo(data) => (data.video.video, data.video.file_name),
+        MessageVoiceNote(data) => (data.voice_note.voice, format!("voice_{}.ogg", message.id())),
+        _ => return None,
+    };
+
+    // Names of other directories than the chosen one are not allowed
+    let file_name = Path::new(&file_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format!("file_{}", message.id()));
+
+    Some((file.id, file_name))
+}
+
+/// Returns the path of the file in the folder, numbered as in "name (1).ext" if a file of the
+/// same name exists already.
+fn unique_file_path(folder: &Path, file_name: &str) -> PathBuf {
+    let path = folder.join(file_name);
+    if !path.exists() {
+        return path;
+    }
+
+    let file_name = Path::new(file_name);
+    let stem = file_name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = file_name
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| folder.join(format!("{stem} ({n}){extension}")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
 fn perform_chat_action<F, Fut>(chat: &model::Chat, op: F)
 where
     F: Fn(i64, i32) -> Fut + 'static,
@@ -130,12 +130,17 @@ impl ChatHistoryRow {
                             | MessageChatJoinByLink
                             | MessageChatJoinByRequest
                             | MessageChatDeleteMember(_)
-                            | MessagePinMessage(_)
                             | MessageScreenshotTaken
                             | MessageGameScore(_)
                             | MessageContactRegistered => {
-                                self.get_or_create_event_row()
-                                    .set_label(&strings::message_content(message));
+                                let child = self.get_or_create_event_row();
+                                child.set_label(&strings::message_content(message));
+                                child.set_target_message_id(None);
+                            }
+                            MessagePinMessage(data) => {
+                                let child = self.get_or_create_event_row();
+                                child.set_label(&strings::message_content(message));
+                                child.set_target_message_id(Some(data.message_id));
                             }
                             _ => self.update_or_create_message_row(message.to_owned().upcast()),
                         }
@@ -152,6 +157,7 @@ impl ChatHistoryRow {
 
                         let child = self.get_or_create_event_row();
                         child.set_label(&date);
+                        child.set_target_message_id(None);
                     }
                 }
             } else if let Some(sponsored_message) = item.downcast_ref::<model::SponsoredMessage>() {
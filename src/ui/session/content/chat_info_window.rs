@@ -1,4 +1,5 @@
 use std::cell::OnceCell;
+use std::cell::RefCell;
 use std::sync::OnceLock;
 
 use adw::prelude::*;
@@ -6,6 +7,7 @@ use adw::subclass::prelude::*;
 use gettextrs::gettext;
 use glib::clone;
 use glib::closure;
+use glib::subclass::Signal;
 use gtk::gdk;
 use gtk::glib;
 use gtk::CompositeTemplate;
@@ -14,6 +16,7 @@ use crate::expressions;
 use crate::i18n::ngettext_f;
 use crate::model;
 use crate::strings;
+use crate::ui;
 use crate::utils;
 
 mod imp {
@@ -23,6 +26,8 @@ mod imp {
     #[template(resource = "/app/drey/paper-plane/ui/session/content/chat_info_window.ui")]
     pub(crate) struct ChatInfoWindow {
         pub(super) chat: OnceCell<model::Chat>,
+        /// The lists of the shared media tabs, by page name
+        pub(super) shared_media_lists: RefCell<Vec<(&'static str, model::SharedMediaList)>>,
         #[template_child]
         pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
         #[template_child]
@@ -31,6 +36,10 @@ mod imp {
         pub(super) subtitle_label: TemplateChild<gtk::Inscription>,
         #[template_child]
         pub(super) info_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) navigation_view: TemplateChild<adw::NavigationView>,
+        #[template_child]
+        pub(super) shared_media_stack: TemplateChild<adw::ViewStack>,
     }
 
     #[glib::object_subclass]
@@ -50,6 +59,15 @@ mod imp {
     }
 
     impl ObjectImpl for ChatInfoWindow {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("message-activated")
+                    .param_types([model::Message::static_type()])
+                    .build()]
+            })
+        }
+
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
             PROPERTIES.get_or_init(|| {
@@ -103,6 +121,12 @@ mod imp {
 
             glib::Propagation::Proceed
         }
+
+        #[template_callback]
+        fn on_shared_media_row_activated(&self) {
+            self.navigation_view.push_by_tag("shared-media");
+            self.obj().load_visible_shared_media();
+        }
     }
 }
 
@@ -144,6 +168,198 @@ impl ChatInfoWindow {
                 self.setup_user_info(&secret.user_());
             }
         }
+
+        self.setup_shared_media();
+    }
+
+    fn setup_shared_media(&self) {
+        use tdlib::enums::SearchMessagesFilter::*;
+
+        let imp = self.imp();
+        let chat = self.chat().unwrap();
+
+        let tabs = [
+            (
+                "media",
+                gettext("Media"),
+                gettext("No Media"),
+                "image-x-generic-symbolic",
+                PhotoAndVideo,
+            ),
+            (
+                "files",
+                gettext("Files"),
+                gettext("No Files"),
+                "folder-documents-symbolic",
+                Document,
+            ),
+            (
+                "links",
+                gettext("Links"),
+                gettext("No Links"),
+                "web-browser-symbolic",
+                Url,
+            ),
+            (
+                "voice",
+                gettext("Voice"),
+                gettext("No Voice Messages"),
+                "audio-input-microphone-symbolic",
+                VoiceNote,
+            ),
+            (
+                "music",
+                gettext("Music"),
+                gettext("No Music"),
+                "audio-x-generic-symbolic",
+                Audio,
+            ),
+        ];
+
+        for (name, title, empty_title, icon_name, filter) in tabs {
+            let is_media = matches!(filter, PhotoAndVideo);
+            let show_links = matches!(filter, Url);
+            let list = model::SharedMediaList::new(chat, filter);
+
+            let factory = gtk::SignalListItemFactory::new();
+            factory.connect_setup(move |_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                if is_media {
+                    list_item.set_child(Some(&ui::SharedMediaThumbnail::default()));
+                } else {
+                    list_item.set_child(Some(&ui::SharedMediaRow::new(show_links)));
+                }
+            });
+            factory.connect_bind(|_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                let message = list_item.item().and_downcast::<model::Message>();
+                list_item.child().unwrap().set_property("message", message);
+            });
+
+            let selection = gtk::NoSelection::new(Some(list.clone()));
+            let view: gtk::Widget = if is_media {
+                let grid_view = gtk::GridView::builder()
+                    .model(&selection)
+                    .factory(&factory)
+                    .min_columns(3)
+                    .max_columns(4)
+                    .single_click_activate(true)
+                    .build();
+                grid_view.add_css_class("shared-media-grid");
+                grid_view.connect_activate(
+                    clone!(@weak self as obj, @weak list => move |_, position| {
+                        obj.activate_shared_message(&list, position);
+                    }),
+                );
+                grid_view.upcast()
+            } else {
+                let list_view = gtk::ListView::builder()
+                    .model(&selection)
+                    .factory(&factory)
+                    .single_click_activate(true)
+                    .build();
+                list_view.add_css_class("navigation-sidebar");
+                list_view.connect_activate(
+                    clone!(@weak self as obj, @weak list => move |_, position| {
+                        obj.activate_shared_message(&list, position);
+                    }),
+                );
+                list_view.upcast()
+            };
+
+            let scrolled_window = gtk::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk::PolicyType::Never)
+                .child(&view)
+                .build();
+            scrolled_window.connect_edge_reached(
+                clone!(@weak self as obj, @weak list => move |_, position| {
+                    if position == gtk::PositionType::Bottom {
+                        obj.load_shared_media(&list);
+                    }
+                }),
+            );
+
+            let status_page = adw::StatusPage::builder()
+                .icon_name(icon_name)
+                .title(empty_title)
+                .build();
+            status_page.add_css_class("compact");
+
+            let stack = gtk::Stack::new();
+            stack.add_named(&scrolled_window, Some("list"));
+            stack.add_named(&status_page, Some("empty"));
+
+            let update_stack = clone!(@weak stack => move |list: &model::SharedMediaList| {
+                stack.set_visible_child_name(if list.n_items() == 0 && list.is_fully_loaded() {
+                    "empty"
+                } else {
+                    "list"
+                });
+            });
+            list.connect_notify_local(Some("is-fully-loaded"), {
+                let update_stack = update_stack.clone();
+                move |list, _| update_stack(list)
+            });
+            list.connect_items_changed(move |list, _, _, _| update_stack(list));
+
+            imp.shared_media_stack
+                .add_titled_with_icon(&stack, Some(name), &title, icon_name);
+            imp.shared_media_lists.borrow_mut().push((name, list));
+        }
+
+        imp.shared_media_stack.connect_visible_child_name_notify(
+            clone!(@weak self as obj => move |_| {
+                obj.load_visible_shared_media();
+            }),
+        );
+    }
+
+    /// Loads the first messages of the visible shared media tab, if not already loaded.
+    fn load_visible_shared_media(&self) {
+        let imp = self.imp();
+
+        let Some(name) = imp.shared_media_stack.visible_child_name() else {
+            return;
+        };
+
+        let list = imp
+            .shared_media_lists
+            .borrow()
+            .iter()
+            .find(|(page_name, _)| *page_name == name.as_str())
+            .map(|(_, list)| list.clone());
+
+        if let Some(list) = list.filter(|list| list.n_items() == 0) {
+            self.load_shared_media(&list);
+        }
+    }
+
+    fn load_shared_media(&self, list: &model::SharedMediaList) {
+        utils::spawn(clone!(@weak list => async move {
+            if let Err(e) = list.load_more().await {
+                log::warn!("Failed to load the shared media of a chat: {e:?}");
+            }
+        }));
+    }
+
+    fn activate_shared_message(&self, list: &model::SharedMediaList, position: u32) {
+        if let Some(message) = list.item(position).and_downcast::<model::Message>() {
+            self.emit_by_name::<()>("message-activated", &[&message]);
+        }
+    }
+
+    /// Connects to the signal emitted when a shared message is activated, to show it in the
+    /// chat history.
+    pub(crate) fn connect_message_activated<F: Fn(&Self, &model::Message) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("message-activated", true, move |values| {
+            let obj = values[0].get::<Self>().unwrap();
+            let message = values[1].get::<model::Message>().unwrap();
+            f(&obj, &message);
+            None
+        })
     }
 
     fn setup_user_info(&self, user: &model::User) {
@@ -0,0 +1,322 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+
+use gettextrs::gettext;
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::i18n::gettext_f;
+use crate::model;
+
+const SEARCH_LIMIT: i32 = 50;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::ChatSearchBar)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/chat_search_bar.ui")]
+    pub(crate) struct ChatSearchBar {
+        #[property(get, set = Self::set_chat, explicit_notify, nullable)]
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        /// The ids of the found messages, ordered from the newest to the oldest one
+        pub(super) results: RefCell<Vec<i64>>,
+        pub(super) total_count: Cell<i32>,
+        pub(super) next_from_message_id: Cell<i64>,
+        pub(super) selected_result: Cell<Option<usize>>,
+        /// Used to discard the results of outdated searches
+        pub(super) search_generation: Cell<u32>,
+        #[template_child]
+        pub(super) search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) own_messages_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub(super) filter_drop_down: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub(super) count_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) older_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) newer_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChatSearchBar {
+        const NAME: &'static str = "PaplChatSearchBar";
+        type Type = super::ChatSearchBar;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ChatSearchBar {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.search_bar.connect_entry(&*self.search_entry);
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for ChatSearchBar {}
+
+    impl ChatSearchBar {
+        fn set_chat(&self, chat: Option<&model::Chat>) {
+            let obj = &*self.obj();
+            if obj.chat().as_ref() == chat {
+                return;
+            }
+
+            self.chat.set(chat);
+            self.search_bar.set_search_mode(false);
+
+            obj.notify_chat();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct ChatSearchBar(ObjectSubclass<imp::ChatSearchBar>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl ChatSearchBar {
+    pub(crate) fn begin_search(&self) {
+        let imp = self.imp();
+        imp.search_bar.set_search_mode(true);
+        imp.search_entry.grab_focus();
+    }
+
+    /// Searches the text in the chat, like a clicked hashtag.
+    pub(crate) fn search_text(&self, text: &str) {
+        let imp = self.imp();
+        imp.search_bar.set_search_mode(true);
+        imp.search_entry.set_text(text);
+        imp.search_entry.grab_focus();
+        imp.search_entry.set_position(-1);
+    }
+
+    #[template_callback]
+    fn on_search_mode_changed(&self) {
+        let imp = self.imp();
+        if !imp.search_bar.is_search_mode() {
+            imp.own_messages_button.set_active(false);
+            imp.filter_drop_down.set_selected(0);
+            // This also resets the results
+            imp.search_entry.set_text("");
+        }
+    }
+
+    #[template_callback]
+    async fn search(&self) {
+        let imp = self.imp();
+
+        let generation = imp.search_generation.get().wrapping_add(1);
+        imp.search_generation.set(generation);
+
+        imp.results.borrow_mut().clear();
+        imp.total_count.set(0);
+        imp.next_from_message_id.set(0);
+        imp.selected_result.set(None);
+
+        let query = imp.search_entry.text();
+        if query.is_empty() && self.search_filter().is_none() {
+            self.update_navigation();
+            return;
+        }
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        match self.search_chat_messages(&chat, 0).await {
+            Ok(data) => {
+                if imp.search_generation.get() != generation {
+                    return;
+                }
+
+                imp.total_count.set(data.total_count);
+                imp.next_from_message_id.set(data.next_from_message_id);
+                imp.results
+                    .borrow_mut()
+                    .extend(data.messages.iter().map(|m| m.id));
+
+                if imp.results.borrow().is_empty() {
+                    self.update_navigation();
+                } else {
+                    self.select_result(0);
+                }
+            }
+            Err(e) => log::warn!("Error searching chat messages: {:?}", e),
+        }
+    }
+
+    #[template_callback]
+    async fn select_older_result(&self) {
+        let imp = self.imp();
+
+        let index = imp.selected_result.get().map(|i| i + 1).unwrap_or_default();
+        if index < imp.results.borrow().len() {
+            self.select_result(index);
+            return;
+        }
+
+        let next_from_message_id = imp.next_from_message_id.get();
+        if next_from_message_id == 0 {
+            return;
+        }
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        let generation = imp.search_generation.get();
+
+        match self.search_chat_messages(&chat, next_from_message_id).await {
+            Ok(data) => {
+                if imp.search_generation.get() != generation {
+                    return;
+                }
+
+                imp.next_from_message_id.set(data.next_from_message_id);
+                imp.results
+                    .borrow_mut()
+                    .extend(data.messages.iter().map(|m| m.id));
+
+                if index < imp.results.borrow().len() {
+                    self.select_result(index);
+                } else {
+                    self.update_navigation();
+                }
+            }
+            Err(e) => log::warn!("Error searching more chat messages: {:?}", e),
+        }
+    }
+
+    #[template_callback]
+    fn select_newer_result(&self) {
+        if let Some(index) = self.imp().selected_result.get().filter(|i| *i > 0) {
+            self.select_result(index - 1);
+        }
+    }
+
+    fn select_result(&self, index: usize) {
+        let imp = self.imp();
+
+        let message_id = imp.results.borrow()[index];
+        imp.selected_result.set(Some(index));
+
+        self.update_navigation();
+
+        if let Err(e) = self.activate_action(
+            "chat-history.scroll-to-message",
+            Some(&message_id.to_variant()),
+        ) {
+            log::warn!("Failed to open the found message: {:?}", e);
+        }
+    }
+
+    fn update_navigation(&self) {
+        let imp = self.imp();
+
+        let is_searching = !imp.search_entry.text().is_empty() || self.search_filter().is_some();
+        let results_len = imp.results.borrow().len();
+        let selected_result = imp.selected_result.get();
+
+        let label = match selected_result {
+            Some(index) => gettext_f(
+                "{current} of {total}",
+                &[
+                    ("current", &(index + 1).to_string()),
+                    ("total", &imp.total_count.get().to_string()),
+                ],
+            ),
+            None if is_searching && results_len == 0 => gettext("No results"),
+            None => String::new(),
+        };
+        imp.count_label.set_label(&label);
+
+        imp.older_button.set_sensitive(
+            selected_result
+                .map(|index| index + 1 < results_len || imp.next_from_message_id.get() != 0)
+                .unwrap_or_default(),
+        );
+        imp.newer_button
+            .set_sensitive(selected_result.filter(|index| *index > 0).is_some());
+    }
+
+    async fn search_chat_messages(
+        &self,
+        chat: &model::Chat,
+        from_message_id: i64,
+    ) -> Result<tdlib::types::FoundChatMessages, tdlib::types::Error> {
+        let imp = self.imp();
+        let session = chat.session_();
+
+        let sender_id = if imp.own_messages_button.is_active() {
+            Some(tdlib::enums::MessageSender::User(
+                tdlib::types::MessageSenderUser {
+                    user_id: session.me_().id(),
+                },
+            ))
+        } else {
+            None
+        };
+
+        let tdlib::enums::FoundChatMessages::FoundChatMessages(data) =
+            tdlib::functions::search_chat_messages(
+                chat.id(),
+                imp.search_entry.text().into(),
+                sender_id,
+                from_message_id,
+                0,
+                SEARCH_LIMIT,
+                self.search_filter(),
+                0,
+                session.client_().id(),
+            )
+            .await?;
+
+        Ok(data)
+    }
+
+    fn search_filter(&self) -> Option<tdlib::enums::SearchMessagesFilter> {
+        use tdlib::enums::SearchMessagesFilter::*;
+
+        match self.imp().filter_drop_down.selected() {
+            1 => Some(Photo),
+            2 => Some(Video),
+            3 => Some(Document),
+            4 => Some(Url),
+            5 => Some(VoiceNote),
+            _ => None,
+        }
+    }
+}
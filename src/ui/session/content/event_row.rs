@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::sync::OnceLock;
 
 use adw::subclass::prelude::*;
@@ -11,6 +12,7 @@ mod imp {
     #[derive(Debug, Default, CompositeTemplate)]
     #[template(resource = "/app/drey/paper-plane/ui/session/content/event_row.ui")]
     pub(crate) struct EventRow {
+        pub(super) target_message_id: Cell<Option<i64>>,
         #[template_child]
         pub(super) label: TemplateChild<gtk::Label>,
     }
@@ -23,6 +25,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -30,6 +33,21 @@ mod imp {
         }
     }
 
+    #[gtk::template_callbacks]
+    impl EventRow {
+        #[template_callback]
+        fn on_released(&self) {
+            if let Some(message_id) = self.target_message_id.get() {
+                if let Err(e) = self.obj().activate_action(
+                    "chat-history.scroll-to-message",
+                    Some(&message_id.to_variant()),
+                ) {
+                    log::warn!("Failed to open the event's message: {:?}", e);
+                }
+            }
+        }
+    }
+
     impl ObjectImpl for EventRow {
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
@@ -82,4 +100,11 @@ impl EventRow {
     pub(crate) fn set_label(&self, label: &str) {
         self.imp().label.set_label(label);
     }
+
+    /// Sets the message that is opened when clicking the row, like the message of a pinned
+    /// message event.
+    pub(crate) fn set_target_message_id(&self, message_id: Option<i64>) {
+        self.imp().target_message_id.set(message_id);
+        self.set_cursor_from_name(message_id.map(|_| "pointer"));
+    }
 }
@@ -0,0 +1,757 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gio;
+use gtk::glib;
+use gtk::CompositeTemplate;
+
+use crate::expressions;
+use crate::model;
+use crate::strings;
+use crate::ui;
+use crate::utils;
+
+/// The delay after which the changed topics are updated, to group the changes of busy forums.
+const UPDATE_DELAY: Duration = Duration::from_millis(500);
+/// The icon colors that TDLib accepts for new topics, with their style classes.
+const TOPIC_ICON_COLORS: &[(i32, &str)] = &[
+    (0x6FB9F0, "forum-topic-icon-blue"),
+    (0xFFD67E, "forum-topic-icon-yellow"),
+    (0xCB86DB, "forum-topic-icon-violet"),
+    (0x8EEE98, "forum-topic-icon-green"),
+    (0xFF93B2, "forum-topic-icon-pink"),
+    (0xFB6F5F, "forum-topic-icon-red"),
+];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/forum_topic_list.ui")]
+    pub(crate) struct ForumTopicList {
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) chat_handlers: RefCell<Vec<glib::SignalHandlerId>>,
+        pub(super) topics: RefCell<Vec<tdlib::types::ForumTopic>>,
+        /// The message thread ids of the topics waiting to be updated
+        pub(super) pending_topic_updates: RefCell<HashSet<i64>>,
+        pub(super) update_source_id: RefCell<Option<glib::SourceId>>,
+        /// Incremented on every reload, to discard the topics of outdated requests
+        pub(super) load_generation: Cell<u32>,
+        #[template_child]
+        pub(super) window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub(super) create_topic_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ForumTopicList {
+        const NAME: &'static str = "PaplForumTopicList";
+        type Type = super::ForumTopicList;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+
+            klass.install_action_async(
+                "forum-topic-list.create-topic",
+                None,
+                |widget, _, _| async move {
+                    widget.create_topic().await;
+                },
+            );
+            klass.install_action_async(
+                "forum-topic-list.edit-topic",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_thread_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.edit_topic(message_thread_id).await;
+                },
+            );
+            klass.install_action_async(
+                "forum-topic-list.toggle-topic-closed",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_thread_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.toggle_topic_closed(message_thread_id).await;
+                },
+            );
+            klass.install_action_async(
+                "forum-topic-list.delete-topic",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_thread_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.delete_topic(message_thread_id).await;
+                },
+            );
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ForumTopicList {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Chat>("chat")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => {
+                    let chat = value.get().unwrap();
+                    obj.set_chat(chat);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.chat().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_expressions();
+        }
+
+        fn dispose(&self) {
+            if let Some(source_id) = self.update_source_id.take() {
+                source_id.remove();
+            }
+        }
+    }
+
+    impl WidgetImpl for ForumTopicList {}
+    impl BinImpl for ForumTopicList {}
+}
+
+glib::wrapper! {
+    /// The list of the topics of a forum, each of them opening its own message thread.
+    pub(crate) struct ForumTopicList(ObjectSubclass<imp::ForumTopicList>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl Default for ForumTopicList {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+#[gtk::template_callbacks]
+impl ForumTopicList {
+    fn setup_expressions(&self) {
+        let chat_expression = Self::this_expression("chat");
+
+        // Chat title
+        expressions::chat_display_name(&chat_expression).bind(
+            &*self.imp().window_title,
+            "title",
+            Some(self),
+        );
+    }
+
+    pub(crate) fn chat(&self) -> Option<model::Chat> {
+        self.imp().chat.upgrade()
+    }
+
+    pub(crate) fn set_chat(&self, chat: Option<&model::Chat>) {
+        let old_chat = self.chat();
+        if old_chat.as_ref() == chat {
+            return;
+        }
+
+        let imp = self.imp();
+
+        if let Some(old_chat) = old_chat {
+            for handler in imp.chat_handlers.take() {
+                old_chat.disconnect(handler);
+            }
+        }
+
+        imp.topics.replace(Vec::new());
+        imp.pending_topic_updates.borrow_mut().clear();
+        if let Some(source_id) = imp.update_source_id.take() {
+            source_id.remove();
+        }
+        imp.list_box.remove_all();
+
+        if let Some(chat) = chat {
+            let handlers = vec![
+                chat.connect_forum_topic_changed(
+                    clone!(@weak self as obj => move |_, message_thread_id| {
+                        obj.queue_topic_update(message_thread_id);
+                    }),
+                ),
+                chat.connect_deleted_forum_topic(
+                    clone!(@weak self as obj => move |_, message_thread_id| {
+                        obj.remove_topic(message_thread_id);
+                    }),
+                ),
+                // Keep the last messages and the unread counts of the topics up to date
+                chat.connect_new_message(clone!(@weak self as obj => move |_, message| {
+                    // Messages of the general topic aren't part of a message thread
+                    let message_thread_id = match message.message_thread_id() {
+                        0 => obj.general_topic_id(),
+                        id => Some(id),
+                    };
+                    if let Some(message_thread_id) = message_thread_id {
+                        obj.queue_topic_update(message_thread_id);
+                    }
+                })),
+            ];
+            imp.chat_handlers.replace(handlers);
+
+            imp.create_topic_button
+                .set_visible(chat.can_manage_topics());
+            imp.stack.set_visible_child_name("loading");
+        }
+
+        imp.chat.set(chat);
+        self.notify("chat");
+
+        self.reload();
+    }
+
+    /// Queues the update of the topic with the given message thread id.
+    fn queue_topic_update(&self, message_thread_id: i64) {
+        let imp = self.imp();
+
+        imp.pending_topic_updates
+            .borrow_mut()
+            .insert(message_thread_id);
+
+        if imp.update_source_id.borrow().is_some() {
+            return;
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            UPDATE_DELAY,
+            clone!(@weak self as obj => move || {
+                obj.imp().update_source_id.replace(None);
+                obj.update_pending_topics();
+            }),
+        );
+        imp.update_source_id.replace(Some(source_id));
+    }
+
+    /// Fetches the topics waiting to be updated and replaces their rows.
+    fn update_pending_topics(&self) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        let message_thread_ids = self.imp().pending_topic_updates.take();
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            for message_thread_id in message_thread_ids {
+                let topic = match chat.get_forum_topic(message_thread_id).await {
+                    Ok(topic) => topic,
+                    Err(e) => {
+                        log::warn!("Failed to update a topic of a forum: {e:?}");
+                        continue;
+                    }
+                };
+                let custom_emojis = custom_emojis(&chat, std::slice::from_ref(&topic)).await;
+
+                if obj.chat().as_ref() != Some(&chat) {
+                    return;
+                }
+
+                let emoji = custom_emojis
+                    .get(&topic.info.icon.custom_emoji_id)
+                    .map(|sticker| sticker.emoji.as_str());
+                obj.update_topic(&chat, topic, emoji);
+            }
+        }));
+    }
+
+    /// Replaces the row of the topic, moving it to the position given by its last message.
+    fn update_topic(
+        &self,
+        chat: &model::Chat,
+        topic: tdlib::types::ForumTopic,
+        emoji: Option<&str>,
+    ) {
+        let imp = self.imp();
+        let mut topics = imp.topics.borrow_mut();
+
+        let old_index = topics
+            .iter()
+            .position(|t| t.info.message_thread_id == topic.info.message_thread_id);
+        if let Some(old_index) = old_index {
+            topics.remove(old_index);
+            if let Some(row) = imp.list_box.row_at_index(old_index as i32) {
+                imp.list_box.remove(&row);
+            }
+        }
+
+        // Pinned topics keep their order, the others are sorted by their last message
+        let pinned_count = topics.iter().take_while(|t| t.is_pinned).count();
+        let index = if topic.is_pinned {
+            old_index.unwrap_or(pinned_count).min(pinned_count)
+        } else {
+            let last_message_id = topic.last_message.as_ref().map(|m| m.id);
+            topics[pinned_count..]
+                .iter()
+                .position(|t| t.last_message.as_ref().map(|m| m.id) < last_message_id)
+                .map(|i| i + pinned_count)
+                .unwrap_or(topics.len())
+        };
+
+        imp.list_box.insert(
+            &new_topic_row(&topic, chat, emoji, chat.can_manage_topics()),
+            index as i32,
+        );
+        topics.insert(index, topic);
+
+        imp.stack.set_visible_child_name("topics");
+    }
+
+    fn remove_topic(&self, message_thread_id: i64) {
+        let imp = self.imp();
+        let mut topics = imp.topics.borrow_mut();
+
+        let Some(index) = topics
+            .iter()
+            .position(|t| t.info.message_thread_id == message_thread_id)
+        else {
+            return;
+        };
+
+        topics.remove(index);
+        if let Some(row) = imp.list_box.row_at_index(index as i32) {
+            imp.list_box.remove(&row);
+        }
+
+        if topics.is_empty() {
+            imp.stack.set_visible_child_name("empty");
+        }
+    }
+
+    /// Returns the message thread id of the general topic, if it has been loaded.
+    fn general_topic_id(&self) -> Option<i64> {
+        self.imp()
+            .topics
+            .borrow()
+            .iter()
+            .find(|topic| topic.info.is_general)
+            .map(|topic| topic.info.message_thread_id)
+    }
+
+    /// Loads the topics of the forum again, like after coming back from one of them.
+    pub(crate) fn reload(&self) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        let imp = self.imp();
+        let generation = imp.load_generation.get().wrapping_add(1);
+        imp.load_generation.set(generation);
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            let result = chat.get_forum_topics().await;
+            let custom_emojis = match &result {
+                Ok(topics) => custom_emojis(&chat, topics).await,
+                Err(_) => HashMap::new(),
+            };
+
+            let imp = obj.imp();
+            if imp.load_generation.get() != generation || obj.chat().as_ref() != Some(&chat) {
+                return;
+            }
+
+            match result {
+                Ok(topics) => {
+                    imp.list_box.remove_all();
+
+                    let can_manage_topics = chat.can_manage_topics();
+                    for topic in &topics {
+                        let emoji = custom_emojis
+                            .get(&topic.info.icon.custom_emoji_id)
+                            .map(|sticker| sticker.emoji.as_str());
+                        imp.list_box.append(&new_topic_row(
+                            topic,
+                            &chat,
+                            emoji,
+                            can_manage_topics,
+                        ));
+                    }
+
+                    imp.stack.set_visible_child_name(if topics.is_empty() {
+                        "empty"
+                    } else {
+                        "topics"
+                    });
+                    imp.topics.replace(topics);
+                }
+                Err(e) => {
+                    log::warn!("Failed to load the topics of a forum: {e:?}");
+                    if imp.topics.borrow().is_empty() {
+                        imp.stack.set_visible_child_name("error");
+                    }
+                }
+            }
+        }));
+    }
+
+    fn topic(&self, message_thread_id: i64) -> Option<tdlib::types::ForumTopic> {
+        self.imp()
+            .topics
+            .borrow()
+            .iter()
+            .find(|topic| topic.info.message_thread_id == message_thread_id)
+            .cloned()
+    }
+
+    #[template_callback]
+    fn on_list_box_row_activated(&self, row: &gtk::ListBoxRow) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let Some(topic) = self
+            .imp()
+            .topics
+            .borrow()
+            .get(row.index() as usize)
+            .cloned()
+        else {
+            return;
+        };
+
+        utils::ancestor::<_, ui::Content>(self).open_thread(
+            &chat,
+            topic.info.message_thread_id,
+            &chat,
+            &topic.info.name,
+        );
+    }
+
+    async fn create_topic(&self) {
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let Some(name) = self
+            .choose_topic_name(&gettext("New Topic"), "", &gettext("_Create"))
+            .await
+        else {
+            return;
+        };
+
+        // Pick a color for the icon, like the official apps do
+        let index = glib::random_int_range(0, TOPIC_ICON_COLORS.len() as i32) as usize;
+        let icon_color = TOPIC_ICON_COLORS[index].0;
+
+        match chat.create_forum_topic(name.clone(), icon_color).await {
+            Ok(message_thread_id) => {
+                utils::ancestor::<_, ui::Content>(self).open_thread(
+                    &chat,
+                    message_thread_id,
+                    &chat,
+                    &name,
+                );
+            }
+            Err(e) => {
+                log::warn!("Failed to create a forum topic: {e:?}");
+                utils::show_toast(self, gettext("Failed to create the topic"));
+            }
+        }
+    }
+
+    async fn edit_topic(&self, message_thread_id: i64) {
+        let (Some(chat), Some(topic)) = (self.chat(), self.topic(message_thread_id)) else {
+            return;
+        };
+        let Some(name) = self
+            .choose_topic_name(&gettext("Edit Topic"), &topic.info.name, &gettext("_Save"))
+            .await
+        else {
+            return;
+        };
+
+        if name == topic.info.name {
+            return;
+        }
+
+        if let Err(e) = chat.edit_forum_topic(message_thread_id, name).await {
+            log::warn!("Failed to edit a forum topic: {e:?}");
+            utils::show_toast(self, gettext("Failed to edit the topic"));
+        }
+    }
+
+    async fn toggle_topic_closed(&self, message_thread_id: i64) {
+        let (Some(chat), Some(topic)) = (self.chat(), self.topic(message_thread_id)) else {
+            return;
+        };
+
+        let is_closed = !topic.info.is_closed;
+        if let Err(e) = chat
+            .toggle_forum_topic_is_closed(message_thread_id, is_closed)
+            .await
+        {
+            log::warn!("Failed to close or reopen a forum topic: {e:?}");
+            utils::show_toast(
+                self,
+                if is_closed {
+                    gettext("Failed to close the topic")
+                } else {
+                    gettext("Failed to reopen the topic")
+                },
+            );
+        }
+    }
+
+    async fn delete_topic(&self, message_thread_id: i64) {
+        let (Some(chat), Some(topic)) = (self.chat(), self.topic(message_thread_id)) else {
+            return;
+        };
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Delete Topic?"))
+            .body(utils::freplace(
+                gettext("All messages in “{name}” will be deleted for all members."),
+                &[("name", &topic.info.name)],
+            ))
+            .build();
+        dialog.set_transient_for(self.root().and_downcast_ref::<gtk::Window>());
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("delete", &gettext("_Delete")),
+        ]);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+        if dialog.choose_future().await != "delete" {
+            return;
+        }
+
+        if let Err(e) = chat.delete_forum_topic(message_thread_id).await {
+            log::warn!("Failed to delete a forum topic: {e:?}");
+            utils::show_toast(self, gettext("Failed to delete the topic"));
+        }
+    }
+
+    /// Asks for the name of a topic, returning `None` if the dialog has been cancelled.
+    async fn choose_topic_name(&self, heading: &str, name: &str, response: &str) -> Option<String> {
+        let name_entry = gtk::Entry::builder()
+            .text(name)
+            .placeholder_text(gettext("Topic Name"))
+            .activates_default(true)
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(heading)
+            .extra_child(&name_entry)
+            .build();
+        dialog.set_transient_for(self.root().and_downcast_ref::<gtk::Window>());
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("confirm", response)]);
+        dialog.set_default_response(Some("confirm"));
+        dialog.set_response_appearance("confirm", adw::ResponseAppearance::Suggested);
+        dialog.set_response_enabled("confirm", !name.trim().is_empty());
+
+        name_entry.connect_changed(clone!(@weak dialog => move |entry| {
+            dialog.set_response_enabled("confirm", !entry.text().trim().is_empty());
+        }));
+
+        if dialog.choose_future().await != "confirm" {
+            return None;
+        }
+
+        Some(name_entry.text().trim().to_owned()).filter(|name| !name.is_empty())
+    }
+}
+
+/// Returns the stickers of the custom emoji used as icons by the topics, mapped by their id.
+///
+/// Topics with a custom emoji as icon are shown with the emoji it stands for.
+async fn custom_emojis(
+    chat: &model::Chat,
+    topics: &[tdlib::types::ForumTopic],
+) -> HashMap<i64, tdlib::types::Sticker> {
+    let custom_emoji_ids = topics
+        .iter()
+        .map(|topic| topic.info.icon.custom_emoji_id)
+        .filter(|id| *id != 0)
+        .collect::<Vec<_>>();
+
+    if custom_emoji_ids.is_empty() {
+        HashMap::new()
+    } else {
+        chat.session_()
+            .custom_emoji_stickers(&custom_emoji_ids)
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// Creates the row of a topic, showing its icon, name, last message, state and unread count.
+fn new_topic_row(
+    topic: &tdlib::types::ForumTopic,
+    chat: &model::Chat,
+    emoji: Option<&str>,
+    can_manage_topics: bool,
+) -> gtk::ListBoxRow {
+    let info = &topic.info;
+
+    let icon = gtk::Label::builder()
+        .label(match emoji {
+            Some(emoji) => emoji.to_owned(),
+            None if info.is_general => "#".to_owned(),
+            None => info
+                .name
+                .chars()
+                .next()
+                .map(|c| c.to_uppercase().collect())
+                .unwrap_or_default(),
+        })
+        .valign(gtk::Align::Center)
+        .css_classes(["forum-topic-icon"])
+        .build();
+    if emoji.is_none() {
+        let color_class = TOPIC_ICON_COLORS
+            .iter()
+            .find(|(color, _)| *color == info.icon.color)
+            .map(|(_, class)| *class)
+            .unwrap_or(TOPIC_ICON_COLORS[0].1);
+        icon.add_css_class(color_class);
+    } else {
+        icon.add_css_class("emoji");
+    }
+
+    let name_label = gtk::Label::builder()
+        .label(info.name.as_str())
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes(["heading"])
+        .build();
+
+    let last_message_label = gtk::Label::builder()
+        .label(
+            topic
+                .last_message
+                .clone()
+                .map(|message| strings::message_content(&model::Message::new(chat, message)))
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or_default(),
+        )
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes(["dim-label"])
+        .build();
+
+    let text_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .hexpand(true)
+        .valign(gtk::Align::Center)
+        .build();
+    text_box.append(&name_label);
+    text_box.append(&last_message_label);
+
+    let row_box = gtk::Box::builder().spacing(12).build();
+    row_box.append(&icon);
+    row_box.append(&text_box);
+
+    if topic.is_pinned {
+        row_box.append(
+            &gtk::Image::builder()
+                .icon_name("view-pin-symbolic")
+                .tooltip_text(gettext("Pinned"))
+                .css_classes(["dim-label"])
+                .build(),
+        );
+    }
+
+    if info.is_closed {
+        row_box.append(
+            &gtk::Image::builder()
+                .icon_name("system-lock-screen-symbolic")
+                .tooltip_text(gettext("Closed"))
+                .css_classes(["dim-label"])
+                .build(),
+        );
+    }
+
+    if topic.unread_count > 0 {
+        row_box.append(
+            &gtk::Label::builder()
+                .label(topic.unread_count.to_string())
+                .valign(gtk::Align::Center)
+                .css_classes(["unread-count"])
+                .build(),
+        );
+    }
+
+    // Creators of topics can also edit and close them
+    let can_edit_topic = can_manage_topics || info.is_outgoing;
+    if can_edit_topic {
+        let target = info.message_thread_id.to_variant();
+
+        let menu = gio::Menu::new();
+        menu.append_item(&menu_item(
+            &gettext("_Edit"),
+            "forum-topic-list.edit-topic",
+            &target,
+        ));
+        menu.append_item(&menu_item(
+            &if info.is_closed {
+                gettext("_Reopen")
+            } else {
+                gettext("C_lose")
+            },
+            "forum-topic-list.toggle-topic-closed",
+            &target,
+        ));
+        // The general topic can't be deleted
+        if can_manage_topics && !info.is_general {
+            menu.append_item(&menu_item(
+                &gettext("_Delete"),
+                "forum-topic-list.delete-topic",
+                &target,
+            ));
+        }
+
+        row_box.append(
+            &gtk::MenuButton::builder()
+                .icon_name("view-more-symbolic")
+                .menu_model(&menu)
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build(),
+        );
+    }
+
+    gtk::ListBoxRow::builder().child(&row_box).build()
+}
+
+fn menu_item(label: &str, action: &str, target: &glib::Variant) -> gio::MenuItem {
+    let item = gio::MenuItem::new(Some(label), None);
+    item.set_action_and_target_value(Some(action), Some(target));
+    item
+}
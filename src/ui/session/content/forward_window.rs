@@ -0,0 +1,342 @@
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::types::ChatId;
+use crate::ui;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/forward_window.ui")]
+    pub(crate) struct ForwardWindow {
+        pub(super) from_chat: glib::WeakRef<model::Chat>,
+        pub(super) message_ids: OnceCell<Vec<i64>>,
+        pub(super) selected_chats: RefCell<Vec<model::Chat>>,
+        pub(super) check_buttons: RefCell<HashMap<i64, glib::WeakRef<gtk::CheckButton>>>,
+        pub(super) filter: OnceCell<gtk::CustomFilter>,
+        /// The ids of the chats in the main and the archive chat list.
+        pub(super) listed_chat_ids: RefCell<HashSet<ChatId>>,
+        pub(super) chat_list_handlers: RefCell<Vec<(model::ChatList, glib::SignalHandlerId)>>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) send_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) send_copy_button: TemplateChild<gtk::CheckButton>,
+        #[template_child]
+        pub(super) remove_caption_button: TemplateChild<gtk::CheckButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ForwardWindow {
+        const NAME: &'static str = "PaplForwardWindow";
+        type Type = super::ForwardWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+
+            klass.install_action_async("forward-window.send", None, |widget, _, _| async move {
+                widget.send().await;
+            });
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ForwardWindow {
+        fn dispose(&self) {
+            for (chat_list, handler_id) in self.chat_list_handlers.take() {
+                chat_list.disconnect(handler_id);
+            }
+        }
+    }
+
+    impl WidgetImpl for ForwardWindow {}
+    impl WindowImpl for ForwardWindow {}
+    impl AdwWindowImpl for ForwardWindow {}
+
+    #[gtk::template_callbacks]
+    impl ForwardWindow {
+        #[template_callback]
+        fn on_key_pressed(
+            &self,
+            key: gdk::Key,
+            _: u32,
+            modifier: gdk::ModifierType,
+            _: &gtk::EventControllerKey,
+        ) -> glib::Propagation {
+            if key == gdk::Key::Escape
+                || (key == gdk::Key::w && modifier == gdk::ModifierType::CONTROL_MASK)
+            {
+                self.obj().close();
+            }
+
+            glib::Propagation::Proceed
+        }
+
+        #[template_callback]
+        fn on_search_changed(&self) {
+            if let Some(filter) = self.filter.get() {
+                filter.changed(gtk::FilterChange::Different);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct ForwardWindow(ObjectSubclass<imp::ForwardWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl ForwardWindow {
+    pub(crate) fn new(
+        parent: Option<&gtk::Window>,
+        from_chat: &model::Chat,
+        message_ids: Vec<i64>,
+    ) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+        let imp = obj.imp();
+
+        imp.from_chat.set(Some(from_chat));
+        imp.message_ids.set(message_ids).unwrap();
+
+        let session = from_chat.session_();
+
+        // The chat folder list also contains the main chat list
+        let chat_lists = gio::ListStore::new::<gio::ListModel>();
+        chat_lists.append(&gtk::FlattenListModel::new(Some(
+            session.chat_folder_list(),
+        )));
+        chat_lists.append(&session.archive_chat_list());
+
+        obj.update_listed_chat_ids();
+
+        let filter =
+            gtk::CustomFilter::new(clone!(@weak obj => @default-return false, move |item| {
+                let item = item.downcast_ref::<model::ChatListItem>().unwrap();
+                let Some(chat) = item.chat() else {
+                    return false;
+                };
+
+                // Show the chats of the folders only once, as they are also in the main or
+                // the archive chat list once these have been loaded
+                if matches!(item.chat_list_type().0, tdlib::enums::ChatList::Folder(_))
+                    && obj.imp().listed_chat_ids.borrow().contains(&chat.id())
+                {
+                    return false;
+                }
+
+                chat.can_send_messages() && obj.chat_matches_search(&chat)
+            }));
+        let filter_model = gtk::FilterListModel::new(
+            Some(gtk::FlattenListModel::new(Some(chat_lists))),
+            Some(filter.clone()),
+        );
+        for chat_list in [session.main_chat_list(), session.archive_chat_list()] {
+            let handler_id =
+                chat_list.connect_items_changed(clone!(@weak obj => move |_, _, _, _| {
+                    obj.update_listed_chat_ids();
+                }));
+            imp.chat_list_handlers
+                .borrow_mut()
+                .push((chat_list, handler_id));
+        }
+        imp.filter.set(filter).unwrap();
+
+        imp.list_box.bind_model(
+            Some(&filter_model),
+            clone!(@weak obj => @default-panic, move |item| {
+                let chat = item
+                    .downcast_ref::<model::ChatListItem>()
+                    .and_then(model::ChatListItem::chat)
+                    .unwrap();
+                obj.create_chat_row(&chat).upcast()
+            }),
+        );
+
+        obj
+    }
+
+    /// Updates the ids of the chats in the main and the archive chat list, refiltering the chats
+    /// only if the lists gained or lost chats, not when they have just been reordered.
+    fn update_listed_chat_ids(&self) {
+        let imp = self.imp();
+        let Some(from_chat) = imp.from_chat.upgrade() else {
+            return;
+        };
+        let session = from_chat.session_();
+
+        let chat_ids = session
+            .main_chat_list()
+            .chat_ids()
+            .into_iter()
+            .chain(session.archive_chat_list().chat_ids())
+            .collect::<HashSet<_>>();
+
+        let old_chat_ids = imp.listed_chat_ids.replace(chat_ids);
+        let chat_ids = imp.listed_chat_ids.borrow();
+
+        // More listed chats can only hide folder chats, and less listed chats can only show them
+        let change = match (
+            chat_ids.is_superset(&old_chat_ids),
+            chat_ids.is_subset(&old_chat_ids),
+        ) {
+            (true, true) => return,
+            (true, false) => gtk::FilterChange::MoreStrict,
+            (false, true) => gtk::FilterChange::LessStrict,
+            (false, false) => gtk::FilterChange::Different,
+        };
+        drop(chat_ids);
+
+        if let Some(filter) = imp.filter.get() {
+            filter.changed(change);
+        }
+    }
+
+    fn chat_title(chat: &model::Chat) -> String {
+        if chat.is_own_chat() {
+            gettext("Saved Messages")
+        } else {
+            chat.title()
+        }
+    }
+
+    fn chat_matches_search(&self, chat: &model::Chat) -> bool {
+        let query = self.imp().search_entry.text().to_lowercase();
+        query.is_empty() || Self::chat_title(chat).to_lowercase().contains(&query)
+    }
+
+    fn create_chat_row(&self, chat: &model::Chat) -> adw::ActionRow {
+        let avatar = ui::Avatar::new();
+        avatar.set_size(32);
+        avatar.set_item(Some(chat.clone().upcast()));
+
+        let check_button = gtk::CheckButton::builder()
+            .valign(gtk::Align::Center)
+            .active(self.imp().selected_chats.borrow().contains(chat))
+            .build();
+        check_button.connect_toggled(clone!(@weak self as obj, @weak chat => move |button| {
+            obj.set_chat_selected(&chat, button.is_active());
+        }));
+        self.imp()
+            .check_buttons
+            .borrow_mut()
+            .insert(chat.id(), check_button.downgrade());
+
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&Self::chat_title(chat)))
+            .activatable_widget(&check_button)
+            .build();
+        row.add_prefix(&avatar);
+        row.add_suffix(&check_button);
+
+        row
+    }
+
+    fn set_chat_selected(&self, chat: &model::Chat, selected: bool) {
+        let imp = self.imp();
+
+        let mut selected_chats = imp.selected_chats.borrow_mut();
+        if selected {
+            if !selected_chats.contains(chat) {
+                selected_chats.push(chat.clone());
+            }
+        } else {
+            selected_chats.retain(|c| c != chat);
+        }
+
+        imp.send_button.set_sensitive(!selected_chats.is_empty());
+    }
+
+    fn unselect_chat(&self, chat: &model::Chat) {
+        let check_button = self
+            .imp()
+            .check_buttons
+            .borrow()
+            .get(&chat.id())
+            .and_then(glib::WeakRef::upgrade);
+
+        match check_button {
+            // This will also update the selected chats
+            Some(check_button) => check_button.set_active(false),
+            None => self.set_chat_selected(chat, false),
+        }
+    }
+
+    async fn send(&self) {
+        let imp = self.imp();
+
+        let Some(from_chat) = imp.from_chat.upgrade() else {
+            return;
+        };
+        let message_ids = imp.message_ids.get().unwrap();
+        let send_copy = imp.send_copy_button.is_active();
+        let remove_caption = send_copy && imp.remove_caption_button.is_active();
+        let client_id = from_chat.session_().client_().id();
+
+        imp.send_button.set_sensitive(false);
+
+        let chats = imp.selected_chats.borrow().clone();
+        let mut failed = false;
+
+        for chat in chats {
+            let result = tdlib::functions::forward_messages(
+                chat.id(),
+                0,
+                from_chat.id(),
+                message_ids.clone(),
+                None,
+                send_copy,
+                remove_caption,
+                client_id,
+            )
+            .await;
+
+            match result {
+                // Make sure that the messages aren't forwarded twice when retrying
+                Ok(_) => self.unselect_chat(&chat),
+                Err(e) => {
+                    log::warn!("Error forwarding messages: {:?}", e);
+                    imp.toast_overlay.add_toast(
+                        adw::Toast::builder()
+                            .title(e.message)
+                            .timeout(3)
+                            .priority(adw::ToastPriority::High)
+                            .build(),
+                    );
+                    failed = true;
+                }
+            }
+        }
+
+        if failed {
+            imp.send_button
+                .set_sensitive(!imp.selected_chats.borrow().is_empty());
+        } else {
+            self.close();
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::gio;
+use gtk::glib;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::strings;
+use crate::types::MessageId;
+use crate::ui;
+use crate::utils;
+
+/// The number of messages requested at once when browsing the media of the chat.
+const GALLERY_PAGE_SIZE: i32 = 20;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/media_viewer.ui")]
+    pub(crate) struct MediaViewer {
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        /// The messages of the gallery, from the oldest to the newest
+        pub(super) messages: RefCell<Vec<model::Message>>,
+        pub(super) index: Cell<usize>,
+        pub(super) has_older: Cell<bool>,
+        pub(super) has_newer: Cell<bool>,
+        pub(super) is_loading_older: Cell<bool>,
+        pub(super) is_loading_newer: Cell<bool>,
+        /// A stream that is already playing the video of a message
+        pub(super) media_stream: RefCell<Option<(MessageId, gtk::MediaStream)>>,
+        /// The path of the file that is currently shown, once downloaded
+        pub(super) path: RefCell<Option<String>>,
+        /// Used to ignore the results of the loading of messages that are no longer shown
+        pub(super) generation: Cell<u32>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) picture: TemplateChild<ui::ZoomablePicture>,
+        #[template_child]
+        pub(super) video: TemplateChild<gtk::Video>,
+        #[template_child]
+        pub(super) progress_bar: TemplateChild<gtk::ProgressBar>,
+        #[template_child]
+        pub(super) caption_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MediaViewer {
+        const NAME: &'static str = "PaplMediaViewer";
+        type Type = super::MediaViewer;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+
+            klass.install_action("media-viewer.previous", None, move |widget, _, _| {
+                widget.show_previous();
+            });
+            klass.install_action("media-viewer.next", None, move |widget, _, _| {
+                widget.show_next();
+            });
+            klass.install_action("media-viewer.zoom-in", None, move |widget, _, _| {
+                widget.imp().picture.zoom_in();
+            });
+            klass.install_action("media-viewer.zoom-out", None, move |widget, _, _| {
+                widget.imp().picture.zoom_out();
+            });
+            klass.install_action("media-viewer.rotate-left", None, move |widget, _, _| {
+                widget.imp().picture.rotate(false);
+            });
+            klass.install_action("media-viewer.rotate-right", None, move |widget, _, _| {
+                widget.imp().picture.rotate(true);
+            });
+            klass.install_action("media-viewer.copy", None, move |widget, _, _| {
+                widget.copy();
+            });
+            klass.install_action_async("media-viewer.save", None, |widget, _, _| async move {
+                widget.save().await;
+            });
+            klass.install_action(
+                "media-viewer.toggle-fullscreen",
+                None,
+                move |widget, _, _| {
+                    widget.set_fullscreened(!widget.is_fullscreened());
+                },
+            );
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MediaViewer {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            self.picture.connect_notify_local(
+                None,
+                clone!(@weak obj => move |_, _| {
+                    obj.update_actions();
+                }),
+            );
+        }
+
+        fn dispose(&self) {
+            // A stream coming from a message row keeps playing there
+            let external_media = self.media_stream.borrow().as_ref().map(|(_, m)| m.clone());
+            if let Some(media) = self.video.media_stream() {
+                if Some(&media) != external_media.as_ref() {
+                    media.pause();
+                }
+            }
+        }
+    }
+
+    impl WidgetImpl for MediaViewer {}
+    impl WindowImpl for MediaViewer {}
+    impl AdwWindowImpl for MediaViewer {}
+
+    #[gtk::template_callbacks]
+    impl MediaViewer {
+        #[template_callback]
+        fn on_key_pressed(
+            &self,
+            key: gdk::Key,
+            _: u32,
+            modifier: gdk::ModifierType,
+            _: &gtk::EventControllerKey,
+        ) -> glib::Propagation {
+            let obj = self.obj();
+
+            if key == gdk::Key::Escape
+                || (key == gdk::Key::w && modifier == gdk::ModifierType::CONTROL_MASK)
+            {
+                obj.close();
+            } else if key == gdk::Key::Left {
+                obj.show_previous();
+            } else if key == gdk::Key::Right {
+                obj.show_next();
+            } else if key == gdk::Key::F11 {
+                obj.set_fullscreened(!obj.is_fullscreened());
+            } else if key == gdk::Key::plus || key == gdk::Key::equal {
+                self.picture.zoom_in();
+            } else if key == gdk::Key::minus {
+                self.picture.zoom_out();
+            } else if key == gdk::Key::c && modifier == gdk::ModifierType::CONTROL_MASK {
+                obj.copy();
+            } else if key == gdk::Key::s && modifier == gdk::ModifierType::CONTROL_MASK {
+                utils::spawn(clone!(@weak obj => async move {
+                    obj.save().await;
+                }));
+            } else {
+                return glib::Propagation::Proceed;
+            }
+
+            glib::Propagation::Stop
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A window showing the photos and videos of a chat, starting from the specified message.
+    pub(crate) struct MediaViewer(ObjectSubclass<imp::MediaViewer>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl MediaViewer {
+    pub(crate) fn new(parent: Option<&gtk::Window>, message: &model::Message) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+        let imp = obj.imp();
+
+        let chat = message.chat_();
+        imp.chat.set(Some(&chat));
+        imp.messages.replace(vec![message.clone()]);
+        imp.has_older.set(true);
+        imp.has_newer.set(true);
+
+        obj.show_current();
+
+        utils::spawn(clone!(@weak obj, @weak message => async move {
+            obj.load_gallery(&message).await;
+        }));
+
+        obj
+    }
+
+    /// Shows the video of the message through a stream that is already playing it, instead of
+    /// loading it again.
+    pub(crate) fn set_media_stream(
+        &self,
+        message_id: MessageId,
+        media: &impl IsA<gtk::MediaStream>,
+    ) {
+        let imp = self.imp();
+        imp.media_stream
+            .replace(Some((message_id, media.clone().upcast())));
+
+        if self.current_message().map(|message| message.id()) == Some(message_id) {
+            // Ignore the file being loaded in the meantime
+            imp.generation.set(imp.generation.get().wrapping_add(1));
+            imp.video.set_media_stream(Some(media));
+            imp.stack.set_visible_child_name("video");
+        }
+    }
+
+    fn current_message(&self) -> Option<model::Message> {
+        let imp = self.imp();
+        imp.messages.borrow().get(imp.index.get()).cloned()
+    }
+
+    /// Loads the photos and videos around the message the viewer was opened with.
+    async fn load_gallery(&self, message: &model::Message) {
+        let imp = self.imp();
+
+        let Some(chat) = imp.chat.upgrade() else {
+            return;
+        };
+
+        let result = chat
+            .search_messages(
+                tdlib::enums::SearchMessagesFilter::PhotoAndVideo,
+                message.id(),
+                -GALLERY_PAGE_SIZE / 2,
+                GALLERY_PAGE_SIZE + 1,
+            )
+            .await;
+
+        match result {
+            Ok(mut messages) => {
+                // The messages are found from the newest to the oldest
+                messages.reverse();
+
+                // The message may not be part of the gallery, e.g. if it's an animation
+                let Some(index) = messages.iter().position(|m| m == message) else {
+                    imp.has_older.set(false);
+                    imp.has_newer.set(false);
+                    self.update_actions();
+                    return;
+                };
+
+                // Keep showing the same message, even if the user navigated in the meantime
+                let current = self.current_message();
+                imp.messages.replace(messages);
+                imp.index.set(
+                    current
+                        .and_then(|current| {
+                            imp.messages.borrow().iter().position(|m| *m == current)
+                        })
+                        .unwrap_or(index),
+                );
+
+                self.update_actions();
+                self.update_title();
+            }
+            Err(e) => log::warn!("Failed to load the media of a chat: {e:?}"),
+        }
+    }
+
+    async fn load_older(&self) {
+        let imp = self.imp();
+
+        if imp.is_loading_older.get() || !imp.has_older.get() {
+            return;
+        }
+        let (Some(chat), Some(oldest)) =
+            (imp.chat.upgrade(), imp.messages.borrow().first().cloned())
+        else {
+            return;
+        };
+
+        imp.is_loading_older.set(true);
+        let result = chat
+            .search_messages(
+                tdlib::enums::SearchMessagesFilter::PhotoAndVideo,
+                oldest.id(),
+                0,
+                GALLERY_PAGE_SIZE,
+            )
+            .await;
+        imp.is_loading_older.set(false);
+
+        match result {
+            Ok(messages) => {
+                let mut older: Vec<_> = messages
+                    .into_iter()
+                    .filter(|message| message.id() < oldest.id())
+                    .collect();
+                older.reverse();
+
+                if older.is_empty() {
+                    imp.has_older.set(false);
+                } else {
+                    imp.index.set(imp.index.get() + older.len());
+                    imp.messages.borrow_mut().splice(0..0, older);
+                }
+
+                self.update_actions();
+                self.update_title();
+            }
+            Err(e) => log::warn!("Failed to load older media of a chat: {e:?}"),
+        }
+    }
+
+    async fn load_newer(&self) {
+        let imp = self.imp();
+
+        if imp.is_loading_newer.get() || !imp.has_newer.get() {
+            return;
+        }
+        let (Some(chat), Some(newest)) =
+            (imp.chat.upgrade(), imp.messages.borrow().last().cloned())
+        else {
+            return;
+        };
+
+        imp.is_loading_newer.set(true);
+        let result = chat
+            .search_messages(
+                tdlib::enums::SearchMessagesFilter::PhotoAndVideo,
+                newest.id(),
+                -GALLERY_PAGE_SIZE,
+                GALLERY_PAGE_SIZE + 1,
+            )
+            .await;
+        imp.is_loading_newer.set(false);
+
+        match result {
+            Ok(messages) => {
+                let mut newer: Vec<_> = messages
+                    .into_iter()
+                    .filter(|message| message.id() > newest.id())
+                    .collect();
+                newer.reverse();
+
+                if newer.is_empty() {
+                    imp.has_newer.set(false);
+                } else {
+                    imp.messages.borrow_mut().extend(newer);
+                }
+
+                self.update_actions();
+                self.update_title();
+            }
+            Err(e) => log::warn!("Failed to load newer media of a chat: {e:?}"),
+        }
+    }
+
+    fn show_previous(&self) {
+        let imp = self.imp();
+
+        let index = imp.index.get();
+        if index == 0 {
+            return;
+        }
+
+        imp.index.set(index - 1);
+        self.show_current();
+
+        if index - 1 < 2 {
+            utils::spawn(clone!(@weak self as obj => async move {
+                obj.load_older().await;
+            }));
+        }
+    }
+
+    fn show_next(&self) {
+        let imp = self.imp();
+
+        let index = imp.index.get();
+        let len = imp.messages.borrow().len();
+        if index + 1 >= len {
+            return;
+        }
+
+        imp.index.set(index + 1);
+        self.show_current();
+
+        if index + 3 >= len {
+            utils::spawn(clone!(@weak self as obj => async move {
+                obj.load_newer().await;
+            }));
+        }
+    }
+
+    fn update_actions(&self) {
+        let imp = self.imp();
+
+        let index = imp.index.get();
+        let len = imp.messages.borrow().len();
+        self.action_set_enabled("media-viewer.previous", index > 0);
+        self.action_set_enabled("media-viewer.next", index + 1 < len);
+
+        let is_image = imp.stack.visible_child_name().as_deref() == Some("image");
+        let has_texture = is_image && imp.picture.texture().is_some();
+        self.action_set_enabled(
+            "media-viewer.zoom-in",
+            has_texture && imp.picture.can_zoom_in(),
+        );
+        self.action_set_enabled(
+            "media-viewer.zoom-out",
+            has_texture && imp.picture.can_zoom_out(),
+        );
+        self.action_set_enabled("media-viewer.rotate-left", has_texture);
+        self.action_set_enabled("media-viewer.rotate-right", has_texture);
+        self.action_set_enabled("media-viewer.copy", has_texture);
+        self.action_set_enabled("media-viewer.save", imp.path.borrow().is_some());
+    }
+
+    fn update_title(&self) {
+        let imp = self.imp();
+
+        let Some(message) = self.current_message() else {
+            return;
+        };
+
+        imp.window_title
+            .set_title(&strings::message_sender(&message.sender(), true));
+
+        let date = glib::DateTime::from_unix_local(message.date() as i64)
+            .and_then(|date| date.format("%x %X"))
+            .map(String::from)
+            .unwrap_or_default();
+
+        // The total number of photos and videos isn't known, only show the position when all
+        // of them have been loaded
+        if imp.has_older.get() || imp.has_newer.get() {
+            imp.window_title.set_subtitle(&date);
+        } else {
+            imp.window_title.set_subtitle(&format!(
+                "{date} · {}/{}",
+                imp.index.get() + 1,
+                imp.messages.borrow().len()
+            ));
+        }
+    }
+
+    fn show_current(&self) {
+        use tdlib::enums::MessageContent::*;
+
+        let imp = self.imp();
+
+        let Some(message) = self.current_message() else {
+            return;
+        };
+
+        imp.generation.set(imp.generation.get().wrapping_add(1));
+        imp.path.replace(None);
+        imp.picture.set_texture(None);
+        if let Some(media) = imp.video.media_stream() {
+            media.pause();
+        }
+        imp.video.set_media_stream(gtk::MediaStream::NONE);
+
+        self.update_title();
+
+        let (caption, file, minithumbnail, is_video, is_animation) = match message.content().0 {
+            MessagePhoto(data) => {
+                let Some(size) = data.photo.sizes.into_iter().last() else {
+                    return;
+                };
+                (
+                    data.caption,
+                    size.photo,
+                    data.photo.minithumbnail,
+                    false,
+                    false,
+                )
+            }
+            MessageVideo(data) => (
+                data.caption,
+                data.video.video,
+                data.video.minithumbnail,
+                true,
+                false,
+            ),
+            MessageAnimation(data) => (
+                data.caption,
+                data.animation.animation,
+                data.animation.minithumbnail,
+                true,
+                true,
+            ),
+            _ => return,
+        };
+
+        let caption = utils::parse_formatted_text(caption);
+        imp.caption_label.set_markup(&caption);
+        imp.caption_label.set_visible(!caption.is_empty());
+
+        let media_stream = imp
+            .media_stream
+            .borrow()
+            .as_ref()
+            .filter(|(message_id, _)| *message_id == message.id())
+            .map(|(_, media)| media.clone());
+
+        if let Some(media) = media_stream {
+            if file.local.is_downloading_completed {
+                imp.path.replace(Some(file.local.path));
+            }
+            imp.video.set_media_stream(Some(&media));
+            media.play();
+            imp.stack.set_visible_child_name("video");
+        } else if file.local.is_downloading_completed {
+            self.load_file(file.local.path, is_video, is_animation);
+        } else {
+            if !is_video {
+                // Show a blurred preview of the photo while it's being downloaded
+                let minithumbnail = minithumbnail.and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                });
+                imp.picture.set_texture(minithumbnail.as_ref());
+            }
+
+            imp.progress_bar.set_fraction(0.0);
+            imp.stack
+                .set_visible_child_name(if is_video { "loading" } else { "image" });
+
+            let generation = imp.generation.get();
+            message.chat_().session_().download_file_with_updates(
+                file.id,
+                clone!(@weak self as obj => move |file| {
+                    let imp = obj.imp();
+                    if imp.generation.get() != generation {
+                        return;
+                    }
+
+                    if file.local.is_downloading_completed {
+                        obj.load_file(file.local.path, is_video, is_animation);
+                    } else if file.size > 0 {
+                        imp.progress_bar.set_fraction(
+                            file.local.downloaded_size as f64 / file.size as f64,
+                        );
+                    }
+                }),
+            );
+        }
+
+        self.update_actions();
+    }
+
+    fn load_file(&self, path: String, is_video: bool, is_animation: bool) {
+        let imp = self.imp();
+
+        imp.path.replace(Some(path.clone()));
+
+        if is_video {
+            let media = gtk::MediaFile::for_filename(&path);
+            media.set_loop(is_animation);
+            media.set_muted(is_animation);
+            imp.video.set_media_stream(Some(&media));
+            imp.stack.set_visible_child_name("video");
+            self.update_actions();
+        } else {
+            let generation = imp.generation.get();
+            utils::spawn(clone!(@weak self as obj => async move {
+                let result = gio::spawn_blocking(move || utils::decode_image_from_path(&path))
+                    .await
+                    .unwrap();
+
+                let imp = obj.imp();
+                if imp.generation.get() != generation {
+                    return;
+                }
+
+                match result {
+                    Ok(texture) => {
+                        imp.picture.set_texture(Some(texture.upcast_ref()));
+                        imp.stack.set_visible_child_name("image");
+                    }
+                    Err(e) => log::warn!("Error decoding a photo: {e:?}"),
+                }
+                obj.update_actions();
+            }));
+        }
+    }
+
+    fn copy(&self) {
+        let imp = self.imp();
+
+        if let Some(texture) = imp.picture.texture() {
+            self.clipboard().set_texture(&texture);
+            imp.toast_overlay
+                .add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+        }
+    }
+
+    async fn save(&self) {
+        use tdlib::enums::MessageContent::*;
+
+        let imp = self.imp();
+
+        let (Some(path), Some(message)) = (imp.path.borrow().clone(), self.current_message())
+        else {
+            return;
+        };
+
+        let file_name = match message.content().0 {
+            MessageVideo(data) if !data.video.file_name.is_empty() => data.video.file_name,
+            MessageAnimation(data) if !data.animation.file_name.is_empty() => {
+                data.animation.file_name
+            }
+            MessagePhoto(_) => format!("photo_{}.jpg", message.id()),
+            _ => format!("video_{}.mp4", message.id()),
+        };
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Save As"))
+            .initial_name(file_name)
+            .modal(true)
+            .build();
+
+        let Some(destination) = dialog
+            .save_future(Some(self))
+            .await
+            .ok()
+            .and_then(|file| file.path())
+        else {
+            return;
+        };
+
+        let result = gio::spawn_blocking(move || std::fs::copy(path, destination))
+            .await
+            .map_err(|_| "The copying thread panicked".to_string())
+            .and_then(|result| result.map_err(|e| e.to_string()));
+
+        let toast = match result {
+            Ok(_) => gettext("Saved"),
+            Err(e) => {
+                log::warn!("Error saving a media file: {}", e);
+                gettext("Could not save the file")
+            }
+        };
+        imp.toast_overlay.add_toast(adw::Toast::new(&toast));
+    }
+}
@@ -0,0 +1,292 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::ui::MessageBaseExt;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/audio.ui")]
+    pub(crate) struct MessageAudio {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) player_signal_group: OnceCell<glib::SignalGroup>,
+        /// The duration of the audio file, in seconds
+        pub(super) duration: Cell<i32>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<ui::MessageBubble>,
+        #[template_child]
+        pub(super) cover_picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) performer_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) progress_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub(super) duration_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageAudio {
+        const NAME: &'static str = "PaplMessageAudio";
+        type Type = super::MessageAudio;
+        type ParentType = ui::MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageAudio {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.upgrade().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let player_signal_group = glib::SignalGroup::new::<model::AudioPlayer>();
+            for property in ["message", "playing", "position", "duration"] {
+                player_signal_group.connect_notify_local(
+                    Some(property),
+                    clone!(@weak obj => move |_, _| {
+                        obj.update_playback_state();
+                    }),
+                );
+            }
+            self.player_signal_group.set(player_signal_group).unwrap();
+
+            self.progress_scale.connect_change_value(
+                clone!(@weak obj => @default-return glib::Propagation::Proceed,
+                    move |_, _, value| {
+                        if let Some(player) = obj.active_player() {
+                            player.seek(value);
+                        }
+                        glib::Propagation::Proceed
+                    }
+                ),
+            );
+        }
+    }
+
+    impl WidgetImpl for MessageAudio {}
+    impl ui::MessageBaseImpl for MessageAudio {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageAudio(ObjectSubclass<imp::MessageAudio>)
+        @extends gtk::Widget, ui::MessageBase;
+}
+
+impl ui::MessageBaseExt for MessageAudio {
+    type Message = model::Message;
+
+    fn set_message(&self, message: &Self::Message) {
+        let imp = self.imp();
+
+        let old_message = imp.message.upgrade();
+        if old_message.as_ref() == Some(message) {
+            return;
+        }
+
+        if let Some(old_message) = old_message {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+
+        imp.message.set(Some(message));
+
+        self.update_content(message);
+        imp.player_signal_group
+            .get()
+            .unwrap()
+            .set_target(Some(&message.chat_().session_().audio_player()));
+        self.update_playback_state();
+
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageAudio {
+    fn update_content(&self, message: &model::Message) {
+        if let tdlib::enums::MessageContent::MessageAudio(data) = message.content().0 {
+            let imp = self.imp();
+
+            imp.message_bubble.set_text(data.caption);
+
+            let audio = data.audio;
+
+            imp.title_label.set_label(if audio.title.is_empty() {
+                &audio.file_name
+            } else {
+                &audio.title
+            });
+            if audio.performer.is_empty() {
+                imp.performer_label.set_label(&gettext("Unknown Artist"));
+            } else {
+                imp.performer_label.set_label(&audio.performer);
+            }
+
+            imp.duration.set(audio.duration);
+
+            match audio.album_cover_thumbnail {
+                Some(thumbnail) if thumbnail.file.local.is_downloading_completed => {
+                    imp.cover_picture
+                        .set_filename(Some(&thumbnail.file.local.path));
+                }
+                thumbnail => {
+                    imp.cover_picture.set_paintable(
+                        audio
+                            .album_cover_minithumbnail
+                            .and_then(|m| {
+                                gdk::Texture::from_bytes(&glib::Bytes::from_owned(
+                                    glib::base64_decode(&m.data),
+                                ))
+                                .ok()
+                            })
+                            .as_ref(),
+                    );
+
+                    if let Some(thumbnail) = thumbnail {
+                        let session = message.chat_().session_();
+                        utils::spawn(clone!(@weak self as obj, @weak message => async move {
+                            match session.download_file(thumbnail.file.id).await {
+                                Ok(file) => {
+                                    let imp = obj.imp();
+                                    // The row could have been recycled for another message
+                                    if imp.message.upgrade().as_ref() == Some(&message) {
+                                        imp.cover_picture.set_filename(Some(&file.local.path));
+                                    }
+                                }
+                                Err(e) => log::warn!("Failed to download an album cover: {e:?}"),
+                            }
+                        }));
+                    }
+                }
+            }
+
+            self.update_playback_state();
+        }
+    }
+
+    /// Returns the audio player of the session, if it's playing this audio file.
+    fn active_player(&self) -> Option<model::AudioPlayer> {
+        let imp = self.imp();
+
+        let player = imp
+            .player_signal_group
+            .get()
+            .unwrap()
+            .target()
+            .and_downcast::<model::AudioPlayer>()?;
+
+        if player.message().is_some() && player.message() == imp.message.upgrade() {
+            Some(player)
+        } else {
+            None
+        }
+    }
+
+    fn update_playback_state(&self) {
+        let imp = self.imp();
+
+        match self.active_player() {
+            Some(player) => {
+                imp.progress_scale.set_visible(true);
+                imp.progress_scale.set_value(player.progress());
+                imp.duration_label.set_label(&format!(
+                    "{} / {}",
+                    utils::format_duration(player.position() / 1000),
+                    utils::format_duration(player.duration() / 1000)
+                ));
+                self.update_play_button(player.playing());
+            }
+            None => {
+                imp.progress_scale.set_visible(false);
+                imp.duration_label
+                    .set_label(&utils::format_duration(imp.duration.get().max(0) as u64));
+                self.update_play_button(false);
+            }
+        }
+    }
+
+    fn update_play_button(&self, playing: bool) {
+        let imp = self.imp();
+
+        if playing {
+            imp.play_button
+                .set_icon_name("media-playback-pause-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Pause")));
+        } else {
+            imp.play_button
+                .set_icon_name("media-playback-start-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Play")));
+        }
+    }
+
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        if let Some(message) = self.imp().message.upgrade() {
+            let audio_player = message.chat_().session_().audio_player();
+            if audio_player.is_available() {
+                audio_player.play_message(&message);
+            } else {
+                utils::show_toast(self, gettext("Audio files can't be played on this system"));
+            }
+        }
+    }
+}
@@ -4,6 +4,7 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
+use crate::model;
 use crate::ui;
 use crate::utils;
 
@@ -39,8 +40,42 @@ mod imp {
         }
 
         #[template_callback]
-        fn on_long_pressed(&self, x: f64, y: f64) {
-            self.show_message_menu(x as i32, y as i32);
+        fn on_primary_pressed(gesture: &gtk::GestureClick, _: i32, _: f64, _: f64) {
+            let Some(obj) = gesture.widget().and_downcast::<super::MessageBase>() else {
+                return;
+            };
+            let chat_history = utils::ancestor::<_, ui::ChatHistory>(&obj);
+
+            if chat_history.selection_mode()
+                || gesture
+                    .current_event_state()
+                    .contains(gdk::ModifierType::CONTROL_MASK)
+            {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                obj.imp().toggle_selection(&chat_history);
+            }
+        }
+
+        #[template_callback]
+        fn on_long_pressed(gesture: &gtk::GestureLongPress, _x: f64, _y: f64) {
+            let Some(obj) = gesture.widget().and_downcast::<super::MessageBase>() else {
+                return;
+            };
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+
+            // This enters the selection mode with this message selected if it isn't active yet
+            let chat_history = utils::ancestor::<_, ui::ChatHistory>(&obj);
+            obj.imp().toggle_selection(&chat_history);
+        }
+
+        fn toggle_selection(&self, chat_history: &ui::ChatHistory) {
+            if let Some(message) = self
+                .obj()
+                .property::<Option<glib::Object>>("message")
+                .and_downcast::<model::Message>()
+            {
+                chat_history.toggle_message_selection(message.id());
+            }
         }
 
         fn show_message_menu(&self, x: i32, y: i32) {
@@ -48,6 +83,22 @@ mod imp {
             let chat_history = utils::ancestor::<_, ui::ChatHistory>(obj);
             let menu = chat_history.message_menu();
 
+            // The message or the chat may have changed since the actions were last updated,
+            // e.g. when the message has been pinned
+            if let Some(row) = obj
+                .ancestor(ui::MessageRow::static_type())
+                .and_downcast::<ui::MessageRow>()
+            {
+                row.update_actions();
+            }
+
+            let message = obj
+                .property::<Option<glib::Object>>("message")
+                .and_downcast::<model::Message>();
+            chat_history
+                .message_reaction_picker()
+                .set_message(message.as_ref());
+
             menu.set_pointing_to(Some(&gdk::Rectangle::new(x, y, 0, 0)));
             menu.unparent();
             menu.set_parent(obj);
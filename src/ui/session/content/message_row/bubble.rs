@@ -1,3 +1,4 @@
+use std::cell::OnceCell;
 use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hash;
@@ -5,10 +6,14 @@ use std::hash::Hasher;
 use std::sync::OnceLock;
 
 use adw::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
 use gtk::glib;
+use gtk::pango;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
+use crate::i18n::ngettext_f;
 use crate::model;
 use crate::ui;
 use crate::utils;
@@ -32,6 +37,9 @@ mod imp {
     pub(crate) struct MessageBubble {
         pub(super) sender_color_class: RefCell<Option<String>>,
         pub(super) sender_binding: RefCell<Option<gtk::ExpressionWatch>>,
+        pub(super) session: glib::WeakRef<model::ClientStateSession>,
+        pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) interaction_info_signal_group: OnceCell<glib::SignalGroup>,
         #[template_child]
         pub(super) box_: TemplateChild<gtk::Box>,
         #[template_child]
@@ -43,11 +51,19 @@ mod imp {
         #[template_child]
         pub(super) prefix_bin: TemplateChild<adw::Bin>,
         #[template_child]
+        pub(super) blocks_box: TemplateChild<gtk::Box>,
+        #[template_child]
         pub(super) message_label: TemplateChild<ui::MessageLabel>,
         #[template_child]
         pub(super) indicators: TemplateChild<ui::MessageIndicators>,
         #[template_child]
+        pub(super) reactions: TemplateChild<ui::MessageReactions>,
+        #[template_child]
+        pub(super) thread_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub(super) suffix_bin: TemplateChild<adw::Bin>,
+        #[template_child]
+        pub(super) footer_bin: TemplateChild<adw::Bin>,
     }
 
     #[glib::object_subclass]
@@ -78,6 +94,9 @@ mod imp {
                     glib::ParamSpecObject::builder::<gtk::Widget>("suffix")
                         .write_only()
                         .build(),
+                    glib::ParamSpecObject::builder::<gtk::Widget>("footer")
+                        .write_only()
+                        .build(),
                 ]
             })
         }
@@ -89,10 +108,29 @@ mod imp {
                 "prefix" => obj.set_prefix(value.get().unwrap()),
                 "label" => obj.set_label(value.get().unwrap()),
                 "suffix" => obj.set_suffix(value.get().unwrap()),
+                "footer" => obj.set_footer(value.get().unwrap()),
                 _ => unimplemented!(),
             }
         }
 
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let interaction_info_signal_group =
+                glib::SignalGroup::new::<model::MessageInteractionInfo>();
+            interaction_info_signal_group.connect_notify_local(
+                Some("reply-count"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_thread_button();
+                }),
+            );
+            self.interaction_info_signal_group
+                .set(interaction_info_signal_group)
+                .unwrap();
+        }
+
         fn dispose(&self) {
             utils::unparent_children(&*self.obj());
         }
@@ -137,6 +175,20 @@ impl MessageBubble {
         let imp = self.imp();
 
         imp.indicators.set_message(message.upcast_ref());
+        imp.reactions.set_message(Some(message));
+
+        imp.session.set(Some(&message.chat_().session_()));
+
+        // The spoilers revealed in the previous message of a recycled row are hidden again
+        if imp.message.upgrade().as_ref() != Some(message) {
+            imp.message_label.hide_spoilers();
+        }
+        imp.message.set(Some(message));
+        imp.interaction_info_signal_group
+            .get()
+            .unwrap()
+            .set_target(Some(&message.interaction_info()));
+        self.update_thread_button();
 
         let is_channel = if let model::ChatType::Supergroup(data) = message.chat_().chat_type() {
             data.is_channel()
@@ -223,6 +275,17 @@ impl MessageBubble {
         let imp = self.imp();
 
         imp.indicators.set_message(sponsored_message.upcast_ref());
+        imp.reactions.set_message(None);
+
+        imp.session.set(Some(&sponsored_message.chat().session_()));
+
+        imp.message_label.hide_spoilers();
+        imp.message.set(None);
+        imp.interaction_info_signal_group
+            .get()
+            .unwrap()
+            .set_target(Option::<&model::MessageInteractionInfo>::None);
+        self.update_thread_button();
 
         self.remove_css_class("outgoing");
 
@@ -240,23 +303,131 @@ impl MessageBubble {
         imp.sender_label.set_visible(true);
     }
 
+    /// Shows a button to open the replies to the message, or the comments of a channel post.
+    fn update_thread_button(&self) {
+        let imp = self.imp();
+
+        let Some(message) = imp
+            .message
+            .upgrade()
+            .filter(model::Message::can_get_message_thread)
+        else {
+            imp.thread_button.set_visible(false);
+            return;
+        };
+
+        let is_channel = matches!(
+            message.chat_().chat_type(),
+            model::ChatType::Supergroup(data) if data.is_channel()
+        );
+        let reply_count = message.interaction_info().reply_count();
+        let num = reply_count.to_string();
+
+        let label = if is_channel {
+            if reply_count == 0 {
+                gettext("Leave a Comment")
+            } else {
+                ngettext_f(
+                    "{num} Comment",
+                    "{num} Comments",
+                    reply_count,
+                    &[("num", &num)],
+                )
+            }
+        } else if reply_count > 0 {
+            ngettext_f(
+                "{num} Reply",
+                "{num} Replies",
+                reply_count,
+                &[("num", &num)],
+            )
+        } else {
+            imp.thread_button.set_visible(false);
+            return;
+        };
+
+        imp.thread_button.set_label(&label);
+        imp.thread_button
+            .set_action_target_value(Some(&message.id().to_variant()));
+        imp.thread_button.set_visible(true);
+    }
+
     pub(crate) fn set_prefix(&self, prefix: Option<&gtk::Widget>) {
         self.imp().prefix_bin.set_child(prefix);
     }
 
+    /// Sets the label to markup, without text entities.
     pub(crate) fn set_label(&self, label: String) {
+        self.set_text_blocks(Vec::new());
+        self.update_label(label, &[]);
+    }
+
+    /// Sets the label to a formatted text. Its code blocks and quotes are shown apart, above
+    /// the remaining text.
+    pub(crate) fn set_text(&self, text: tdlib::types::FormattedText) {
+        let mut blocks = utils::text_blocks(text);
+        let last_text = match blocks.pop() {
+            Some(utils::TextBlock::Text(text)) => Some(text),
+            Some(block) => {
+                blocks.push(block);
+                None
+            }
+            None => None,
+        };
+
+        self.set_text_blocks(blocks);
+
+        match last_text {
+            Some(text) => {
+                let entities = text.entities.clone();
+                self.update_label(utils::parse_formatted_text(text), &entities);
+            }
+            None => self.update_label(String::new(), &[]),
+        }
+    }
+
+    fn set_text_blocks(&self, blocks: Vec<utils::TextBlock>) {
         let imp = self.imp();
 
-        if label.is_empty() {
-            imp.message_label.set_label(String::new());
-            imp.message_label.set_visible(false);
+        while let Some(child) = imp.blocks_box.first_child() {
+            imp.blocks_box.remove(&child);
+        }
 
-            self.remove_css_class("with-label");
-        } else {
-            imp.message_label.set_label(label);
-            imp.message_label.set_visible(true);
+        let session = imp.session.upgrade();
+        for block in blocks {
+            let widget = match block {
+                utils::TextBlock::Text(text) => new_text_block(text, session.as_ref()),
+                utils::TextBlock::Quote(text) => {
+                    let widget = new_text_block(text, session.as_ref());
+                    widget.add_css_class("quote");
+                    widget
+                }
+                utils::TextBlock::Code { text, language } => new_code_block(text, language),
+            };
+            imp.blocks_box.append(&widget);
+        }
+
+        imp.blocks_box
+            .set_visible(imp.blocks_box.first_child().is_some());
+    }
+
+    fn update_label(&self, label: String, entities: &[tdlib::types::TextEntity]) {
+        let imp = self.imp();
 
+        if let Some(session) = imp.session.upgrade() {
+            imp.message_label.set_entities(entities, &session);
+        }
+
+        // The label still holds the indicators below the text blocks
+        let has_text = !label.is_empty() || imp.blocks_box.is_visible();
+
+        imp.message_label.set_label(label);
+        imp.message_label.set_visible(has_text);
+
+        if has_text {
             self.add_css_class("with-label");
+        } else {
+            self.remove_css_class("with-label");
         }
 
         self.update_indicators_position();
@@ -270,6 +441,11 @@ impl MessageBubble {
         self.imp().suffix_bin.set_child(prefix);
     }
 
+    /// Sets the widget shown below the label, like the preview of a link.
+    pub(crate) fn set_footer(&self, footer: Option<&gtk::Widget>) {
+        self.imp().footer_bin.set_child(footer);
+    }
+
     fn update_sender_color(&self, sender_id: Option<i64>) {
         let imp = self.imp();
 
@@ -289,11 +465,10 @@ impl MessageBubble {
     fn update_indicators_position(&self) {
         let imp = self.imp();
 
-        if imp.message_label.label().is_empty() && imp.message_label.indicators().is_some() {
+        if !imp.message_label.is_visible() && imp.message_label.indicators().is_some() {
             imp.message_label.set_indicators(None);
             imp.overlay.add_overlay(&*imp.indicators);
-        } else if !imp.message_label.label().is_empty() && imp.message_label.indicators().is_none()
-        {
+        } else if imp.message_label.is_visible() && imp.message_label.indicators().is_none() {
             imp.overlay.remove_overlay(&*imp.indicators);
             imp.message_label
                 .set_indicators(Some(imp.indicators.clone()));
@@ -306,3 +481,62 @@ fn hash_label(label: &str) -> i64 {
     label.hash(&mut hasher);
     hasher.finish() as i64
 }
+
+fn new_text_block(
+    text: tdlib::types::FormattedText,
+    session: Option<&model::ClientStateSession>,
+) -> ui::MessageLabel {
+    let label: ui::MessageLabel = glib::Object::new();
+    if let Some(session) = session {
+        label.set_entities(&text.entities, session);
+    }
+    label.set_label(utils::parse_formatted_text(text));
+    label
+}
+
+fn new_code_block(text: String, language: String) -> gtk::Widget {
+    let content_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .hexpand(true)
+        .build();
+
+    if !language.is_empty() {
+        content_box.append(
+            &gtk::Label::builder()
+                .label(&language)
+                .xalign(0.0)
+                .css_classes(["caption-heading", "dim-label"])
+                .build(),
+        );
+    }
+
+    content_box.append(
+        &gtk::Label::builder()
+            .label(&text)
+            .selectable(true)
+            .wrap(true)
+            .wrap_mode(pango::WrapMode::WordChar)
+            .xalign(0.0)
+            .css_classes(["monospace"])
+            .build(),
+    );
+
+    let copy_button = gtk::Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text(gettext("Copy"))
+        .valign(gtk::Align::Start)
+        .css_classes(["flat", "circular"])
+        .build();
+    copy_button.connect_clicked(move |button| {
+        button.clipboard().set_text(&text);
+        utils::show_toast(button, gettext("Copied to clipboard"));
+    });
+
+    let code_block = gtk::Box::builder()
+        .spacing(6)
+        .css_classes(["code-block"])
+        .build();
+    code_block.append(&content_box);
+    code_block.append(&copy_button);
+    code_block.upcast()
+}
@@ -131,8 +131,7 @@ impl MessageDocument {
         if let tdlib::enums::MessageContent::MessageDocument(data) = message.content().0 {
             let imp = self.imp();
 
-            let message_text = utils::parse_formatted_text(data.caption);
-            imp.message_bubble.set_label(message_text);
+            imp.message_bubble.set_text(data.caption);
 
             imp.file_name_label.set_label(&data.document.file_name);
 
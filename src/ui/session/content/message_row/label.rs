@@ -1,17 +1,34 @@
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::sync::OnceLock;
 
+use glib::clone;
 use gtk::glib;
+use gtk::graphene;
+use gtk::gsk;
 use gtk::pango;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
+use crate::model;
 use crate::ui;
+use crate::utils;
 
 const OBJECT_REPLACEMENT_CHARACTER: char = '\u{FFFC}';
 const INDICATORS_SPACING: i32 = 6;
 
+/// A custom emoji shown in place of its fallback emoji.
+#[derive(Debug)]
+struct CustomEmoji {
+    /// The offset of the fallback emoji in UTF-16 code units
+    offset: usize,
+    /// The length of the fallback emoji in UTF-16 code units
+    length: usize,
+    custom_emoji_id: i64,
+    sticker: ui::Sticker,
+}
+
 mod imp {
     use super::*;
 
@@ -21,6 +38,14 @@ mod imp {
         pub(super) text: RefCell<String>,
         pub(super) indicators: RefCell<Option<ui::MessageIndicators>>,
         pub(super) indicators_size: RefCell<Option<(i32, i32)>>,
+        pub(super) custom_emojis: RefCell<Vec<CustomEmoji>>,
+        /// The size of the custom emoji in pixels, depending on the font of the label
+        pub(super) custom_emoji_size: Cell<i32>,
+        /// Used to discard the custom emoji stickers of outdated requests
+        pub(super) custom_emojis_generation: Cell<u32>,
+        /// The offsets and the lengths of the spoilers, in UTF-16 code units
+        pub(super) spoilers: RefCell<Vec<(usize, usize)>>,
+        pub(super) are_spoilers_revealed: Cell<bool>,
         #[template_child]
         pub(super) label: TemplateChild<gtk::Label>,
     }
@@ -76,11 +101,36 @@ mod imp {
             }
         }
 
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            self.label.connect_activate_link(
+                clone!(@weak obj => @default-return glib::Propagation::Proceed, move |_, uri| {
+                    obj.activate_link(uri)
+                }),
+            );
+
+            // Spoilers are revealed by clicking them, before the label handles the click
+            let gesture = gtk::GestureClick::new();
+            gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+            gesture.connect_pressed(clone!(@weak obj => move |gesture, _, x, y| {
+                if obj.reveal_spoiler_at(x, y) {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                }
+            }));
+            obj.add_controller(gesture);
+        }
+
         fn dispose(&self) {
             self.label.unparent();
             if let Some(indicators) = self.indicators.take() {
                 indicators.unparent();
             }
+            for custom_emoji in self.custom_emojis.take() {
+                custom_emoji.sticker.unparent();
+            }
         }
     }
 
@@ -88,6 +138,13 @@ mod imp {
         fn measure(&self, orientation: gtk::Orientation, for_size: i32) -> (i32, i32, i32, i32) {
             let obj = self.obj();
 
+            // The size of the custom emoji follows the font size, which may have changed
+            if !self.custom_emojis.borrow().is_empty()
+                && obj.custom_emoji_metrics().1 != self.custom_emoji_size.get()
+            {
+                obj.update_label_attributes();
+            }
+
             if let Some(indicators) = self.indicators.borrow().as_ref() {
                 let (_, indicators_size) = indicators.preferred_size();
                 let old = self
@@ -98,10 +155,10 @@ mod imp {
                     if indicators_size.width() != old_indicators_size.0
                         || indicators_size.height() != old_indicators_size.1
                     {
-                        obj.update_label_attributes(&indicators_size);
+                        obj.update_label_attributes();
                     }
                 } else {
-                    obj.update_label_attributes(&indicators_size);
+                    obj.update_label_attributes();
                 }
 
                 let (mut minimum, mut natural, minimum_baseline, natural_baseline) =
@@ -128,6 +185,7 @@ mod imp {
             if let Some(indicators) = self.indicators.borrow().as_ref() {
                 indicators.allocate(width, height, baseline, None);
             }
+            self.obj().allocate_custom_emojis();
         }
 
         fn request_mode(&self) -> gtk::SizeRequestMode {
@@ -146,27 +204,255 @@ glib::wrapper! {
 }
 
 impl MessageLabel {
-    fn update_label_attributes(&self, indicators_size: &gtk::Requisition) {
+    fn update_label_attributes(&self) {
         let imp = self.imp();
-        if let Some(start_index) = imp.label.text().find(OBJECT_REPLACEMENT_CHARACTER) {
-            let attrs = pango::AttrList::new();
-            let width = indicators_size.width() + INDICATORS_SPACING;
-            let height = indicators_size.height();
-            let logical_rect = pango::Rectangle::new(
-                0,
-                -(height - (height / 4)) * pango::SCALE,
-                width * pango::SCALE,
-                height * pango::SCALE,
-            );
-            let mut shape = pango::AttrShape::new(&logical_rect, &logical_rect);
+        let text = imp.label.text();
+        let attrs = pango::AttrList::new();
+        let mut has_attrs = false;
 
-            shape.set_start_index(start_index as u32);
-            shape.set_end_index((start_index + OBJECT_REPLACEMENT_CHARACTER.len_utf8()) as u32);
-            attrs.insert(shape);
+        if let Some(indicators) = imp.indicators.borrow().as_ref() {
+            if let Some(start_index) = text.find(OBJECT_REPLACEMENT_CHARACTER) {
+                let (_, indicators_size) = indicators.preferred_size();
+                let width = indicators_size.width() + INDICATORS_SPACING;
+                let height = indicators_size.height();
+                let logical_rect = pango::Rectangle::new(
+                    0,
+                    -(height - (height / 4)) * pango::SCALE,
+                    width * pango::SCALE,
+                    height * pango::SCALE,
+                );
+                let mut shape = pango::AttrShape::new(&logical_rect, &logical_rect);
+
+                shape.set_start_index(start_index as u32);
+                shape.set_end_index((start_index + OBJECT_REPLACEMENT_CHARACTER.len_utf8()) as u32);
+                attrs.insert(shape);
+                has_attrs = true;
+            }
+        }
+
+        let custom_emojis = imp.custom_emojis.borrow();
+        if !custom_emojis.is_empty() {
+            let (ascent, size) = self.custom_emoji_metrics();
+            imp.custom_emoji_size.set(size);
+
+            // Reserve the space of the custom emoji in place of the first character of their
+            // fallback emoji, and hide the remaining ones
+            let logical_rect =
+                pango::Rectangle::new(0, -ascent, size * pango::SCALE, size * pango::SCALE);
+            let empty_rect = pango::Rectangle::new(0, 0, 0, 0);
+
+            for custom_emoji in custom_emojis.iter() {
+                if custom_emoji.sticker.longer_side_size() != size {
+                    custom_emoji.sticker.set_longer_side_size(size);
+                }
 
-            imp.label.set_attributes(Some(&attrs));
+                let Some((start_index, end_index)) =
+                    byte_range(&text, custom_emoji.offset, custom_emoji.length)
+                else {
+                    continue;
+                };
+                let first_char_end_index = start_index
+                    + text[start_index..]
+                        .chars()
+                        .next()
+                        .map(char::len_utf8)
+                        .unwrap_or_default();
+
+                let mut shape = pango::AttrShape::new(&logical_rect, &logical_rect);
+                shape.set_start_index(start_index as u32);
+                shape.set_end_index(first_char_end_index as u32);
+                attrs.insert(shape);
+                has_attrs = true;
+
+                if first_char_end_index < end_index {
+                    let mut shape = pango::AttrShape::new(&empty_rect, &empty_rect);
+                    shape.set_start_index(first_char_end_index as u32);
+                    shape.set_end_index(end_index as u32);
+                    attrs.insert(shape);
+                }
+            }
+        }
+
+        if !imp.are_spoilers_revealed.get() {
+            let spoilers = imp.spoilers.borrow();
+            if !spoilers.is_empty() {
+                // Hide the text of the spoilers behind a translucent box of its color
+                let color = self.color();
+                let to_u16 = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+
+                for (offset, length) in spoilers.iter() {
+                    let Some((start_index, end_index)) = byte_range(&text, *offset, *length) else {
+                        continue;
+                    };
+
+                    let mut foreground_alpha = pango::AttrInt::new_foreground_alpha(1);
+                    let mut background = pango::AttrColor::new_background(
+                        to_u16(color.red()),
+                        to_u16(color.green()),
+                        to_u16(color.blue()),
+                    );
+                    let mut background_alpha =
+                        pango::AttrInt::new_background_alpha(to_u16(color.alpha() * 0.25));
+
+                    foreground_alpha.set_start_index(start_index as u32);
+                    foreground_alpha.set_end_index(end_index as u32);
+                    background.set_start_index(start_index as u32);
+                    background.set_end_index(end_index as u32);
+                    background_alpha.set_start_index(start_index as u32);
+                    background_alpha.set_end_index(end_index as u32);
+
+                    attrs.insert(foreground_alpha);
+                    attrs.insert(background);
+                    attrs.insert(background_alpha);
+                    has_attrs = true;
+                }
+            }
+        }
+
+        imp.label.set_attributes(has_attrs.then_some(&attrs));
+    }
+
+    /// Returns whether the offset, in UTF-16 code units, is inside a hidden spoiler.
+    fn is_in_hidden_spoiler(&self, offset: usize) -> bool {
+        let imp = self.imp();
+        !imp.are_spoilers_revealed.get()
+            && imp
+                .spoilers
+                .borrow()
+                .iter()
+                .any(|(start, length)| *start <= offset && offset < start + length)
+    }
+
+    /// Hides the revealed spoilers again.
+    pub(crate) fn hide_spoilers(&self) {
+        let imp = self.imp();
+
+        if imp.are_spoilers_revealed.get() {
+            imp.are_spoilers_revealed.set(false);
+            self.update_label_attributes();
+            self.queue_allocate();
+        }
+    }
+
+    /// Reveals the spoilers if one of them is at the point, returning whether it's the case.
+    fn reveal_spoiler_at(&self, x: f64, y: f64) -> bool {
+        let imp = self.imp();
+
+        // Previews of single lines are activated as a whole instead
+        if imp.are_spoilers_revealed.get()
+            || imp.spoilers.borrow().is_empty()
+            || imp.label.is_single_line_mode()
+        {
+            return false;
+        }
+
+        let layout = imp.label.layout();
+        let (offset_x, offset_y) = imp.label.layout_offsets();
+        let (is_inside, index, _) = layout.xy_to_index(
+            (x as i32 - offset_x) * pango::SCALE,
+            (y as i32 - offset_y) * pango::SCALE,
+        );
+        if !is_inside {
+            return false;
+        }
+
+        let text = imp.label.text();
+        let Some(prefix) = text.get(..index as usize) else {
+            return false;
+        };
+        if !self.is_in_hidden_spoiler(prefix.encode_utf16().count()) {
+            return false;
+        }
+
+        imp.are_spoilers_revealed.set(true);
+        self.update_label_attributes();
+        self.queue_allocate();
+
+        true
+    }
+
+    fn activate_link(&self, uri: &str) -> glib::Propagation {
+        let result = if let Some(username) = uri.strip_prefix(utils::USERNAME_URI_PREFIX) {
+            self.activate_action("chat-history.open-username", Some(&username.to_variant()))
+        } else if let Some(user_id) = uri.strip_prefix(utils::USER_ID_URI_PREFIX) {
+            match user_id.parse::<i64>() {
+                Ok(user_id) => {
+                    self.activate_action("chat-history.open-user", Some(&user_id.to_variant()))
+                }
+                Err(_) => return glib::Propagation::Stop,
+            }
+        } else if let Some(hashtag) = uri.strip_prefix(utils::HASHTAG_URI_PREFIX) {
+            self.activate_action("chat-history.search-text", Some(&hashtag.to_variant()))
+        } else if let Some(command) = uri.strip_prefix(utils::BOT_COMMAND_URI_PREFIX) {
+            self.activate_action("chat-history.send-bot-command", Some(&command.to_variant()))
         } else {
-            imp.label.set_attributes(None);
+            return glib::Propagation::Proceed;
+        };
+
+        if let Err(e) = result {
+            log::warn!("Failed to activate a link: {e:?}");
+        }
+
+        glib::Propagation::Stop
+    }
+
+    /// Returns the ascent, in pango units, and the size, in pixels, of the custom emoji,
+    /// which match the ones of the font of the label.
+    fn custom_emoji_metrics(&self) -> (i32, i32) {
+        let metrics = self.imp().label.pango_context().metrics(None, None);
+        let size = (metrics.ascent() + metrics.descent()) / pango::SCALE;
+        (metrics.ascent(), size.max(1))
+    }
+
+    fn allocate_custom_emojis(&self) {
+        let imp = self.imp();
+
+        let custom_emojis = imp.custom_emojis.borrow();
+        if custom_emojis.is_empty() {
+            return;
+        }
+
+        let text = imp.label.text();
+        let layout = imp.label.layout();
+        let (offset_x, offset_y) = imp.label.layout_offsets();
+        let size = imp.custom_emoji_size.get();
+
+        for custom_emoji in custom_emojis.iter() {
+            let sticker = &custom_emoji.sticker;
+
+            let Some((start_index, _)) =
+                byte_range(&text, custom_emoji.offset, custom_emoji.length)
+                    .filter(|_| !self.is_in_hidden_spoiler(custom_emoji.offset))
+            else {
+                sticker.set_child_visible(false);
+                continue;
+            };
+
+            let rect = layout.index_to_pos(start_index as i32);
+            let x = rect.x().min(rect.x() + rect.width());
+
+            // The custom emoji may be hidden by the ellipsis
+            let is_visible = !layout.is_ellipsized() || {
+                let (is_inside, index, _) =
+                    layout.xy_to_index(x + rect.width().abs() / 2, rect.y() + rect.height() / 2);
+                is_inside && index == start_index as i32
+            };
+            sticker.set_child_visible(is_visible);
+            if !is_visible {
+                continue;
+            }
+
+            // Align the custom emoji to the bottom of its line, like its reserved space
+            let x = offset_x + x / pango::SCALE;
+            let y = offset_y + (rect.y() + rect.height()) / pango::SCALE - size;
+
+            sticker.measure(gtk::Orientation::Horizontal, -1);
+            sticker.allocate(
+                size,
+                size,
+                -1,
+                Some(gsk::Transform::new().translate(&graphene::Point::new(x as f32, y as f32))),
+            );
         }
     }
 
@@ -183,19 +469,18 @@ impl MessageLabel {
     fn update_label(&self) {
         let imp = self.imp();
         let text = imp.text.borrow();
-        if let Some(indicators) = imp.indicators.borrow().as_ref() {
+        if imp.indicators.borrow().is_some() {
             if !self.is_opposite_text_direction() {
                 imp.label
                     .set_label(&format!("{text}{OBJECT_REPLACEMENT_CHARACTER}"));
             } else {
                 imp.label.set_label(&text);
             }
-
-            let (_, indicators_size) = indicators.preferred_size();
-            self.update_label_attributes(&indicators_size);
         } else {
             imp.label.set_label(&text);
         }
+
+        self.update_label_attributes();
     }
 
     pub(crate) fn label(&self) -> String {
@@ -215,6 +500,122 @@ impl MessageLabel {
         self.imp().label.add_css_class(class);
     }
 
+    /// Draws the text entities that can't be expressed with markup: spoilers, which are
+    /// hidden until clicked, and custom emoji, which are shown in place of their fallback
+    /// emoji. The offsets of the entities must be relative to the text of the label.
+    pub(crate) fn set_entities(
+        &self,
+        entities: &[tdlib::types::TextEntity],
+        session: &model::ClientStateSession,
+    ) {
+        let imp = self.imp();
+
+        let spoilers = entities
+            .iter()
+            .filter(|entity| matches!(entity.r#type, tdlib::enums::TextEntityType::Spoiler))
+            .map(|entity| (entity.offset as usize, entity.length as usize))
+            .collect::<Vec<_>>();
+        if *imp.spoilers.borrow() != spoilers {
+            imp.spoilers.replace(spoilers);
+            imp.are_spoilers_revealed.set(false);
+            self.update_label_attributes();
+            self.queue_allocate();
+        }
+
+        let entities = entities
+            .iter()
+            .filter_map(|entity| match entity.r#type {
+                tdlib::enums::TextEntityType::CustomEmoji(ref data) => Some((
+                    entity.offset as usize,
+                    entity.length as usize,
+                    data.custom_emoji_id,
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let is_unchanged = {
+            let custom_emojis = imp.custom_emojis.borrow();
+            custom_emojis.len() == entities.len()
+                && custom_emojis
+                    .iter()
+                    .zip(&entities)
+                    .all(|(a, b)| (a.offset, a.length, a.custom_emoji_id) == *b)
+        };
+        if is_unchanged {
+            return;
+        }
+
+        let generation = imp.custom_emojis_generation.get().wrapping_add(1);
+        imp.custom_emojis_generation.set(generation);
+
+        if entities.is_empty() {
+            self.replace_custom_emojis(Vec::new());
+            return;
+        }
+
+        utils::spawn(clone!(@weak self as obj, @weak session => async move {
+            let custom_emoji_ids = entities.iter().map(|(_, _, id)| *id).collect::<Vec<_>>();
+            let result = session.custom_emoji_stickers(&custom_emoji_ids).await;
+
+            if obj.imp().custom_emojis_generation.get() != generation {
+                return;
+            }
+
+            match result {
+                Ok(stickers) => {
+                    let size = obj.custom_emoji_metrics().1;
+                    let custom_emojis = entities
+                        .into_iter()
+                        .filter_map(|(offset, length, custom_emoji_id)| {
+                            let sticker = stickers.get(&custom_emoji_id)?.clone();
+                            let sticker_widget: ui::Sticker = glib::Object::builder()
+                                .property("longer-side-size", size)
+                                .build();
+                            sticker_widget.update_sticker(sticker, true, session.clone());
+
+                            Some(CustomEmoji {
+                                offset,
+                                length,
+                                custom_emoji_id,
+                                sticker: sticker_widget,
+                            })
+                        })
+                        .collect();
+
+                    obj.replace_custom_emojis(custom_emojis);
+                }
+                Err(e) => log::warn!("Failed to load custom emoji stickers: {e:?}"),
+            }
+        }));
+    }
+
+    fn replace_custom_emojis(&self, custom_emojis: Vec<CustomEmoji>) {
+        let imp = self.imp();
+
+        for custom_emoji in &custom_emojis {
+            custom_emoji.sticker.set_parent(self);
+        }
+        for old in imp.custom_emojis.replace(custom_emojis) {
+            old.sticker.unparent();
+        }
+
+        self.update_label_attributes();
+        self.queue_resize();
+    }
+
+    /// Makes the label fit in a single line, ellipsizing its text.
+    pub(crate) fn set_single_line(&self) {
+        let label = &self.imp().label;
+        label.set_wrap(false);
+        label.set_single_line_mode(true);
+        label.set_ellipsize(pango::EllipsizeMode::End);
+    }
+
+    pub(crate) fn set_max_width_chars(&self, n_chars: i32) {
+        self.imp().label.set_max_width_chars(n_chars);
+    }
+
     pub(crate) fn indicators(&self) -> Option<ui::MessageIndicators> {
         self.imp().indicators.borrow().clone()
     }
@@ -236,3 +637,25 @@ impl MessageLabel {
         }
     }
 }
+
+/// Converts a range of UTF-16 code units of the text to a range of bytes, if it's valid.
+fn byte_range(text: &str, offset: usize, length: usize) -> Option<(usize, usize)> {
+    let mut start_index = None;
+    let mut code_units = 0;
+
+    for (index, c) in text.char_indices() {
+        if code_units == offset {
+            start_index = Some(index);
+        }
+        if code_units == offset + length {
+            return start_index.map(|start_index| (start_index, index));
+        }
+        code_units += c.len_utf16();
+    }
+
+    if code_units == offset + length {
+        start_index.map(|start_index| (start_index, text.len()))
+    } else {
+        None
+    }
+}
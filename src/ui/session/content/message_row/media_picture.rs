@@ -2,6 +2,7 @@ use std::cell::Cell;
 use std::sync::OnceLock;
 
 use glib::clone;
+use glib::subclass::Signal;
 use gtk::gdk;
 use gtk::glib;
 use gtk::prelude::*;
@@ -38,6 +39,11 @@ mod imp {
     }
 
     impl ObjectImpl for MediaPicture {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("activated").build()])
+        }
+
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
             PROPERTIES.get_or_init(|| {
@@ -82,6 +88,15 @@ mod imp {
                 .connect_paintable_notify(clone!(@weak obj => move |_| {
                     obj.notify("paintable");
                 }));
+
+            let click = gtk::GestureClick::builder().button(1).build();
+            click.connect_released(clone!(@weak obj => move |click, n_press, _, _| {
+                if n_press == 1 {
+                    click.set_state(gtk::EventSequenceState::Claimed);
+                    obj.emit_by_name::<()>("activated", &[]);
+                }
+            }));
+            obj.add_controller(click);
         }
 
         fn dispose(&self) {
@@ -139,6 +154,15 @@ impl MediaPicture {
         self.imp().picture.set_paintable(paintable);
     }
 
+    /// Connects to the signal emitted when the picture is clicked, to view the media.
+    pub(crate) fn connect_activated<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("activated", true, move |values| {
+            let obj = values[0].get::<Self>().unwrap();
+            f(&obj);
+            None
+        })
+    }
+
     pub(crate) fn aspect_ratio(&self) -> f64 {
         self.imp().aspect_ratio.get()
     }
@@ -1,3 +1,4 @@
+mod audio;
 mod base;
 mod bubble;
 mod document;
@@ -6,11 +7,18 @@ mod label;
 mod location;
 mod media_picture;
 mod photo;
+mod poll;
+mod reaction_picker;
+mod reactions;
 mod reply;
 mod sticker;
 mod text;
 mod venue;
 mod video;
+mod video_note;
+mod voice_note;
+mod waveform;
+mod web_page;
 
 use std::cell::RefCell;
 use std::sync::OnceLock;
@@ -23,6 +31,7 @@ use gtk::glib;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
+pub(crate) use self::audio::MessageAudio;
 pub(crate) use self::base::MessageBase;
 pub(crate) use self::base::MessageBaseExt;
 pub(crate) use self::base::MessageBaseImpl;
@@ -34,11 +43,18 @@ pub(crate) use self::label::MessageLabel;
 pub(crate) use self::location::MessageLocation;
 pub(crate) use self::media_picture::MediaPicture;
 pub(crate) use self::photo::MessagePhoto;
+pub(crate) use self::poll::MessagePoll;
+pub(crate) use self::reaction_picker::MessageReactionPicker;
+pub(crate) use self::reactions::MessageReactions;
 pub(crate) use self::reply::MessageReply;
 pub(crate) use self::sticker::MessageSticker;
 pub(crate) use self::text::MessageText;
 pub(crate) use self::venue::MessageVenue;
 pub(crate) use self::video::MessageVideo;
+pub(crate) use self::video_note::MessageVideoNote;
+pub(crate) use self::voice_note::MessageVoiceNote;
+pub(crate) use self::waveform::MessageWaveform;
+pub(crate) use self::web_page::MessageWebPage;
 use crate::model;
 use crate::ui;
 use crate::utils;
@@ -72,6 +88,21 @@ mod imp {
                 widget.reply()
             });
             klass.install_action("message-row.edit", None, move |widget, _, _| widget.edit());
+            klass.install_action("message-row.forward", None, move |widget, _, _| {
+                widget.forward()
+            });
+            klass.install_action("message-row.pin", None, move |widget, _, _| {
+                widget.show_pin_dialog()
+            });
+            klass.install_action("message-row.unpin", None, move |widget, _, _| {
+                widget.unpin()
+            });
+            klass.install_action("message-row.save-animation", None, move |widget, _, _| {
+                widget.save_animation()
+            });
+            klass.install_action("message-row.select", None, move |widget, _, _| {
+                widget.select()
+            });
             klass.install_action("message-row.revoke-delete", None, move |widget, _, _| {
                 widget.show_delete_dialog(true)
             });
@@ -163,6 +194,111 @@ impl Row {
         }
     }
 
+    fn forward(&self) {
+        if let Ok(message) = self.message().downcast::<model::Message>() {
+            let window = self.root().and_downcast::<gtk::Window>();
+            ui::ForwardWindow::new(window.as_ref(), &message.chat_(), vec![message.id()]).present();
+        }
+    }
+
+    fn select(&self) {
+        if let Ok(message) = self.message().downcast::<model::Message>() {
+            utils::ancestor::<_, ui::ChatHistory>(self).toggle_message_selection(message.id());
+        }
+    }
+
+    fn show_pin_dialog(&self) {
+        let Ok(message) = self.message().downcast::<model::Message>() else {
+            return;
+        };
+        let window: gtk::Window = self.root().and_then(|root| root.downcast().ok()).unwrap();
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Pin Message"))
+            .body(gettext(
+                "Do you want to pin this message at the top of the chat?",
+            ))
+            .transient_for(&window)
+            .build();
+
+        // Members of private chats are always notified
+        let notify_button = if matches!(message.chat_().chat_type(), model::ChatType::Private(_)) {
+            None
+        } else {
+            let button = gtk::CheckButton::builder()
+                .label(gettext("Notify All Members"))
+                .active(true)
+                .halign(gtk::Align::Center)
+                .build();
+            dialog.set_extra_child(Some(&button));
+            Some(button)
+        };
+
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("pin", &gettext("_Pin"))]);
+        dialog.set_default_response(Some("pin"));
+        dialog.set_response_appearance("pin", adw::ResponseAppearance::Suggested);
+
+        dialog.choose(
+            gio::Cancellable::NONE,
+            clone!(@weak self as obj => move |response| {
+                if response == "pin" {
+                    let notify = notify_button.map(|b| b.is_active()).unwrap_or(true);
+                    utils::spawn(async move {
+                        if let Err(e) = message.pin(notify).await {
+                            log::warn!("Error pinning a message: {e:?}");
+                            utils::show_toast(&obj, gettext("Failed to pin the message"));
+                        }
+                    });
+                }
+            }),
+        );
+    }
+
+    fn unpin(&self) {
+        if let Ok(message) = self.message().downcast::<model::Message>() {
+            utils::spawn(clone!(@weak self as obj => async move {
+                if let Err(e) = message.unpin().await {
+                    log::warn!("Error unpinning a message: {e:?}");
+                    utils::show_toast(&obj, gettext("Failed to unpin the message"));
+                }
+            }));
+        }
+    }
+
+    fn save_animation(&self) {
+        let Ok(message) = self.message().downcast::<model::Message>() else {
+            return;
+        };
+        let tdlib::enums::MessageContent::MessageAnimation(data) = message.content().0 else {
+            return;
+        };
+
+        let client_id = message.chat_().session_().client_().id();
+        let animation = tdlib::enums::InputFile::Remote(tdlib::types::InputFileRemote {
+            id: data.animation.animation.remote.id,
+        });
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            match tdlib::functions::add_saved_animation(animation, client_id).await {
+                Ok(_) => {
+                    utils::show_toast(&obj, gettext("GIF saved"));
+
+                    // The row may have been removed in the meantime
+                    if let Some(content) = obj.ancestor(ui::Content::static_type()) {
+                        content
+                            .downcast::<ui::Content>()
+                            .unwrap()
+                            .invalidate_saved_animations();
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Error saving an animation: {e:?}");
+                    utils::show_toast(&obj, gettext("Failed to save the GIF"));
+                }
+            }
+        }));
+    }
+
     fn show_delete_dialog(&self, revoke: bool) {
         let window: gtk::Window = self.root().and_then(|root| root.downcast().ok()).unwrap();
 
@@ -307,17 +443,38 @@ impl Row {
         }
     }
 
-    fn update_actions(&self) {
+    pub(crate) fn update_actions(&self) {
         self.action_set_enabled("message-row.reply", self.can_reply_to_message());
         self.action_set_enabled("message-row.edit", self.can_edit_message());
 
         if let Some(message) = self.message().downcast_ref::<model::Message>() {
+            self.action_set_enabled("message-row.forward", message.can_be_forwarded());
+
+            let can_pin = message.sending_state().is_none() && message.chat_().can_pin_messages();
+            self.action_set_enabled("message-row.pin", can_pin && !message.is_pinned());
+            self.action_set_enabled("message-row.unpin", can_pin && message.is_pinned());
+
+            self.action_set_enabled(
+                "message-row.save-animation",
+                message.sending_state().is_none()
+                    && matches!(
+                        message.content().0,
+                        tdlib::enums::MessageContent::MessageAnimation(_)
+                    ),
+            );
+
+            self.action_set_enabled("message-row.select", message.sending_state().is_none());
             self.action_set_enabled("message-row.delete", message.can_be_deleted_only_for_self());
             self.action_set_enabled(
                 "message-row.revoke-delete",
                 message.can_be_deleted_for_all_users(),
             );
         } else {
+            self.action_set_enabled("message-row.forward", false);
+            self.action_set_enabled("message-row.pin", false);
+            self.action_set_enabled("message-row.unpin", false);
+            self.action_set_enabled("message-row.save-animation", false);
+            self.action_set_enabled("message-row.select", false);
             self.action_set_enabled("message-row.delete", false);
             self.action_set_enabled("message-row.revoke-delete", false);
         }
@@ -334,26 +491,28 @@ impl Row {
             };
 
             match message_.content().0 {
-                // FIXME: Re-enable MessageVideo when
-                // https://github.com/paper-plane-developers/paper-plane/issues/410 is fixed
-                MessageAnimation(_) /*| MessageContent::MessageVideo(_)*/ => {
+                MessageAnimation(_) | MessageVideo(_) => {
                     self.update_specific_content::<_, ui::MessageVideo>(message_);
                 }
-                MessageAnimatedEmoji(data)
-                    if data.animated_emoji.sticker.clone().map(
-                        |s| matches!(s.format, tdlib::enums::StickerFormat::Webp | tdlib::enums::StickerFormat::Tgs)
-                    ).unwrap_or_default() => {
+                MessageVideoNote(_) => {
+                    self.update_specific_content::<_, ui::MessageVideoNote>(message_);
+                }
+                MessageAnimatedEmoji(data) if data.animated_emoji.sticker.is_some() => {
                     self.update_specific_content::<_, ui::MessageSticker>(message_);
                 }
+                MessageAudio(_) => {
+                    self.update_specific_content::<_, ui::MessageAudio>(message_);
+                }
                 MessageLocation(_) => {
                     self.update_specific_content::<_, ui::MessageLocation>(message_);
                 }
                 MessagePhoto(_) => {
                     self.update_specific_content::<_, ui::MessagePhoto>(message_);
                 }
-                MessageSticker(data)
-                    if matches!(data.sticker.format, tdlib::enums::StickerFormat::Webp | tdlib::enums::StickerFormat::Tgs) =>
-                {
+                MessagePoll(_) => {
+                    self.update_specific_content::<_, ui::MessagePoll>(message_);
+                }
+                MessageSticker(_) => {
                     self.update_specific_content::<_, ui::MessageSticker>(message_);
                 }
                 MessageDocument(_) => {
@@ -362,6 +521,9 @@ impl Row {
                 MessageVenue(_) => {
                     self.update_specific_content::<_, ui::MessageVenue>(message_);
                 }
+                MessageVoiceNote(_) => {
+                    self.update_specific_content::<_, ui::MessageVoiceNote>(message_);
+                }
                 _ => {
                     self.update_specific_content::<_, ui::MessageText>(&message);
                 }
@@ -2,7 +2,6 @@ use std::cell::RefCell;
 use std::sync::OnceLock;
 
 use glib::clone;
-use glib::closure;
 use gtk::gdk;
 use gtk::gio;
 use gtk::glib;
@@ -75,9 +74,16 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            self.obj().connect_scale_factor_notify(|obj| {
+            let obj = &*self.obj();
+
+            obj.connect_scale_factor_notify(|obj| {
                 obj.update_photo(&obj.imp().message.upgrade().unwrap());
             });
+
+            self.picture
+                .connect_activated(clone!(@weak obj => move |_| {
+                    obj.open_media_viewer();
+                }));
         }
     }
 
@@ -115,17 +121,17 @@ impl ui::MessageBaseExt for MessagePhoto {
         imp.message_bubble.update_from_message(message, true);
 
         // Setup caption expression
-        let caption_binding = model::Message::this_expression("content")
-            .chain_closure::<String>(closure!(
-                |_: model::Message, content: model::BoxedMessageContent| {
-                    if let tdlib::enums::MessageContent::MessagePhoto(data) = content.0 {
-                        utils::parse_formatted_text(data.caption)
-                    } else {
-                        unreachable!();
-                    }
+        let caption_binding = model::Message::this_expression("content").watch(
+            Some(message),
+            clone!(@weak self as obj, @weak message => move || {
+                if let tdlib::enums::MessageContent::MessagePhoto(data) = message.content().0 {
+                    obj.imp().message_bubble.set_text(data.caption);
                 }
-            ))
-            .bind(&*imp.message_bubble, "label", Some(message));
+            }),
+        );
+        if let tdlib::enums::MessageContent::MessagePhoto(data) = message.content().0 {
+            imp.message_bubble.set_text(data.caption);
+        }
         imp.binding.replace(Some(caption_binding));
 
         // Load photo
@@ -145,6 +151,12 @@ impl MessagePhoto {
         self.imp().message.upgrade().map(|message| message.id())
     }
 
+    fn open_media_viewer(&self) {
+        if let Some(message) = self.imp().message.upgrade() {
+            ui::MediaViewer::new(self.root().and_downcast_ref(), &message).present();
+        }
+    }
+
     fn update_photo(&self, message: &model::Message) {
         if let tdlib::enums::MessageContent::MessagePhoto(mut data) = message.content().0 {
             let imp = self.imp();
@@ -0,0 +1,489 @@
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use gettextrs::gettext;
+use glib::clone;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::i18n::ngettext_f;
+use crate::model;
+use crate::ui;
+use crate::ui::MessageBaseExt;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/poll.ui")]
+    pub(crate) struct MessagePoll {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: glib::WeakRef<model::Message>,
+        /// The options selected before voting, in polls allowing multiple answers
+        pub(super) selected_option_ids: RefCell<Vec<i32>>,
+        pub(super) option_rows: RefCell<Vec<super::OptionRow>>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<ui::MessageBubble>,
+        #[template_child]
+        pub(super) question_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) type_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) explanation_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub(super) explanation_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) options_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) voter_count_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) vote_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) retract_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessagePoll {
+        const NAME: &'static str = "PaplMessagePoll";
+        type Type = super::MessagePoll;
+        type ParentType = ui::MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("messagepoll");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessagePoll {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.upgrade().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl WidgetImpl for MessagePoll {}
+    impl ui::MessageBaseImpl for MessagePoll {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessagePoll(ObjectSubclass<imp::MessagePoll>)
+        @extends gtk::Widget, ui::MessageBase;
+}
+
+impl ui::MessageBaseExt for MessagePoll {
+    type Message = model::Message;
+
+    fn set_message(&self, message: &Self::Message) {
+        let imp = self.imp();
+
+        let old_message = imp.message.upgrade();
+        if old_message.as_ref() == Some(message) {
+            return;
+        }
+
+        if let Some(old_message) = old_message {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(message, false);
+
+        // Votes are received as updates of the content of the message
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+
+        imp.message.set(Some(message));
+        imp.explanation_button.set_active(false);
+        imp.selected_option_ids.borrow_mut().clear();
+        self.clear_option_rows();
+
+        self.update_content(message);
+
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessagePoll {
+    fn update_content(&self, message: &model::Message) {
+        use tdlib::enums::PollType::*;
+
+        let tdlib::enums::MessageContent::MessagePoll(data) = message.content().0 else {
+            return;
+        };
+
+        let imp = self.imp();
+        let poll = data.poll;
+
+        let (is_quiz, allow_multiple_answers, correct_option_id, explanation) = match poll.r#type {
+            Regular(data) => (false, data.allow_multiple_answers, None, None),
+            // The correct option is only known after answering
+            Quiz(data) => (
+                true,
+                false,
+                Some(data.correct_option_id).filter(|id| *id >= 0),
+                Some(data.explanation).filter(|e| !e.text.is_empty()),
+            ),
+        };
+
+        let has_voted = poll.options.iter().any(|o| o.is_chosen);
+        let is_voting = poll.options.iter().any(|o| o.is_being_chosen);
+        let show_results = has_voted || poll.is_closed;
+
+        imp.question_label.set_label(&poll.question);
+        imp.type_label.set_label(&if poll.is_closed {
+            gettext("Final Results")
+        } else {
+            match (is_quiz, poll.is_anonymous) {
+                (true, true) => gettext("Anonymous Quiz"),
+                (true, false) => gettext("Quiz"),
+                (false, true) => gettext("Anonymous Poll"),
+                (false, false) => gettext("Public Poll"),
+            }
+        });
+
+        match explanation.filter(|_| show_results) {
+            Some(explanation) => {
+                imp.explanation_label
+                    .set_markup(&utils::parse_formatted_text(explanation));
+                imp.explanation_button.set_visible(true);
+            }
+            None => {
+                imp.explanation_button.set_active(false);
+                imp.explanation_button.set_visible(false);
+            }
+        }
+
+        let kind = if show_results {
+            OptionRowKind::Result
+        } else if allow_multiple_answers {
+            OptionRowKind::Check
+        } else {
+            OptionRowKind::Button
+        };
+
+        // The selection is kept until voting, for the options that still exist
+        let has_selection = {
+            let mut selected_option_ids = imp.selected_option_ids.borrow_mut();
+            if show_results {
+                selected_option_ids.clear();
+            } else {
+                selected_option_ids.retain(|id| (*id as usize) < poll.options.len());
+            }
+            !selected_option_ids.is_empty()
+        };
+
+        // The rows are updated in place, as long as the options are shown the same way
+        let are_rows_outdated = {
+            let option_rows = imp.option_rows.borrow();
+            option_rows.len() != poll.options.len()
+                || option_rows.iter().any(|row| row.kind() != kind)
+        };
+        if are_rows_outdated {
+            self.clear_option_rows();
+
+            let option_rows = (0..poll.options.len() as i32)
+                .map(|option_id| match kind {
+                    OptionRowKind::Button => self.create_option_button(option_id),
+                    OptionRowKind::Check => self.create_check_button(option_id),
+                    OptionRowKind::Result => OptionRow::new_result(),
+                })
+                .collect::<Vec<_>>();
+            for row in &option_rows {
+                imp.options_box.append(row.widget());
+            }
+            imp.option_rows.replace(option_rows);
+        }
+
+        for (option_id, (row, option)) in imp
+            .option_rows
+            .borrow()
+            .iter()
+            .zip(&poll.options)
+            .enumerate()
+        {
+            row.update(option, correct_option_id.map(|id| id == option_id as i32));
+        }
+        imp.options_box.set_sensitive(!is_voting);
+
+        let total_voter_count = poll.total_voter_count.max(0) as u32;
+        imp.voter_count_label
+            .set_label(&match (total_voter_count, is_quiz) {
+                (0, true) => gettext("No answers yet"),
+                (0, false) => gettext("No votes yet"),
+                (count, true) => ngettext_f(
+                    "{num} answer",
+                    "{num} answers",
+                    count,
+                    &[("num", &count.to_string())],
+                ),
+                (count, false) => ngettext_f(
+                    "{num} vote",
+                    "{num} votes",
+                    count,
+                    &[("num", &count.to_string())],
+                ),
+            });
+
+        imp.vote_button
+            .set_visible(!show_results && allow_multiple_answers);
+        imp.vote_button.set_sensitive(has_selection);
+        // Answers to quizzes are final
+        imp.retract_button
+            .set_visible(has_voted && !is_quiz && !poll.is_closed);
+    }
+
+    fn clear_option_rows(&self) {
+        let imp = self.imp();
+        for row in imp.option_rows.take() {
+            imp.options_box.remove(row.widget());
+        }
+    }
+
+    /// Creates a button voting for an option as soon as it's clicked.
+    fn create_option_button(&self, option_id: i32) -> OptionRow {
+        let label = option_label();
+
+        let button = gtk::Button::builder().child(&label).build();
+        button.add_css_class("poll-option");
+        button.connect_clicked(clone!(@weak self as obj => move |_| {
+            obj.vote(vec![option_id]);
+        }));
+
+        OptionRow::Button { button, label }
+    }
+
+    /// Creates a check button selecting an option, for polls allowing multiple answers.
+    fn create_check_button(&self, option_id: i32) -> OptionRow {
+        let label = option_label();
+
+        let check_button = gtk::CheckButton::builder()
+            .child(&label)
+            .active(self.imp().selected_option_ids.borrow().contains(&option_id))
+            .build();
+        check_button.add_css_class("poll-option");
+        check_button.connect_toggled(clone!(@weak self as obj => move |check_button| {
+            let imp = obj.imp();
+
+            let mut selected_option_ids = imp.selected_option_ids.borrow_mut();
+            if !check_button.is_active() {
+                selected_option_ids.retain(|id| *id != option_id);
+            } else if !selected_option_ids.contains(&option_id) {
+                selected_option_ids.push(option_id);
+            }
+
+            imp.vote_button.set_sensitive(!selected_option_ids.is_empty());
+        }));
+
+        OptionRow::Check {
+            check_button,
+            label,
+        }
+    }
+
+    fn vote(&self, option_ids: Vec<i32>) {
+        let Some(message) = self.imp().message.upgrade() else {
+            return;
+        };
+
+        let is_retracting = option_ids.is_empty();
+
+        utils::spawn(clone!(@weak self as obj => async move {
+            if let Err(e) = message.set_poll_answer(option_ids).await {
+                log::warn!("Error setting a poll answer: {e:?}");
+                utils::show_toast(
+                    &obj,
+                    if is_retracting {
+                        gettext("Failed to retract the vote")
+                    } else {
+                        gettext("Failed to vote")
+                    },
+                );
+            }
+        }));
+    }
+
+    #[template_callback]
+    fn on_vote_button_clicked(&self) {
+        let option_ids = self.imp().selected_option_ids.borrow().clone();
+        self.vote(option_ids);
+    }
+
+    #[template_callback]
+    fn on_retract_button_clicked(&self) {
+        self.vote(Vec::new());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionRowKind {
+    Button,
+    Check,
+    Result,
+}
+
+/// The widgets showing an option of the poll.
+#[derive(Debug)]
+enum OptionRow {
+    Button {
+        button: gtk::Button,
+        label: gtk::Label,
+    },
+    Check {
+        check_button: gtk::CheckButton,
+        label: gtk::Label,
+    },
+    Result {
+        row: gtk::Box,
+        percentage_label: gtk::Label,
+        text_label: gtk::Label,
+        icon: gtk::Image,
+        progress_bar: gtk::ProgressBar,
+    },
+}
+
+impl OptionRow {
+    /// Creates a row showing the results of an option.
+    fn new_result() -> Self {
+        let percentage_label = gtk::Label::builder()
+            .width_chars(4)
+            .xalign(1.0)
+            .valign(gtk::Align::Start)
+            .build();
+        percentage_label.add_css_class("numeric");
+        percentage_label.add_css_class("caption-heading");
+
+        let text_label = gtk::Label::builder()
+            .hexpand(true)
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .build();
+
+        let icon = gtk::Image::builder().valign(gtk::Align::Start).build();
+
+        let header = gtk::Box::builder().spacing(6).build();
+        header.append(&percentage_label);
+        header.append(&text_label);
+        header.append(&icon);
+
+        let progress_bar = gtk::ProgressBar::new();
+
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(3)
+            .build();
+        row.add_css_class("poll-result");
+        row.append(&header);
+        row.append(&progress_bar);
+
+        Self::Result {
+            row,
+            percentage_label,
+            text_label,
+            icon,
+            progress_bar,
+        }
+    }
+
+    fn kind(&self) -> OptionRowKind {
+        match self {
+            Self::Button { .. } => OptionRowKind::Button,
+            Self::Check { .. } => OptionRowKind::Check,
+            Self::Result { .. } => OptionRowKind::Result,
+        }
+    }
+
+    fn widget(&self) -> &gtk::Widget {
+        match self {
+            Self::Button { button, .. } => button.upcast_ref(),
+            Self::Check { check_button, .. } => check_button.upcast_ref(),
+            Self::Result { row, .. } => row.upcast_ref(),
+        }
+    }
+
+    /// Shows the option. `is_correct` is `None` if the option isn't part of a quiz, or if the
+    /// correct option isn't known.
+    fn update(&self, option: &tdlib::types::PollOption, is_correct: Option<bool>) {
+        match self {
+            Self::Button { label, .. } | Self::Check { label, .. } => label.set_label(&option.text),
+            Self::Result {
+                percentage_label,
+                text_label,
+                icon,
+                progress_bar,
+                ..
+            } => {
+                percentage_label.set_label(&format!("{}%", option.vote_percentage));
+                text_label.set_label(&option.text);
+                progress_bar.set_fraction(option.vote_percentage as f64 / 100.0);
+
+                let icon_name = match is_correct {
+                    Some(true) => Some("object-select-symbolic"),
+                    Some(false) if option.is_chosen => Some("window-close-symbolic"),
+                    None if option.is_chosen => Some("object-select-symbolic"),
+                    _ => None,
+                };
+                icon.set_icon_name(icon_name);
+                icon.set_visible(icon_name.is_some());
+
+                for class in ["success", "error", "accent"] {
+                    icon.remove_css_class(class);
+                    progress_bar.remove_css_class(class);
+                }
+                if icon_name.is_some() {
+                    match is_correct {
+                        Some(true) => {
+                            icon.add_css_class("success");
+                            progress_bar.add_css_class("success");
+                        }
+                        Some(false) => {
+                            icon.add_css_class("error");
+                            progress_bar.add_css_class("error");
+                        }
+                        None => icon.add_css_class("accent"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn option_label() -> gtk::Label {
+    gtk::Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .wrap_mode(gtk::pango::WrapMode::WordChar)
+        .build()
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use glib::clone;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::utils;
+
+/// The size of the custom emoji shown in the picker.
+const CUSTOM_EMOJI_SIZE: i32 = 22;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(
+        resource = "/app/drey/paper-plane/ui/session/content/message_row/reaction_picker.ui"
+    )]
+    pub(crate) struct MessageReactionPicker {
+        pub(super) message: glib::WeakRef<model::Message>,
+        #[template_child]
+        pub(super) box_: TemplateChild<gtk::Box>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageReactionPicker {
+        const NAME: &'static str = "PaplMessageReactionPicker";
+        type Type = super::MessageReactionPicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_css_name("messagereactionpicker");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageReactionPicker {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.message().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            utils::unparent_children(&*self.obj());
+        }
+    }
+
+    impl WidgetImpl for MessageReactionPicker {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageReactionPicker(ObjectSubclass<imp::MessageReactionPicker>)
+        @extends gtk::Widget;
+}
+
+impl Default for MessageReactionPicker {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MessageReactionPicker {
+    pub(crate) fn message(&self) -> Option<model::Message> {
+        self.imp().message.upgrade()
+    }
+
+    pub(crate) fn set_message(&self, message: Option<&model::Message>) {
+        if self.message().as_ref() == message {
+            return;
+        }
+
+        let imp = self.imp();
+
+        utils::unparent_children(&*imp.box_);
+        self.set_visible(false);
+
+        if let Some(message) = message {
+            utils::spawn(clone!(@weak self as obj, @weak message => async move {
+                match message.available_reactions().await {
+                    Ok(reactions) => {
+                        let stickers = custom_emoji_stickers(&message, &reactions).await;
+
+                        // The picker may have been reused for another message in the meantime
+                        if obj.message().as_ref() == Some(&message) {
+                            obj.update_reactions(reactions, &stickers);
+                        }
+                    }
+                    Err(e) => log::warn!("Error getting available reactions: {:?}", e),
+                }
+            }));
+        }
+
+        imp.message.set(message);
+        self.notify("message");
+    }
+
+    fn update_reactions(
+        &self,
+        reactions: tdlib::types::AvailableReactions,
+        custom_emoji_stickers: &HashMap<i64, tdlib::types::Sticker>,
+    ) {
+        let imp = self.imp();
+
+        let Some(message) = self.message() else {
+            return;
+        };
+
+        let reactions = reactions
+            .top_reactions
+            .into_iter()
+            .chain(reactions.recent_reactions)
+            .chain(reactions.popular_reactions)
+            .filter(|reaction| !reaction.needs_premium);
+
+        let mut reaction_types = Vec::new();
+        for reaction in reactions {
+            if !reaction_types.contains(&reaction.r#type) {
+                reaction_types.push(reaction.r#type);
+            }
+        }
+
+        for reaction_type in reaction_types {
+            let button = match &reaction_type {
+                tdlib::enums::ReactionType::Emoji(data) => {
+                    gtk::Button::builder().label(&data.emoji).build()
+                }
+                tdlib::enums::ReactionType::CustomEmoji(data) => {
+                    // Custom emoji whose sticker couldn't be loaded can't be shown
+                    let Some(sticker) = custom_emoji_stickers.get(&data.custom_emoji_id) else {
+                        continue;
+                    };
+
+                    let sticker_widget: ui::Sticker = glib::Object::builder()
+                        .property("longer-side-size", CUSTOM_EMOJI_SIZE)
+                        .build();
+                    sticker_widget.update_sticker(
+                        sticker.clone(),
+                        true,
+                        message.chat_().session_(),
+                    );
+
+                    gtk::Button::builder()
+                        .child(&sticker_widget)
+                        .tooltip_text(&sticker.emoji)
+                        .build()
+                }
+            };
+            button.add_css_class("flat");
+            button.add_css_class("circular");
+
+            button.connect_clicked(clone!(@weak self as obj => move |button| {
+                if let Some(popover) = button
+                    .ancestor(gtk::Popover::static_type())
+                    .and_downcast::<gtk::Popover>()
+                {
+                    popover.popdown();
+                }
+
+                if let Some(message) = obj.message() {
+                    let reaction_type = reaction_type.clone();
+                    utils::spawn(async move {
+                        if let Err(e) = message.toggle_reaction(reaction_type).await {
+                            log::warn!("Error toggling a message reaction: {:?}", e);
+                        }
+                    });
+                }
+            }));
+
+            imp.box_.append(&button);
+        }
+
+        self.set_visible(imp.box_.first_child().is_some());
+    }
+}
+
+/// Returns the stickers of the custom emoji among the available reactions, mapped by their id.
+async fn custom_emoji_stickers(
+    message: &model::Message,
+    reactions: &tdlib::types::AvailableReactions,
+) -> HashMap<i64, tdlib::types::Sticker> {
+    let custom_emoji_ids = reactions
+        .top_reactions
+        .iter()
+        .chain(&reactions.recent_reactions)
+        .chain(&reactions.popular_reactions)
+        .filter(|reaction| !reaction.needs_premium)
+        .filter_map(|reaction| match &reaction.r#type {
+            tdlib::enums::ReactionType::CustomEmoji(data) => Some(data.custom_emoji_id),
+            tdlib::enums::ReactionType::Emoji(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    if custom_emoji_ids.is_empty() {
+        return HashMap::new();
+    }
+
+    message
+        .chat_()
+        .session_()
+        .custom_emoji_stickers(&custom_emoji_ids)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to load custom emoji stickers: {e:?}");
+            HashMap::new()
+        })
+}
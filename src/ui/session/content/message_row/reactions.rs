@@ -0,0 +1,237 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use glib::clone;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::utils;
+
+/// The size of the custom emoji shown in the reactions.
+const CUSTOM_EMOJI_SIZE: i32 = 18;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/reactions.ui")]
+    pub(crate) struct MessageReactions {
+        pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) interaction_info_signal_group: OnceCell<glib::SignalGroup>,
+        pub(super) custom_emojis_generation: Cell<u32>,
+        #[template_child]
+        pub(super) flow_box: TemplateChild<gtk::FlowBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageReactions {
+        const NAME: &'static str = "PaplMessageReactions";
+        type Type = super::MessageReactions;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_css_name("messagereactions");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageReactions {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.message().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let interaction_info_signal_group =
+                glib::SignalGroup::new::<model::MessageInteractionInfo>();
+            interaction_info_signal_group.connect_notify_local(
+                Some("reactions"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_reactions();
+                }),
+            );
+            self.interaction_info_signal_group
+                .set(interaction_info_signal_group)
+                .unwrap();
+        }
+
+        fn dispose(&self) {
+            self.flow_box.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageReactions {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageReactions(ObjectSubclass<imp::MessageReactions>)
+        @extends gtk::Widget;
+}
+
+impl MessageReactions {
+    pub(crate) fn message(&self) -> Option<model::Message> {
+        self.imp().message.upgrade()
+    }
+
+    pub(crate) fn set_message(&self, message: Option<&model::Message>) {
+        if self.message().as_ref() == message {
+            return;
+        }
+
+        let imp = self.imp();
+
+        imp.interaction_info_signal_group
+            .get()
+            .unwrap()
+            .set_target(message.map(model::Message::interaction_info).as_ref());
+        imp.message.set(message);
+
+        self.update_reactions();
+
+        self.notify("message");
+    }
+
+    fn update_reactions(&self) {
+        let imp = self.imp();
+
+        imp.flow_box.remove_all();
+
+        let generation = imp.custom_emojis_generation.get().wrapping_add(1);
+        imp.custom_emojis_generation.set(generation);
+
+        let Some(message) = self.message() else {
+            self.set_visible(false);
+            return;
+        };
+
+        let reactions = message.interaction_info().reactions().0;
+        self.set_visible(!reactions.is_empty());
+
+        let custom_emoji_ids = reactions
+            .iter()
+            .filter_map(|reaction| match &reaction.r#type {
+                tdlib::enums::ReactionType::CustomEmoji(data) => Some(data.custom_emoji_id),
+                tdlib::enums::ReactionType::Emoji(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        if custom_emoji_ids.is_empty() {
+            self.append_reactions(reactions, &HashMap::new());
+            return;
+        }
+
+        utils::spawn(clone!(@weak self as obj, @weak message => async move {
+            let session = message.chat_().session_();
+            let result = session.custom_emoji_stickers(&custom_emoji_ids).await;
+
+            if obj.imp().custom_emojis_generation.get() != generation {
+                return;
+            }
+
+            // The reactions are still shown without their custom emoji
+            let stickers = result.unwrap_or_else(|e| {
+                log::warn!("Failed to load custom emoji stickers: {e:?}");
+                HashMap::new()
+            });
+            obj.append_reactions(reactions, &stickers);
+        }));
+    }
+
+    fn append_reactions(
+        &self,
+        reactions: Vec<tdlib::types::MessageReaction>,
+        custom_emoji_stickers: &HashMap<i64, tdlib::types::Sticker>,
+    ) {
+        let imp = self.imp();
+
+        let Some(message) = self.message() else {
+            return;
+        };
+
+        for reaction in reactions {
+            let button = gtk::Button::new();
+            button.add_css_class("reaction");
+            if reaction.is_chosen {
+                button.add_css_class("chosen");
+            }
+
+            match &reaction.r#type {
+                tdlib::enums::ReactionType::Emoji(data) => {
+                    button.set_label(&format!("{} {}", data.emoji, reaction.total_count));
+                }
+                tdlib::enums::ReactionType::CustomEmoji(data) => {
+                    let box_ = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+
+                    // Without its sticker, only the count of the reaction is shown
+                    if let Some(sticker) = custom_emoji_stickers.get(&data.custom_emoji_id) {
+                        let sticker_widget: ui::Sticker = glib::Object::builder()
+                            .property("longer-side-size", CUSTOM_EMOJI_SIZE)
+                            .property("valign", gtk::Align::Center)
+                            .build();
+                        sticker_widget.update_sticker(
+                            sticker.clone(),
+                            true,
+                            message.chat_().session_(),
+                        );
+                        box_.append(&sticker_widget);
+                    }
+
+                    box_.append(&gtk::Label::new(Some(&reaction.total_count.to_string())));
+                    button.set_child(Some(&box_));
+                }
+            }
+
+            let reaction_type = reaction.r#type;
+            button.connect_clicked(clone!(@weak self as obj => move |_| {
+                if let Some(message) = obj.message() {
+                    let reaction_type = reaction_type.clone();
+                    utils::spawn(async move {
+                        if let Err(e) = message.toggle_reaction(reaction_type).await {
+                            log::warn!("Error toggling a message reaction: {:?}", e);
+                        }
+                    });
+                }
+            }));
+
+            imp.flow_box.append(&button);
+        }
+
+        self.set_visible(imp.flow_box.first_child().is_some());
+    }
+}
@@ -12,6 +12,7 @@ use gtk::CompositeTemplate;
 
 use crate::model;
 use crate::strings;
+use crate::ui;
 use crate::utils;
 
 mod imp {
@@ -34,7 +35,7 @@ mod imp {
         #[template_child]
         pub(super) sender_label: TemplateChild<gtk::Label>,
         #[template_child]
-        pub(super) message_label: TemplateChild<gtk::Label>,
+        pub(super) message_label: TemplateChild<ui::MessageLabel>,
     }
 
     #[glib::object_subclass]
@@ -67,9 +68,20 @@ mod imp {
         }
 
         fn constructed(&self) {
-            self.message_label.set_label(&gettext("Loading…"));
+            self.message_label.set_single_line();
+            self.message_label.set_label(gettext("Loading…"));
 
             let obj = self.obj();
+
+            obj.set_cursor_from_name(Some("pointer"));
+
+            let gesture = gtk::GestureClick::new();
+            gesture.connect_released(clone!(@weak obj => move |gesture, _, _, _| {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                obj.open_replied_message();
+            }));
+            obj.add_controller(gesture);
+
             utils::spawn(clone!(@weak obj => async move {
                 obj.load_replied_message().await;
             }));
@@ -109,7 +121,7 @@ impl MessageReply {
 
                 match chat.fetch_message(reply_to.message_id).await {
                     Ok(message) => self.update_from_message(&message, is_outgoing),
-                    Err(_) => imp.message_label.set_label("Deleted message"),
+                    Err(_) => imp.message_label.set_label("Deleted message".to_owned()),
                 };
             }
             tdlib::enums::MessageReplyTo::Story(_) => {
@@ -119,6 +131,28 @@ impl MessageReply {
         };
     }
 
+    fn open_replied_message(&self) {
+        let Some(message) = self.message() else {
+            return;
+        };
+
+        if let Some(model::BoxedMessageReplyTo(tdlib::enums::MessageReplyTo::Message(reply_to))) =
+            message.reply_to()
+        {
+            // Messages of other chats can't be shown in this chat history
+            if reply_to.chat_id != 0 && reply_to.chat_id != message.chat_().id() {
+                return;
+            }
+
+            if let Err(e) = self.activate_action(
+                "chat-history.scroll-to-message",
+                Some(&reply_to.message_id.to_variant()),
+            ) {
+                log::warn!("Failed to open the replied message: {:?}", e);
+            }
+        }
+    }
+
     pub(crate) fn set_max_char_width(&self, n_chars: i32) {
         self.imp().message_label.set_max_width_chars(n_chars);
         self.imp().sender_label.set_max_width_chars(n_chars);
@@ -173,6 +207,9 @@ impl MessageReply {
         // Set content label expression
 
         let caption = strings::message_content(replied_message.clone().as_ref());
-        imp.message_label.set_label(&caption);
+        let entities = utils::preview_text_entities(&caption, replied_message.content().0);
+        imp.message_label.set_label(utils::escape(&caption));
+        imp.message_label
+            .set_entities(&entities, &replied_message.chat_().session_());
     }
 }
@@ -1,6 +1,9 @@
+use std::cell::RefCell;
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use adw::prelude::*;
+use glib::clone;
 use gtk::glib;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
@@ -8,12 +11,16 @@ use gtk::CompositeTemplate;
 use crate::model;
 use crate::ui;
 use crate::ui::MessageBaseExt;
+use crate::utils;
 
 const MAX_REPLY_CHAR_WIDTH: i32 = 18;
 
 const STICKER_SIZE: i32 = 176;
 const EMOJI_SIZE: i32 = 112;
 
+const EMOJI_EFFECT_SIZE: i32 = EMOJI_SIZE * 2;
+const EMOJI_EFFECT_DURATION: Duration = Duration::from_secs(3);
+
 mod imp {
     use super::*;
 
@@ -21,6 +28,7 @@ mod imp {
     #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/sticker.ui")]
     pub(crate) struct MessageSticker {
         pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) emoji_effect: RefCell<Option<ui::Sticker>>,
         #[template_child]
         pub(super) overlay: TemplateChild<gtk::Overlay>,
         #[template_child]
@@ -82,9 +90,30 @@ mod imp {
     impl MessageSticker {
         #[template_callback]
         fn on_pressed(&self, _n_press: i32, _x: f64, _y: f64) {
-            // TODO: animated emoji needs to play
-            // effect when someone clicks on it
-            self.sticker.play_animation();
+            let Some(message) = self.message.upgrade() else {
+                return;
+            };
+
+            match message.content().0 {
+                tdlib::enums::MessageContent::MessageSticker(data) if data.sticker.set_id != 0 => {
+                    let parent = self.obj().root().and_downcast::<gtk::Window>();
+                    ui::StickerSetWindow::new(
+                        parent.as_ref(),
+                        &message.chat_().session_(),
+                        data.sticker.set_id,
+                    )
+                    .present();
+                }
+                tdlib::enums::MessageContent::MessageAnimatedEmoji(_) => {
+                    self.sticker.play_animation();
+
+                    let obj = self.obj();
+                    utils::spawn(clone!(@weak obj, @weak message => async move {
+                        obj.play_emoji_effect(&message).await;
+                    }));
+                }
+                _ => self.sticker.play_animation(),
+            }
         }
     }
 }
@@ -94,6 +123,59 @@ glib::wrapper! {
         @extends gtk::Widget, ui::MessageBase;
 }
 
+impl MessageSticker {
+    /// Sends the interaction with the animated emoji, and plays its effect, if any.
+    async fn play_emoji_effect(&self, message: &model::Message) {
+        let chat = message.chat_();
+        let session = chat.session_();
+
+        let result = tdlib::functions::click_animated_emoji_message(
+            chat.id(),
+            message.id(),
+            session.client_().id(),
+        )
+        .await;
+
+        match result {
+            Ok(tdlib::enums::Sticker::Sticker(effect)) => self.show_emoji_effect(effect, session),
+            // The emoji doesn't have an effect
+            Err(e) if e.code == 404 => {}
+            Err(e) => log::warn!("Error clicking an animated emoji: {e:?}"),
+        }
+    }
+
+    fn show_emoji_effect(&self, effect: tdlib::types::Sticker, session: model::ClientStateSession) {
+        let imp = self.imp();
+
+        if let Some(old) = imp.emoji_effect.take() {
+            imp.overlay.remove_overlay(&old);
+        }
+
+        let effect_widget: ui::Sticker = glib::Object::builder()
+            .property("longer-side-size", EMOJI_EFFECT_SIZE)
+            .property("can-target", false)
+            .property("halign", gtk::Align::Center)
+            .property("valign", gtk::Align::Center)
+            .build();
+        effect_widget.update_sticker(effect, false, session);
+
+        imp.overlay.add_overlay(&effect_widget);
+        imp.overlay.set_clip_overlay(&effect_widget, false);
+        imp.emoji_effect.replace(Some(effect_widget.clone()));
+
+        glib::timeout_add_local_once(
+            EMOJI_EFFECT_DURATION,
+            clone!(@weak self as obj, @weak effect_widget => move || {
+                let imp = obj.imp();
+                if imp.emoji_effect.borrow().as_ref() == Some(&effect_widget) {
+                    imp.emoji_effect.replace(None);
+                    imp.overlay.remove_overlay(&effect_widget);
+                }
+            }),
+        );
+    }
+}
+
 impl ui::MessageBaseExt for MessageSticker {
     type Message = model::Message;
 
@@ -2,7 +2,7 @@ use std::cell::RefCell;
 use std::sync::OnceLock;
 
 use gettextrs::gettext;
-use glib::closure;
+use glib::clone;
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
@@ -13,6 +13,9 @@ use crate::ui;
 use crate::ui::MessageBaseExt;
 use crate::utils;
 
+/// The maximum number of emoji of a message to be shown big, without a bubble.
+const MAX_BIG_EMOJI_COUNT: usize = 3;
+
 mod imp {
     use super::*;
 
@@ -23,6 +26,8 @@ mod imp {
         pub(super) message: glib::WeakRef<glib::Object>,
         #[template_child]
         pub(super) message_bubble: TemplateChild<ui::MessageBubble>,
+        #[template_child]
+        pub(super) web_page: TemplateChild<ui::MessageWebPage>,
     }
 
     #[glib::object_subclass]
@@ -95,28 +100,24 @@ impl ui::MessageBaseExt for MessageText {
         if let Some(message) = message.downcast_ref::<model::Message>() {
             imp.message_bubble.update_from_message(message, false);
 
-            // Set content label expression
-            let text_binding = model::Message::this_expression("content")
-                .chain_closure::<String>(closure!(
-                    |_: model::Message, content: model::BoxedMessageContent| {
-                        format_message_content_text(content.0)
-                    }
-                ))
-                .bind(&*imp.message_bubble, "label", Some(message));
-            bindings.push(text_binding);
+            let session = message.chat_().session_();
+
+            let content_watch = model::Message::this_expression("content").watch(
+                Some(message),
+                clone!(@weak self as obj, @weak message, @weak session => move || {
+                    obj.update_content(message.content().0, &session);
+                }),
+            );
+            bindings.push(content_watch);
+            self.update_content(message.content().0, &session);
         } else if let Some(sponsored_message) = message.downcast_ref::<model::SponsoredMessage>() {
             imp.message_bubble
                 .update_from_sponsored_message(sponsored_message);
 
-            // Set content label expression
-            let text_binding = model::SponsoredMessage::this_expression("content")
-                .chain_closure::<String>(closure!(
-                    |_: model::SponsoredMessage, content: model::BoxedMessageContent| {
-                        format_message_content_text(content.0)
-                    }
-                ))
-                .bind(&*imp.message_bubble, "label", Some(sponsored_message));
-            bindings.push(text_binding);
+            self.update_content(
+                sponsored_message.content().0,
+                &sponsored_message.chat().session_(),
+            );
         } else {
             unreachable!("Unexpected message type: {:?}", message);
         }
@@ -126,11 +127,51 @@ impl ui::MessageBaseExt for MessageText {
     }
 }
 
-fn format_message_content_text(content: tdlib::enums::MessageContent) -> String {
-    match content {
-        tdlib::enums::MessageContent::MessageText(content) => {
-            utils::parse_formatted_text(content.text)
+impl MessageText {
+    fn update_content(
+        &self,
+        content: tdlib::enums::MessageContent,
+        session: &model::ClientStateSession,
+    ) {
+        self.update_big_emoji(&content);
+
+        let imp = self.imp();
+        let bubble = &imp.message_bubble;
+        match content {
+            tdlib::enums::MessageContent::MessageText(data) => {
+                bubble.set_text(data.text);
+                imp.web_page.set_web_page(data.web_page, session);
+            }
+            tdlib::enums::MessageContent::MessageAnimatedEmoji(data) => {
+                bubble.set_label(utils::escape(&data.emoji));
+                imp.web_page.set_web_page(None, session);
+            }
+            _ => {
+                bubble.set_label(format!("<i>{}</i>", gettext("This message is unsupported")));
+                imp.web_page.set_web_page(None, session);
+            }
+        }
+    }
+
+    /// Shows messages consisting only of a few emoji big and without a bubble.
+    fn update_big_emoji(&self, content: &tdlib::enums::MessageContent) {
+        let is_big_emoji = match content {
+            tdlib::enums::MessageContent::MessageText(data) => {
+                data.text.entities.iter().all(|entity| {
+                    matches!(entity.r#type, tdlib::enums::TextEntityType::CustomEmoji(_))
+                }) && utils::emoji_count(&data.text.text)
+                    .is_some_and(|count| count <= MAX_BIG_EMOJI_COUNT)
+            }
+            // Animated emoji without an animation
+            tdlib::enums::MessageContent::MessageAnimatedEmoji(_) => true,
+            _ => false,
+        };
+
+        let bubble = &self.imp().message_bubble;
+        if is_big_emoji {
+            bubble.add_css_class("big-emoji");
+        } else {
+            bubble.remove_css_class("big-emoji");
         }
-        _ => format!("<i>{}</i>", gettext("This message is unsupported")),
     }
 }
@@ -2,6 +2,7 @@ use std::cell::Cell;
 use std::cell::RefCell;
 use std::sync::OnceLock;
 
+use gettextrs::gettext;
 use glib::clone;
 use gtk::gdk;
 use gtk::glib;
@@ -14,6 +15,10 @@ use crate::ui;
 use crate::ui::MessageBaseExt;
 use crate::utils;
 
+/// The amount of data of a streamable video that has to be downloaded before starting its
+/// playback, and then before resuming it every time it caught up with the download.
+const STREAMING_PREFIX_SIZE: i64 = 1024 * 1024;
+
 mod imp {
     use super::*;
 
@@ -23,12 +28,36 @@ mod imp {
         pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
         pub(super) message: glib::WeakRef<model::Message>,
         pub(super) is_animation: Cell<bool>,
+        /// The id of the video file
+        pub(super) file_id: Cell<i32>,
+        /// The duration of the video, in seconds
+        pub(super) duration: Cell<i32>,
+        pub(super) supports_streaming: Cell<bool>,
+        pub(super) is_downloaded: Cell<bool>,
+        pub(super) is_downloading: Cell<bool>,
+        /// Whether the media was loaded from the completely downloaded file
+        pub(super) is_media_complete: Cell<bool>,
+        /// The size of the downloaded prefix of the file when the media was last loaded
+        pub(super) loaded_prefix_size: Cell<i64>,
+        /// The position to resume the playback from, when it caught up with the download
+        pub(super) stalled_timestamp: Cell<Option<i64>>,
+        pub(super) muted: Cell<bool>,
+        pub(super) media: RefCell<Option<gtk::MediaFile>>,
+        pub(super) viewer: glib::WeakRef<ui::MediaViewer>,
         #[template_child]
         pub(super) message_bubble: TemplateChild<ui::MessageBubble>,
         #[template_child]
         pub(super) picture: TemplateChild<ui::MediaPicture>,
         #[template_child]
         pub(super) indicator: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) spinner: TemplateChild<gtk::Spinner>,
+        #[template_child]
+        pub(super) controls_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) mute_button: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -39,6 +68,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             Self::bind_template(klass);
+            klass.bind_template_instance_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -71,9 +101,27 @@ mod imp {
                 _ => unimplemented!(),
             }
         }
+
+        fn dispose(&self) {
+            if let Some(media) = self.media.take() {
+                media.pause();
+            }
+        }
+    }
+
+    impl WidgetImpl for MessageVideo {
+        fn unmap(&self) {
+            self.parent_unmap();
+
+            // Don't keep playing videos with sound that have been scrolled away
+            if !self.is_animation.get() && self.viewer.upgrade().is_none() {
+                if let Some(media) = &*self.media.borrow() {
+                    media.pause();
+                }
+            }
+        }
     }
 
-    impl WidgetImpl for MessageVideo {}
     impl ui::MessageBaseImpl for MessageVideo {}
 }
 
@@ -113,6 +161,7 @@ impl ui::MessageBaseExt for MessageVideo {
     }
 }
 
+#[gtk::template_callbacks]
 impl MessageVideo {
     fn update_content(
         &self,
@@ -121,95 +170,370 @@ impl MessageVideo {
     ) {
         let imp = self.imp();
 
-        let (caption, file, aspect_ratio, minithumbnail) =
+        let (caption, file, aspect_ratio, minithumbnail, thumbnail) =
             if let tdlib::enums::MessageContent::MessageAnimation(data) = content {
-                imp.indicator.set_label("GIF");
                 imp.is_animation.set(true);
                 (
                     data.caption,
                     data.animation.animation,
                     data.animation.width as f64 / data.animation.height as f64,
                     data.animation.minithumbnail,
+                    None,
                 )
             } else if let tdlib::enums::MessageContent::MessageVideo(data) = content {
-                self.update_remaining_time(data.video.duration as i64);
                 imp.is_animation.set(false);
+                imp.duration.set(data.video.duration);
+                imp.supports_streaming.set(data.video.supports_streaming);
                 (
                     data.caption,
                     data.video.video,
                     data.video.width as f64 / data.video.height as f64,
                     data.video.minithumbnail,
+                    data.video.thumbnail,
                 )
             } else {
                 unreachable!();
             };
 
-        let caption = utils::parse_formatted_text(caption);
-        imp.message_bubble.set_label(caption);
+        imp.message_bubble.set_text(caption);
 
         imp.picture.set_aspect_ratio(aspect_ratio);
 
-        if file.local.is_downloading_completed {
-            self.load_video(&file.local.path);
-        } else {
-            imp.picture.set_paintable(
-                minithumbnail
-                    .and_then(|m| {
-                        gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(
-                            &m.data,
-                        )))
+        // The content of a message is also updated when, for example, its file is downloaded
+        // from another place. Only reset the player if it's a different video.
+        if imp.file_id.get() == file.id
+            && (imp.media.borrow().is_some() || imp.is_downloading.get())
+        {
+            return;
+        }
+        self.stop();
+        imp.file_id.set(file.id);
+        imp.is_downloaded.set(file.local.is_downloading_completed);
+
+        imp.picture.set_paintable(
+            minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
                         .ok()
-                    })
-                    .as_ref(),
-            );
+                })
+                .as_ref(),
+        );
 
-            let file_id = file.id;
-            utils::spawn(clone!(@weak self as obj, @weak session => async move {
-                obj.download_video(file_id, &session).await;
-            }));
+        if imp.is_animation.get() {
+            imp.indicator.set_label("GIF");
+            imp.play_button.set_visible(false);
+
+            if file.local.is_downloading_completed {
+                self.load_media(&file.local.path);
+            } else {
+                let file_id = file.id;
+                utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                    obj.download_animation(file_id, &session).await;
+                }));
+            }
+        } else {
+            self.update_remaining_time(imp.duration.get() as i64);
+            imp.play_button.set_visible(true);
+
+            if let Some(thumbnail) =
+                thumbnail.filter(|t| matches!(t.format, tdlib::enums::ThumbnailFormat::Jpeg))
+            {
+                if thumbnail.file.local.is_downloading_completed {
+                    self.load_poster(&thumbnail.file.local.path);
+                } else {
+                    utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                        match session.download_file(thumbnail.file.id).await {
+                            Ok(file) => obj.load_poster(&file.local.path),
+                            Err(e) => log::warn!("Failed to download a video thumbnail: {e:?}"),
+                        }
+                    }));
+                }
+            }
         }
     }
 
-    async fn download_video(&self, file_id: i32, session: &model::ClientStateSession) {
+    fn load_poster(&self, path: &str) {
+        let imp = self.imp();
+
+        // The video may have already started playing in the meantime
+        if imp.media.borrow().is_some() {
+            return;
+        }
+
+        match gdk::Texture::from_filename(path) {
+            Ok(texture) => imp.picture.set_paintable(Some(&texture)),
+            Err(e) => log::warn!("Failed to load a video thumbnail: {e:?}"),
+        }
+    }
+
+    async fn download_animation(&self, file_id: i32, session: &model::ClientStateSession) {
         match session.download_file(file_id).await {
             Ok(file) => {
-                self.load_video(&file.local.path);
+                if self.imp().file_id.get() == file.id {
+                    self.load_media(&file.local.path);
+                }
             }
             Err(e) => {
-                log::warn!("Failed to download a video: {e:?}");
+                log::warn!("Failed to download an animation: {e:?}");
+            }
+        }
+    }
+
+    /// Starts playing the video, downloading it first if needed.
+    fn play(&self) {
+        let imp = self.imp();
+
+        imp.play_button.set_visible(false);
+
+        if imp.is_downloaded.get() {
+            if let Some(message) = imp.message.upgrade() {
+                if let tdlib::enums::MessageContent::MessageVideo(data) = message.content().0 {
+                    self.load_media(&data.video.video.local.path);
+                }
+            }
+            return;
+        }
+
+        if imp.is_downloading.get() {
+            return;
+        }
+
+        let Some(message) = imp.message.upgrade() else {
+            return;
+        };
+
+        imp.is_downloading.set(true);
+        imp.spinner.set_visible(true);
+        imp.spinner.set_spinning(true);
+
+        message.chat_().session_().download_file_with_updates(
+            imp.file_id.get(),
+            clone!(@weak self as obj => move |file| {
+                obj.handle_file_update(file);
+            }),
+        );
+    }
+
+    fn handle_file_update(&self, file: tdlib::types::File) {
+        let imp = self.imp();
+
+        // The row may have been recycled for another message
+        if imp.file_id.get() != file.id {
+            return;
+        }
+
+        let downloaded_size = file.local.downloaded_prefix_size;
+
+        if file.local.is_downloading_completed {
+            imp.is_downloaded.set(true);
+            imp.is_downloading.set(false);
+        } else if !file.local.is_downloading_active {
+            imp.is_downloading.set(false);
+            imp.spinner.set_visible(false);
+            imp.play_button.set_visible(imp.media.borrow().is_none());
+            log::warn!("The download of a video stopped before completing");
+            return;
+        }
+
+        let media_loaded = imp.media.borrow().is_some();
+        let can_stream = imp.supports_streaming.get()
+            && downloaded_size - imp.loaded_prefix_size.get() >= STREAMING_PREFIX_SIZE;
+
+        if !media_loaded {
+            if imp.is_downloaded.get() || can_stream {
+                imp.loaded_prefix_size.set(downloaded_size);
+                self.load_media(&file.local.path);
+            } else {
+                let size = file.size.max(file.expected_size).max(1);
+                imp.indicator.set_label(&format!(
+                    "{} · {}%",
+                    utils::format_duration(imp.duration.get().max(0) as u64),
+                    downloaded_size * 100 / size
+                ));
             }
+        } else if imp.stalled_timestamp.get().is_some() && (imp.is_downloaded.get() || can_stream) {
+            // Reload the file to make the newly downloaded data available to the player
+            imp.loaded_prefix_size.set(downloaded_size);
+            self.load_media(&file.local.path);
         }
     }
 
-    fn load_video(&self, path: &str) {
+    fn load_media(&self, path: &str) {
         let imp = self.imp();
 
         let media = gtk::MediaFile::for_filename(path);
-        media.set_muted(true);
-        media.set_loop(true);
-        media.play();
+        imp.is_media_complete.set(imp.is_downloaded.get());
+
+        if imp.is_animation.get() {
+            media.set_muted(true);
+            media.set_loop(true);
+        } else {
+            media.set_muted(imp.muted.get());
+
+            if let Some(timestamp) = imp.stalled_timestamp.take() {
+                media.connect_prepared_notify(move |media| {
+                    if media.is_prepared() && media.is_seekable() {
+                        media.seek(timestamp);
+                    }
+                });
+            }
 
-        if !imp.is_animation.get() {
             media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
                 let time = (media.duration() - media.timestamp()) / i64::pow(10, 6);
                 obj.update_remaining_time(time);
             }));
+            media.connect_ended_notify(clone!(@weak self as obj => move |media| {
+                obj.on_media_ended(media);
+            }));
+            media.connect_playing_notify(clone!(@weak self as obj => move |media| {
+                obj.imp().play_button.set_visible(!media.is_playing());
+            }));
+            media.connect_error_notify(|media| {
+                if let Some(error) = media.error() {
+                    log::warn!("Failed to play a video: {error:?}");
+                }
+            });
+
+            imp.spinner.set_visible(false);
+            imp.spinner.set_spinning(false);
+            imp.controls_box.set_visible(true);
+            self.update_mute_button();
         }
 
+        media.play();
+
         imp.picture.set_paintable(Some(&media));
+        if let Some((viewer, message)) = imp.viewer.upgrade().zip(imp.message.upgrade()) {
+            viewer.set_media_stream(message.id(), &media);
+        }
+        imp.media.replace(Some(media));
+    }
+
+    fn on_media_ended(&self, media: &gtk::MediaFile) {
+        if !media.is_ended() {
+            return;
+        }
+
+        let imp = self.imp();
+
+        if imp.is_media_complete.get() {
+            // Go back to the start of the video, ready to be played again
+            media.pause();
+            media.seek(0);
+            imp.play_button.set_visible(true);
+        } else {
+            // The playback caught up with the data available when the media was loaded
+            imp.stalled_timestamp.set(Some(media.timestamp()));
+
+            if imp.is_downloaded.get() {
+                if let Some(path) = media.file().and_then(|file| file.path()) {
+                    self.load_media(&path.to_string_lossy());
+                }
+            } else {
+                imp.play_button.set_visible(false);
+                imp.spinner.set_visible(true);
+                imp.spinner.set_spinning(true);
+            }
+        }
+    }
+
+    /// Stops the playback, going back to showing the poster of the video.
+    fn stop(&self) {
+        let imp = self.imp();
+
+        if let Some(media) = imp.media.take() {
+            media.pause();
+        }
+        if let Some(viewer) = imp.viewer.upgrade() {
+            viewer.close();
+        }
+
+        imp.is_downloading.set(false);
+        imp.loaded_prefix_size.set(0);
+        imp.stalled_timestamp.set(None);
+        imp.spinner.set_visible(false);
+        imp.spinner.set_spinning(false);
+        imp.controls_box.set_visible(false);
     }
 
     fn update_remaining_time(&self, time: i64) {
+        self.imp()
+            .indicator
+            .set_label(&utils::format_duration(time.max(0) as u64));
+    }
+
+    fn update_mute_button(&self) {
         let imp = self.imp();
-        let seconds = time % 60;
-        let minutes = (time % (60 * 60)) / 60;
-        let hours = time / (60 * 60);
 
-        if hours > 0 {
-            imp.indicator
-                .set_label(&format!("{hours}:{minutes:02}:{seconds:02}"));
+        if imp.muted.get() {
+            imp.mute_button.set_icon_name("audio-volume-muted-symbolic");
+            imp.mute_button.set_tooltip_text(Some(&gettext("Unmute")));
         } else {
-            imp.indicator.set_label(&format!("{minutes}:{seconds:02}"));
+            imp.mute_button.set_icon_name("audio-volume-high-symbolic");
+            imp.mute_button.set_tooltip_text(Some(&gettext("Mute")));
+        }
+    }
+
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        match &*self.imp().media.borrow() {
+            Some(media) => media.play(),
+            None => self.play(),
+        }
+    }
+
+    #[template_callback]
+    fn on_picture_activated(&self) {
+        let imp = self.imp();
+
+        if imp.is_animation.get() {
+            if let Some(message) = imp.message.upgrade() {
+                ui::MediaViewer::new(self.root().and_downcast_ref(), &message).present();
+            }
+            return;
         }
+
+        let media = imp.media.borrow().clone();
+        match media {
+            Some(media) if media.is_playing() => media.pause(),
+            Some(media) if imp.stalled_timestamp.get().is_none() => media.play(),
+            Some(_) => {}
+            None => self.play(),
+        }
+    }
+
+    #[template_callback]
+    fn on_mute_button_clicked(&self) {
+        let imp = self.imp();
+
+        imp.muted.set(!imp.muted.get());
+        if let Some(media) = &*imp.media.borrow() {
+            media.set_muted(imp.muted.get());
+        }
+        self.update_mute_button();
+    }
+
+    #[template_callback]
+    fn on_fullscreen_button_clicked(&self) {
+        let imp = self.imp();
+
+        let (Some(media), Some(message)) = (imp.media.borrow().clone(), imp.message.upgrade())
+        else {
+            return;
+        };
+
+        let viewer = ui::MediaViewer::new(self.root().and_downcast_ref(), &message);
+        viewer.set_media_stream(message.id(), &media);
+        viewer.connect_destroy(clone!(@weak self as obj => move |_| {
+            // Keep the state of the mute button in sync with the changes made in the viewer
+            let imp = obj.imp();
+            if let Some(media) = &*imp.media.borrow() {
+                imp.muted.set(media.is_muted());
+            }
+            obj.update_mute_button();
+        }));
+        viewer.present();
+
+        imp.viewer.set(Some(&viewer));
     }
 }
@@ -0,0 +1,307 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use adw::prelude::*;
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::ui::MessageBaseExt;
+use crate::utils;
+
+const MAX_REPLY_CHAR_WIDTH: i32 = 18;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/video_note.ui")]
+    pub(crate) struct MessageVideoNote {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) reply: RefCell<Option<ui::MessageReply>>,
+        /// The id of the video file
+        pub(super) file_id: Cell<i32>,
+        /// The duration of the video note, in seconds
+        pub(super) duration: Cell<i32>,
+        pub(super) media: RefCell<Option<gtk::MediaFile>>,
+        #[template_child]
+        pub(super) overlay: TemplateChild<gtk::Overlay>,
+        #[template_child]
+        pub(super) picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) duration_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) muted_indicator: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) unviewed_indicator: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) indicators: TemplateChild<ui::MessageIndicators>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageVideoNote {
+        const NAME: &'static str = "PaplMessageVideoNote";
+        type Type = super::MessageVideoNote;
+        type ParentType = ui::MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("messagevideonote");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageVideoNote {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.upgrade().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            if let Some(media) = self.media.take() {
+                media.pause();
+            }
+        }
+    }
+
+    impl WidgetImpl for MessageVideoNote {
+        fn unmap(&self) {
+            self.parent_unmap();
+
+            // Go back to the silent preview when the video note is scrolled away
+            if let Some(media) = &*self.media.borrow() {
+                if !media.is_muted() {
+                    self.obj().play_preview(media);
+                }
+            }
+        }
+    }
+
+    impl ui::MessageBaseImpl for MessageVideoNote {}
+}
+
+glib::wrapper! {
+    /// A round video message, shown without a bubble.
+    pub(crate) struct MessageVideoNote(ObjectSubclass<imp::MessageVideoNote>)
+        @extends gtk::Widget, ui::MessageBase;
+}
+
+impl ui::MessageBaseExt for MessageVideoNote {
+    type Message = model::Message;
+
+    fn set_message(&self, message: &Self::Message) {
+        let imp = self.imp();
+
+        let old_message = imp.message.upgrade();
+        if old_message.as_ref() == Some(message) {
+            return;
+        }
+
+        if let Some(old_message) = old_message {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.indicators.set_message(message.upcast_ref());
+
+        if let Some(reply) = imp.reply.take() {
+            reply.unparent();
+        }
+        if matches!(
+            message.reply_to(),
+            Some(model::BoxedMessageReplyTo(
+                tdlib::enums::MessageReplyTo::Message(_)
+            ))
+        ) {
+            let reply = ui::MessageReply::new(message);
+            reply.set_valign(gtk::Align::Start);
+            reply.set_max_char_width(MAX_REPLY_CHAR_WIDTH);
+
+            // The video note and the reply should be at the opposite sides of the box
+            if message.is_outgoing() {
+                reply.insert_before(self, Some(&imp.overlay.get()));
+            } else {
+                reply.insert_after(self, Some(&imp.overlay.get()));
+            }
+            imp.reply.replace(Some(reply));
+        }
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+
+        imp.message.set(Some(message));
+
+        self.update_content(message);
+
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageVideoNote {
+    fn update_content(&self, message: &model::Message) {
+        if let tdlib::enums::MessageContent::MessageVideoNote(data) = message.content().0 {
+            let imp = self.imp();
+            let video_note = data.video_note;
+
+            imp.unviewed_indicator
+                .set_visible(!data.is_viewed && !message.is_outgoing());
+
+            imp.duration.set(video_note.duration);
+
+            // Don't interrupt the playback when only the viewed state changed
+            if imp.file_id.get() == video_note.video.id && imp.media.borrow().is_some() {
+                return;
+            }
+
+            if let Some(media) = imp.media.take() {
+                media.pause();
+            }
+            imp.file_id.set(video_note.video.id);
+            self.update_remaining_time(video_note.duration as i64);
+            imp.muted_indicator.set_visible(true);
+
+            imp.picture.set_paintable(
+                video_note
+                    .minithumbnail
+                    .and_then(|m| {
+                        gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(
+                            &m.data,
+                        )))
+                        .ok()
+                    })
+                    .as_ref(),
+            );
+
+            if video_note.video.local.is_downloading_completed {
+                self.load_video(&video_note.video.local.path);
+            } else {
+                let file_id = video_note.video.id;
+                let session = message.chat_().session_();
+                utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                    match session.download_file(file_id).await {
+                        Ok(file) => {
+                            if obj.imp().file_id.get() == file.id {
+                                obj.load_video(&file.local.path);
+                            }
+                        }
+                        Err(e) => log::warn!("Failed to download a video note: {e:?}"),
+                    }
+                }));
+            }
+        }
+    }
+
+    fn load_video(&self, path: &str) {
+        let imp = self.imp();
+
+        let media = gtk::MediaFile::for_filename(path);
+
+        media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
+            if !media.is_muted() {
+                let time = (media.duration() - media.timestamp()) / i64::pow(10, 6);
+                obj.update_remaining_time(time);
+            }
+        }));
+        media.connect_ended_notify(clone!(@weak self as obj => move |media| {
+            if media.is_ended() {
+                obj.play_preview(media);
+            }
+        }));
+
+        self.play_preview(&media);
+
+        imp.picture.set_paintable(Some(&media));
+        imp.media.replace(Some(media));
+    }
+
+    /// Plays the video note muted and looped, as it's done until it's clicked.
+    fn play_preview(&self, media: &gtk::MediaFile) {
+        let imp = self.imp();
+
+        media.set_muted(true);
+        media.set_loop(true);
+        media.seek(0);
+        media.play();
+
+        imp.muted_indicator.set_visible(true);
+        self.update_remaining_time(imp.duration.get() as i64);
+    }
+
+    fn update_remaining_time(&self, time: i64) {
+        self.imp()
+            .duration_label
+            .set_label(&utils::format_duration(time.max(0) as u64));
+    }
+
+    #[template_callback]
+    fn on_released(&self) {
+        let imp = self.imp();
+
+        let Some(media) = imp.media.borrow().clone() else {
+            return;
+        };
+
+        if !media.is_muted() {
+            if media.is_playing() {
+                media.pause();
+            } else {
+                media.play();
+            }
+            return;
+        }
+
+        // Restart the video note with sound
+        media.set_loop(false);
+        media.set_muted(false);
+        media.seek(0);
+        media.play();
+        imp.muted_indicator.set_visible(false);
+
+        if let Some(message) = imp.message.upgrade() {
+            // Don't play over an audio message
+            message.chat_().session_().audio_player().pause();
+
+            if !message.is_outgoing() {
+                utils::spawn(async move {
+                    if let Err(e) = message.open_content().await {
+                        log::warn!("Failed to mark a video note as viewed: {e:?}");
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use gettextrs::gettext;
+use glib::clone;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::ui::MessageBaseExt;
+use crate::utils;
+
+const PLAYBACK_RATES: &[f64] = &[1.0, 1.5, 2.0];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/voice_note.ui")]
+    pub(crate) struct MessageVoiceNote {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: glib::WeakRef<model::Message>,
+        pub(super) player_signal_group: OnceCell<glib::SignalGroup>,
+        /// The duration of the voice note, in seconds
+        pub(super) duration: Cell<i32>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<ui::MessageBubble>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) waveform: TemplateChild<ui::MessageWaveform>,
+        #[template_child]
+        pub(super) duration_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) unlistened_indicator: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) speed_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageVoiceNote {
+        const NAME: &'static str = "PaplMessageVoiceNote";
+        type Type = super::MessageVoiceNote;
+        type ParentType = ui::MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageVoiceNote {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<model::Message>("message")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.upgrade().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let player_signal_group = glib::SignalGroup::new::<model::AudioPlayer>();
+            for property in ["message", "playing", "position", "duration", "rate"] {
+                player_signal_group.connect_notify_local(
+                    Some(property),
+                    clone!(@weak obj => move |_, _| {
+                        obj.update_playback_state();
+                    }),
+                );
+            }
+            self.player_signal_group.set(player_signal_group).unwrap();
+
+            self.waveform
+                .connect_seek(clone!(@weak obj => move |_, progress| {
+                    if let Some(player) = obj.active_player() {
+                        player.seek(progress);
+                    }
+                }));
+        }
+    }
+
+    impl WidgetImpl for MessageVoiceNote {}
+    impl ui::MessageBaseImpl for MessageVoiceNote {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageVoiceNote(ObjectSubclass<imp::MessageVoiceNote>)
+        @extends gtk::Widget, ui::MessageBase;
+}
+
+impl ui::MessageBaseExt for MessageVoiceNote {
+    type Message = model::Message;
+
+    fn set_message(&self, message: &Self::Message) {
+        let imp = self.imp();
+
+        let old_message = imp.message.upgrade();
+        if old_message.as_ref() == Some(message) {
+            return;
+        }
+
+        if let Some(old_message) = old_message {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+
+        imp.message.set(Some(message));
+
+        self.update_content(message);
+        imp.player_signal_group
+            .get()
+            .unwrap()
+            .set_target(Some(&message.chat_().session_().audio_player()));
+        self.update_playback_state();
+
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageVoiceNote {
+    fn update_content(&self, message: &model::Message) {
+        if let tdlib::enums::MessageContent::MessageVoiceNote(data) = message.content().0 {
+            let imp = self.imp();
+
+            imp.message_bubble.set_text(data.caption);
+
+            imp.waveform
+                .set_samples(utils::decode_waveform(&data.voice_note.waveform));
+            imp.duration.set(data.voice_note.duration);
+
+            imp.unlistened_indicator
+                .set_visible(!data.is_listened && !message.is_outgoing());
+
+            self.update_playback_state();
+        }
+    }
+
+    /// Returns the audio player of the session, if it's playing this voice note.
+    fn active_player(&self) -> Option<model::AudioPlayer> {
+        let imp = self.imp();
+
+        let player = imp
+            .player_signal_group
+            .get()
+            .unwrap()
+            .target()
+            .and_downcast::<model::AudioPlayer>()?;
+
+        if player.message().is_some() && player.message() == imp.message.upgrade() {
+            Some(player)
+        } else {
+            None
+        }
+    }
+
+    fn update_playback_state(&self) {
+        let imp = self.imp();
+
+        match self.active_player() {
+            Some(player) => {
+                imp.waveform.set_progress(player.progress());
+                imp.duration_label.set_label(&format!(
+                    "{} / {}",
+                    utils::format_duration(player.position() / 1000),
+                    utils::format_duration(player.duration() / 1000)
+                ));
+                self.update_play_button(player.playing());
+            }
+            None => {
+                imp.waveform.set_progress(0.0);
+                imp.duration_label
+                    .set_label(&utils::format_duration(imp.duration.get().max(0) as u64));
+                self.update_play_button(false);
+            }
+        }
+
+        let rate = imp
+            .player_signal_group
+            .get()
+            .unwrap()
+            .target()
+            .and_downcast::<model::AudioPlayer>()
+            .map(|player| player.rate())
+            .unwrap_or(1.0);
+        imp.speed_button.set_label(&format!("{rate}×"));
+    }
+
+    fn update_play_button(&self, playing: bool) {
+        let imp = self.imp();
+
+        if playing {
+            imp.play_button
+                .set_icon_name("media-playback-pause-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Pause")));
+        } else {
+            imp.play_button
+                .set_icon_name("media-playback-start-symbolic");
+            imp.play_button.set_tooltip_text(Some(&gettext("Play")));
+        }
+    }
+
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        if let Some(message) = self.imp().message.upgrade() {
+            let audio_player = message.chat_().session_().audio_player();
+            if audio_player.is_available() {
+                audio_player.play_message(&message);
+            } else {
+                utils::show_toast(self, gettext("Voice notes can't be played on this system"));
+            }
+        }
+    }
+
+    #[template_callback]
+    fn on_speed_button_clicked(&self) {
+        if let Some(message) = self.imp().message.upgrade() {
+            // The playback rate is shared by all voice notes
+            let player = message.chat_().session_().audio_player();
+            let index = PLAYBACK_RATES
+                .iter()
+                .position(|rate| *rate == player.rate())
+                .map(|index| (index + 1) % PLAYBACK_RATES.len())
+                .unwrap_or_default();
+
+            player.set_rate(PLAYBACK_RATES[index]);
+        }
+    }
+}
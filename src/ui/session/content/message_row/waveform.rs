@@ -0,0 +1,203 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use glib::clone;
+use glib::subclass::Signal;
+use gtk::glib;
+use gtk::graphene;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+const BAR_WIDTH: f32 = 2.0;
+const BAR_SPACING: f32 = 1.0;
+const MAX_SAMPLE: f32 = 31.0;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct MessageWaveform {
+        /// The samples of the waveform, each in the `0..32` range
+        pub(super) samples: RefCell<Vec<u8>>,
+        pub(super) progress: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageWaveform {
+        const NAME: &'static str = "PaplMessageWaveform";
+        type Type = super::MessageWaveform;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("messagewaveform");
+        }
+    }
+
+    impl ObjectImpl for MessageWaveform {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("seek")
+                    .param_types([f64::static_type()])
+                    .build()]
+            })
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecDouble::builder("progress")
+                    .maximum(1.0)
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "progress" => self.obj().set_progress(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "progress" => self.obj().progress().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            obj.set_cursor_from_name(Some("pointer"));
+
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(@weak obj => move |_, _, x, _| {
+                obj.emit_seek(x);
+            }));
+            obj.add_controller(click);
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_update(clone!(@weak obj => move |drag, offset_x, _| {
+                if let Some((start_x, _)) = drag.start_point() {
+                    obj.emit_seek(start_x + offset_x);
+                }
+            }));
+            obj.add_controller(drag);
+        }
+    }
+
+    impl WidgetImpl for MessageWaveform {
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            match orientation {
+                gtk::Orientation::Horizontal => (60, 180, -1, -1),
+                _ => (24, 24, -1, -1),
+            }
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = &*self.obj();
+
+            let width = obj.width() as f32;
+            let height = obj.height() as f32;
+
+            let n_bars = (width / (BAR_WIDTH + BAR_SPACING)).floor() as usize;
+            if n_bars == 0 {
+                return;
+            }
+
+            let samples = self.samples.borrow();
+            let played_bars = (obj.progress() * n_bars as f64).round() as usize;
+
+            let color = obj.color();
+            let mut unplayed_color = color;
+            unplayed_color.set_alpha(0.4);
+
+            for bar in 0..n_bars {
+                // Take the loudest sample in the range covered by the bar
+                let value = if samples.is_empty() {
+                    0
+                } else {
+                    let start = bar * samples.len() / n_bars;
+                    let end = ((bar + 1) * samples.len() / n_bars).max(start + 1);
+                    samples[start..end.min(samples.len())]
+                        .iter()
+                        .copied()
+                        .max()
+                        .unwrap_or_default()
+                };
+
+                let bar_height = (value as f32 / MAX_SAMPLE * height).max(BAR_WIDTH);
+                let rect = graphene::Rect::new(
+                    bar as f32 * (BAR_WIDTH + BAR_SPACING),
+                    (height - bar_height) / 2.0,
+                    BAR_WIDTH,
+                    bar_height,
+                );
+
+                snapshot.append_color(
+                    if bar < played_bars {
+                        &color
+                    } else {
+                        &unplayed_color
+                    },
+                    &rect,
+                );
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageWaveform(ObjectSubclass<imp::MessageWaveform>)
+        @extends gtk::Widget;
+}
+
+impl Default for MessageWaveform {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MessageWaveform {
+    pub(crate) fn set_samples(&self, samples: Vec<u8>) {
+        self.imp().samples.replace(samples);
+        self.queue_draw();
+    }
+
+    pub(crate) fn progress(&self) -> f64 {
+        self.imp().progress.get()
+    }
+
+    pub(crate) fn set_progress(&self, progress: f64) {
+        let progress = progress.clamp(0.0, 1.0);
+        if self.progress() == progress {
+            return;
+        }
+
+        self.imp().progress.set(progress);
+        self.queue_draw();
+        self.notify("progress");
+    }
+
+    pub(crate) fn connect_seek<F: Fn(&Self, f64) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("seek", true, move |values| {
+            let obj = values[0].get::<Self>().unwrap();
+            let progress = values[1].get::<f64>().unwrap();
+            f(&obj, progress);
+            None
+        })
+    }
+
+    fn emit_seek(&self, x: f64) {
+        let width = self.width();
+        if width > 0 {
+            let progress = (x / width as f64).clamp(0.0, 1.0);
+            self.emit_by_name::<()>("seek", &[&progress]);
+        }
+    }
+}
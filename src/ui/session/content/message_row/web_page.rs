@@ -0,0 +1,180 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+
+use glib::clone;
+use gtk::gdk;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/message_row/web_page.ui")]
+    pub(crate) struct MessageWebPage {
+        pub(super) url: RefCell<String>,
+        /// The id of the shown photo, used to skip the results of outdated downloads
+        pub(super) photo_file_id: Cell<i32>,
+        #[template_child]
+        pub(super) site_name_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) description_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) instant_view_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageWebPage {
+        const NAME: &'static str = "PaplMessageWebPage";
+        type Type = super::MessageWebPage;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("messagewebpage");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageWebPage {
+        fn dispose(&self) {
+            utils::unparent_children(&*self.obj());
+        }
+    }
+
+    impl WidgetImpl for MessageWebPage {}
+}
+
+glib::wrapper! {
+    /// A preview card of the first link of a message.
+    pub(crate) struct MessageWebPage(ObjectSubclass<imp::MessageWebPage>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl MessageWebPage {
+    /// Shows the preview of the web page, or hides the card if there's none.
+    pub(crate) fn set_web_page(
+        &self,
+        web_page: Option<tdlib::types::WebPage>,
+        session: &model::ClientStateSession,
+    ) {
+        let imp = self.imp();
+
+        let Some(web_page) = web_page else {
+            imp.url.replace(String::new());
+            self.set_photo(None, session);
+            self.set_visible(false);
+            return;
+        };
+
+        imp.site_name_label.set_label(&web_page.site_name);
+        imp.site_name_label
+            .set_visible(!web_page.site_name.is_empty());
+
+        imp.title_label.set_label(&web_page.title);
+        imp.title_label.set_visible(!web_page.title.is_empty());
+
+        let has_description = !web_page.description.text.is_empty();
+        imp.description_label
+            .set_label(&utils::parse_formatted_text(web_page.description));
+        imp.description_label.set_visible(has_description);
+
+        imp.instant_view_label
+            .set_visible(web_page.instant_view_version > 0);
+
+        imp.url.replace(web_page.url);
+        self.set_photo(web_page.photo, session);
+        self.set_visible(true);
+    }
+
+    fn set_photo(&self, photo: Option<tdlib::types::Photo>, session: &model::ClientStateSession) {
+        let imp = self.imp();
+
+        let Some(mut photo) = photo.filter(|photo| !photo.sizes.is_empty()) else {
+            imp.photo_file_id.set(0);
+            imp.picture.set_paintable(gdk::Paintable::NONE);
+            imp.picture.set_visible(false);
+            return;
+        };
+
+        // A medium size is enough for the width of the bubble
+        let photo_size = match photo.sizes.iter().position(|s| s.r#type == "x") {
+            Some(pos) => photo.sizes.swap_remove(pos),
+            None => photo.sizes.pop().unwrap(),
+        };
+        let file = photo_size.photo;
+
+        imp.picture.set_visible(true);
+        if imp.photo_file_id.replace(file.id) == file.id {
+            return;
+        }
+
+        imp.picture.set_paintable(
+            photo
+                .minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                })
+                .as_ref(),
+        );
+
+        if file.local.is_downloading_completed {
+            self.load_photo(file.id, file.local.path);
+        } else {
+            utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                match session.download_file(file.id).await {
+                    Ok(file) => obj.load_photo(file.id, file.local.path),
+                    Err(e) => log::warn!("Failed to download a web page photo: {e:?}"),
+                }
+            }));
+        }
+    }
+
+    fn load_photo(&self, file_id: i32, path: String) {
+        utils::spawn(clone!(@weak self as obj => async move {
+            let result = gio::spawn_blocking(move || utils::decode_image_from_path(&path))
+                .await
+                .unwrap();
+
+            // The card may have been recycled in the meantime
+            if obj.imp().photo_file_id.get() != file_id {
+                return;
+            }
+
+            match result {
+                Ok(texture) => obj.imp().picture.set_paintable(Some(&texture)),
+                Err(e) => log::warn!("Error decoding a web page photo: {e:?}"),
+            }
+        }));
+    }
+
+    #[template_callback]
+    fn on_gesture_click_released(&self) {
+        let url = self.imp().url.borrow().clone();
+        if url.is_empty() {
+            return;
+        }
+
+        gtk::UriLauncher::new(&url).launch(
+            self.root().and_downcast_ref::<gtk::Window>(),
+            gio::Cancellable::NONE,
+            |_| {},
+        );
+    }
+}
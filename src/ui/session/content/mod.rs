@@ -1,11 +1,26 @@
+mod animation_picker;
+mod animation_thumbnail;
+mod audio_player_bar;
 mod background;
 mod chat_action_bar;
 mod chat_history;
 mod chat_history_row;
 mod chat_info_window;
+mod chat_search_bar;
 mod event_row;
+mod forum_topic_list;
+mod forward_window;
+mod media_viewer;
 mod message_row;
+mod pinned_messages_bar;
+mod scheduled_messages_window;
 mod send_media_window;
+mod send_poll_window;
+mod shared_media_row;
+mod shared_media_thumbnail;
+mod sticker_picker;
+mod sticker_set_window;
+mod voice_recorder;
 
 use std::sync::OnceLock;
 
@@ -14,13 +29,21 @@ use gtk::glib;
 use gtk::prelude::*;
 use gtk::CompositeTemplate;
 
+pub(crate) use self::animation_picker::AnimationPicker;
+pub(crate) use self::animation_thumbnail::AnimationThumbnail;
+pub(crate) use self::audio_player_bar::AudioPlayerBar;
 pub(crate) use self::background::Background;
 pub(crate) use self::chat_action_bar::ChatActionBar;
 pub(crate) use self::chat_history::ChatHistory;
 pub(crate) use self::chat_history_row::ChatHistoryRow;
 pub(crate) use self::chat_info_window::ChatInfoWindow;
+pub(crate) use self::chat_search_bar::ChatSearchBar;
 pub(crate) use self::event_row::EventRow;
+pub(crate) use self::forum_topic_list::ForumTopicList;
+pub(crate) use self::forward_window::ForwardWindow;
+pub(crate) use self::media_viewer::MediaViewer;
 pub(crate) use self::message_row::MediaPicture;
+pub(crate) use self::message_row::MessageAudio;
 pub(crate) use self::message_row::MessageBase;
 pub(crate) use self::message_row::MessageBaseExt;
 pub(crate) use self::message_row::MessageBaseImpl;
@@ -31,13 +54,27 @@ pub(crate) use self::message_row::MessageIndicators;
 pub(crate) use self::message_row::MessageLabel;
 pub(crate) use self::message_row::MessageLocation;
 pub(crate) use self::message_row::MessagePhoto;
+pub(crate) use self::message_row::MessagePoll;
+pub(crate) use self::message_row::MessageReactionPicker;
+pub(crate) use self::message_row::MessageReactions;
 pub(crate) use self::message_row::MessageReply;
 pub(crate) use self::message_row::MessageSticker;
 pub(crate) use self::message_row::MessageText;
 pub(crate) use self::message_row::MessageVenue;
 pub(crate) use self::message_row::MessageVideo;
+pub(crate) use self::message_row::MessageVideoNote;
+pub(crate) use self::message_row::MessageVoiceNote;
+pub(crate) use self::message_row::MessageWaveform;
+pub(crate) use self::message_row::MessageWebPage;
 pub(crate) use self::message_row::Row as MessageRow;
+pub(crate) use self::pinned_messages_bar::PinnedMessagesBar;
+pub(crate) use self::scheduled_messages_window::ScheduledMessagesWindow;
 pub(crate) use self::send_media_window::SendMediaWindow;
+pub(crate) use self::send_poll_window::SendPollWindow;
+pub(crate) use self::shared_media_row::SharedMediaRow;
+pub(crate) use self::shared_media_thumbnail::SharedMediaThumbnail;
+pub(crate) use self::sticker_picker::StickerPicker;
+pub(crate) use self::sticker_set_window::StickerSetWindow;
 use crate::model;
 
 mod imp {
@@ -53,6 +90,10 @@ mod imp {
         pub(super) unselected_chat_view: TemplateChild<adw::ToolbarView>,
         #[template_child]
         pub(super) chat_history: TemplateChild<ChatHistory>,
+        #[template_child]
+        pub(super) thread_history: TemplateChild<ChatHistory>,
+        #[template_child]
+        pub(super) forum_topic_list: TemplateChild<ForumTopicList>,
     }
 
     #[glib::object_subclass]
@@ -123,7 +164,60 @@ impl Content {
     }
 
     pub(crate) fn handle_paste_action(&self) {
-        self.imp().chat_history.handle_paste_action();
+        let imp = self.imp();
+        if imp.stack.visible_child().as_ref() == Some(imp.thread_history.upcast_ref()) {
+            imp.thread_history.handle_paste_action();
+        } else {
+            imp.chat_history.handle_paste_action();
+        }
+    }
+
+    /// Marks the saved animations of the GIF pickers as outdated.
+    pub(crate) fn invalidate_saved_animations(&self) {
+        let imp = self.imp();
+        imp.chat_history.invalidate_saved_animations();
+        imp.thread_history.invalidate_saved_animations();
+    }
+
+    pub(crate) fn scroll_to_message(&self, message_id: i64) {
+        self.close_thread();
+        self.imp().chat_history.scroll_to_message(message_id);
+    }
+
+    /// Shows the message thread with the given id of `chat` on top of the history of
+    /// `parent_chat`, or on top of its topics for forums.
+    pub(crate) fn open_thread(
+        &self,
+        chat: &model::Chat,
+        message_thread_id: i64,
+        parent_chat: &model::Chat,
+        title: &str,
+    ) {
+        let imp = self.imp();
+        imp.thread_history.set_chat(None);
+        imp.thread_history
+            .set_message_thread(chat, message_thread_id, parent_chat, title);
+        imp.stack.set_visible_child(&imp.thread_history.get());
+    }
+
+    /// Goes back from the message thread to the history of the chat, or to the topics of a
+    /// forum.
+    pub(crate) fn close_thread(&self) {
+        let imp = self.imp();
+        if imp.thread_history.chat().is_none() {
+            return;
+        }
+
+        match self.chat() {
+            Some(chat) if chat.is_forum() => {
+                // The unread counts of the topics may have changed in the meantime
+                imp.forum_topic_list.reload();
+                imp.stack.set_visible_child(&imp.forum_topic_list.get());
+            }
+            Some(_) => imp.stack.set_visible_child(&imp.chat_history.get()),
+            None => {}
+        }
+        imp.thread_history.set_chat(None);
     }
 
     pub(crate) fn chat(&self) -> Option<model::Chat> {
@@ -135,8 +229,19 @@ impl Content {
             return;
         }
 
+        self.close_thread();
+
         let imp = self.imp();
-        if chat.is_some() {
+
+        // Forums show their topics instead of a single history
+        let forum = chat.filter(|chat| chat.is_forum());
+        imp.forum_topic_list.set_chat(forum);
+        imp.chat_history
+            .set_chat(if forum.is_some() { None } else { chat });
+
+        if forum.is_some() {
+            imp.stack.set_visible_child(&imp.forum_topic_list.get());
+        } else if chat.is_some() {
             imp.stack.set_visible_child(&imp.chat_history.get());
         } else {
             imp.stack.set_visible_child(&imp.unselected_chat_view.get());
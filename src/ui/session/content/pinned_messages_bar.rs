@@ -0,0 +1,297 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cell::RefCell;
+
+use gettextrs::gettext;
+use glib::clone;
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::i18n::gettext_f;
+use crate::model;
+use crate::strings;
+use crate::ui;
+use crate::utils;
+
+/// The maximum number of pinned messages loaded.
+const PINNED_MESSAGES_LIMIT: i32 = 100;
+/// The maximum number of segments of the position indicator.
+const MAX_INDICATOR_SEGMENTS: usize = 4;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::PinnedMessagesBar)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/pinned_messages_bar.ui")]
+    pub(crate) struct PinnedMessagesBar {
+        #[property(get, set = Self::set_chat, explicit_notify, nullable)]
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) chat_signal_group: OnceCell<glib::SignalGroup>,
+        /// The pinned messages, from the newest to the oldest
+        pub(super) messages: RefCell<Vec<model::Message>>,
+        /// The index of the shown message
+        pub(super) current: Cell<usize>,
+        /// Used to discard the results of outdated requests
+        pub(super) generation: Cell<u32>,
+        #[template_child]
+        pub(super) revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) indicator: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) content_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) unpin_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PinnedMessagesBar {
+        const NAME: &'static str = "PaplPinnedMessagesBar";
+        type Type = super::PinnedMessagesBar;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("pinnedmessagesbar");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PinnedMessagesBar {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            let chat_signal_group = glib::SignalGroup::new::<model::Chat>();
+            chat_signal_group.connect_local(
+                "pinned-messages-changed",
+                false,
+                clone!(@weak obj => @default-return None, move |_| {
+                    obj.load_pinned_messages();
+                    None
+                }),
+            );
+            chat_signal_group.connect_local(
+                "deleted-message",
+                false,
+                clone!(@weak obj => @default-return None, move |values| {
+                    let message = values[1].get::<model::Message>().unwrap();
+                    obj.remove_message(&message);
+                    None
+                }),
+            );
+            chat_signal_group.connect_notify_local(
+                Some("permissions"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update();
+                }),
+            );
+            self.chat_signal_group.set(chat_signal_group).unwrap();
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for PinnedMessagesBar {}
+
+    impl PinnedMessagesBar {
+        fn set_chat(&self, chat: Option<&model::Chat>) {
+            let obj = &*self.obj();
+            if obj.chat().as_ref() == chat {
+                return;
+            }
+
+            self.chat.set(chat);
+            self.chat_signal_group.get().unwrap().set_target(chat);
+
+            self.messages.borrow_mut().clear();
+            self.current.set(0);
+            obj.update();
+            obj.load_pinned_messages();
+
+            obj.notify_chat();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A bar showing the pinned messages of a chat, one at a time.
+    pub(crate) struct PinnedMessagesBar(ObjectSubclass<imp::PinnedMessagesBar>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl PinnedMessagesBar {
+    fn current_message(&self) -> Option<model::Message> {
+        let imp = self.imp();
+        imp.messages.borrow().get(imp.current.get()).cloned()
+    }
+
+    fn load_pinned_messages(&self) {
+        let imp = self.imp();
+
+        let generation = imp.generation.get().wrapping_add(1);
+        imp.generation.set(generation);
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        utils::spawn(clone!(@weak self as obj, @weak chat => async move {
+            let result = chat
+                .search_messages(
+                    tdlib::enums::SearchMessagesFilter::Pinned,
+                    0,
+                    0,
+                    PINNED_MESSAGES_LIMIT,
+                )
+                .await;
+
+            let imp = obj.imp();
+            if imp.generation.get() != generation {
+                return;
+            }
+
+            match result {
+                Ok(messages) => {
+                    // Keep showing the same message, if it's still pinned
+                    let current = obj
+                        .current_message()
+                        .and_then(|current| messages.iter().position(|m| *m == current))
+                        .unwrap_or_default();
+
+                    imp.messages.replace(messages);
+                    imp.current.set(current);
+                    obj.update();
+                }
+                Err(e) => log::warn!("Failed to load the pinned messages of a chat: {e:?}"),
+            }
+        }));
+    }
+
+    fn remove_message(&self, message: &model::Message) {
+        let imp = self.imp();
+
+        let position = imp.messages.borrow().iter().position(|m| m == message);
+        if let Some(position) = position {
+            let len = {
+                let mut messages = imp.messages.borrow_mut();
+                messages.remove(position);
+                messages.len()
+            };
+
+            if imp.current.get() > position || imp.current.get() >= len {
+                imp.current.set(imp.current.get().saturating_sub(1));
+            }
+            self.update();
+        }
+    }
+
+    fn update(&self) {
+        let imp = self.imp();
+
+        let Some(message) = self.current_message() else {
+            imp.revealer.set_reveal_child(false);
+            return;
+        };
+
+        let len = imp.messages.borrow().len();
+        let current = imp.current.get();
+
+        if len > 1 {
+            // The oldest pinned message is the first one
+            imp.title_label.set_label(&gettext_f(
+                "Pinned Message #{number}",
+                &[("number", &(len - current).to_string())],
+            ));
+        } else {
+            imp.title_label.set_label(&gettext("Pinned Message"));
+        }
+        imp.content_label
+            .set_label(&strings::message_content(&message));
+
+        self.update_indicator(len, current);
+
+        imp.unpin_button
+            .set_visible(message.chat_().can_pin_messages());
+        imp.revealer.set_reveal_child(true);
+    }
+
+    /// Updates the segments showing the position of the shown message, with the newest
+    /// message at the bottom.
+    fn update_indicator(&self, len: usize, current: usize) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.indicator.first_child() {
+            imp.indicator.remove(&child);
+        }
+
+        if len < 2 {
+            return;
+        }
+
+        let segments = len.min(MAX_INDICATOR_SEGMENTS);
+        let active = segments - 1 - current * segments / len;
+
+        for index in 0..segments {
+            let segment = gtk::Box::builder().vexpand(true).build();
+            segment.add_css_class("segment");
+            if index == active {
+                segment.add_css_class("active");
+            }
+            imp.indicator.append(&segment);
+        }
+    }
+
+    #[template_callback]
+    fn on_message_button_clicked(&self) {
+        let imp = self.imp();
+
+        let Some(message) = self.current_message() else {
+            return;
+        };
+
+        utils::ancestor::<_, ui::ChatHistory>(self).scroll_to_message(message.id());
+
+        // Show the next older pinned message, to be able to cycle through all of them
+        let len = imp.messages.borrow().len();
+        imp.current.set((imp.current.get() + 1) % len);
+        self.update();
+    }
+
+    #[template_callback]
+    fn on_unpin_button_clicked(&self) {
+        if let Some(message) = self.current_message() {
+            utils::spawn(clone!(@weak self as obj => async move {
+                if let Err(e) = message.unpin().await {
+                    log::warn!("Error unpinning a message: {e:?}");
+                    utils::show_toast(&obj, gettext("Failed to unpin the message"));
+                }
+            }));
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::gio;
+use gtk::glib;
+use gtk::CompositeTemplate;
+
+use crate::i18n::gettext_f;
+use crate::model;
+use crate::strings;
+use crate::types::MessageId;
+use crate::ui;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/scheduled_messages_window.ui")]
+    pub(crate) struct ScheduledMessagesWindow {
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) chat_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) is_loaded: Cell<bool>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ScheduledMessagesWindow {
+        const NAME: &'static str = "PaplScheduledMessagesWindow";
+        type Type = super::ScheduledMessagesWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+
+            klass.install_action_async(
+                "scheduled-messages-window.send-now",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.send_now(message_id).await;
+                },
+            );
+            klass.install_action_async(
+                "scheduled-messages-window.reschedule",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.reschedule(message_id).await;
+                },
+            );
+            klass.install_action_async(
+                "scheduled-messages-window.edit",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.edit(message_id).await;
+                },
+            );
+            klass.install_action_async(
+                "scheduled-messages-window.delete",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.delete(message_id).await;
+                },
+            );
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ScheduledMessagesWindow {
+        fn dispose(&self) {
+            if let (Some(chat), Some(handler)) = (self.chat.upgrade(), self.chat_handler.take()) {
+                chat.disconnect(handler);
+            }
+        }
+    }
+
+    impl WidgetImpl for ScheduledMessagesWindow {}
+    impl WindowImpl for ScheduledMessagesWindow {}
+    impl AdwWindowImpl for ScheduledMessagesWindow {}
+
+    #[gtk::template_callbacks]
+    impl ScheduledMessagesWindow {
+        #[template_callback]
+        fn on_key_pressed(
+            &self,
+            key: gdk::Key,
+            _: u32,
+            modifier: gdk::ModifierType,
+            _: &gtk::EventControllerKey,
+        ) -> glib::Propagation {
+            if key == gdk::Key::Escape
+                || (key == gdk::Key::w && modifier == gdk::ModifierType::CONTROL_MASK)
+            {
+                self.obj().close();
+            }
+
+            glib::Propagation::Proceed
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A window listing the messages of a chat that are scheduled to be sent later.
+    pub(crate) struct ScheduledMessagesWindow(ObjectSubclass<imp::ScheduledMessagesWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl ScheduledMessagesWindow {
+    pub(crate) fn new(parent: Option<&gtk::Window>, chat: &model::Chat) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+        let imp = obj.imp();
+
+        imp.chat.set(Some(chat));
+
+        let handler = chat.connect_scheduled_messages_changed(clone!(@weak obj => move |_| {
+            obj.update_list();
+        }));
+        imp.chat_handler.replace(Some(handler));
+
+        utils::spawn(clone!(@weak obj, @weak chat => async move {
+            if let Err(e) = chat.load_scheduled_messages().await {
+                log::warn!("Failed to load the scheduled messages of a chat: {e:?}");
+                obj.show_toast(gettext("Failed to load the scheduled messages"));
+            }
+
+            obj.imp().is_loaded.set(true);
+            obj.update_list();
+        }));
+
+        obj
+    }
+
+    fn show_toast(&self, title: String) {
+        self.imp().toast_overlay.add_toast(adw::Toast::new(&title));
+    }
+
+    fn update_list(&self) {
+        let imp = self.imp();
+
+        if !imp.is_loaded.get() {
+            return;
+        }
+        let Some(chat) = imp.chat.upgrade() else {
+            return;
+        };
+
+        while let Some(row) = imp.list_box.first_child() {
+            imp.list_box.remove(&row);
+        }
+
+        let messages = chat.scheduled_messages();
+        for message in &messages {
+            imp.list_box.append(&self.create_message_row(message));
+        }
+
+        imp.stack
+            .set_visible_child_name(if messages.is_empty() { "empty" } else { "list" });
+    }
+
+    fn create_message_row(&self, message: &model::Message) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(strings::message_content(message))
+            .title_lines(2)
+            .subtitle(schedule_description(message))
+            .use_markup(false)
+            .build();
+
+        let target = message.id().to_variant();
+        let menu = gio::Menu::new();
+
+        let item = gio::MenuItem::new(Some(&gettext("_Send Now")), None);
+        item.set_action_and_target_value(Some("scheduled-messages-window.send-now"), Some(&target));
+        menu.append_item(&item);
+
+        let item = gio::MenuItem::new(Some(&gettext("_Reschedule…")), None);
+        item.set_action_and_target_value(
+            Some("scheduled-messages-window.reschedule"),
+            Some(&target),
+        );
+        menu.append_item(&item);
+
+        // TODO: Support editing more message types in the future
+        if message.can_be_edited()
+            && matches!(
+                message.content().0,
+                tdlib::enums::MessageContent::MessageText(_)
+            )
+        {
+            let item = gio::MenuItem::new(Some(&gettext("_Edit…")), None);
+            item.set_action_and_target_value(Some("scheduled-messages-window.edit"), Some(&target));
+            menu.append_item(&item);
+        }
+
+        let item = gio::MenuItem::new(Some(&gettext("_Delete")), None);
+        item.set_action_and_target_value(Some("scheduled-messages-window.delete"), Some(&target));
+        menu.append_item(&item);
+
+        let menu_button = gtk::MenuButton::builder()
+            .icon_name("view-more-symbolic")
+            .menu_model(&menu)
+            .valign(gtk::Align::Center)
+            .build();
+        menu_button.add_css_class("flat");
+        row.add_suffix(&menu_button);
+
+        row
+    }
+
+    fn message(&self, message_id: MessageId) -> Option<model::Message> {
+        self.imp()
+            .chat
+            .upgrade()
+            .and_then(|chat| chat.scheduled_message(message_id))
+    }
+
+    async fn send_now(&self, message_id: MessageId) {
+        let Some(message) = self.message(message_id) else {
+            return;
+        };
+
+        if let Err(e) = message.reschedule(None).await {
+            log::warn!("Failed to send a scheduled message: {e:?}");
+            self.show_toast(gettext("Failed to send the message"));
+        }
+    }
+
+    async fn reschedule(&self, message_id: MessageId) {
+        use tdlib::enums::MessageSchedulingState::*;
+
+        let Some(message) = self.message(message_id) else {
+            return;
+        };
+
+        let date_time = match message.scheduling_state().map(|s| s.0) {
+            Some(SendAtDate(data)) => glib::DateTime::from_unix_local(data.send_date as i64).ok(),
+            _ => None,
+        }
+        .or_else(|| {
+            glib::DateTime::now_local()
+                .and_then(|now| now.add_hours(1))
+                .ok()
+        })
+        .unwrap();
+
+        let Some(date_time) = ui::DateTimePicker::choose(
+            self,
+            &gettext("Reschedule Message"),
+            &gettext("_Reschedule"),
+            &date_time,
+        )
+        .await
+        else {
+            return;
+        };
+
+        let scheduling_state = SendAtDate(tdlib::types::MessageSchedulingStateSendAtDate {
+            send_date: date_time.to_unix() as i32,
+        });
+        if let Err(e) = message.reschedule(Some(scheduling_state)).await {
+            log::warn!("Failed to reschedule a message: {e:?}");
+            self.show_toast(gettext("Failed to reschedule the message"));
+        }
+    }
+
+    async fn edit(&self, message_id: MessageId) {
+        let Some(message) = self.message(message_id) else {
+            return;
+        };
+        let tdlib::enums::MessageContent::MessageText(data) = message.content().0 else {
+            return;
+        };
+
+        let chat = message.chat_();
+        let client_id = chat.session_().client_().id();
+
+        let message_entry = ui::MessageEntry::new();
+        message_entry.set_chat(Some(chat.clone()));
+        message_entry.set_sent_text(data.text);
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Edit Message"))
+            .extra_child(&message_entry)
+            .transient_for(self)
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("save", &gettext("_Save"))]);
+        dialog.set_default_response(Some("save"));
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+        if dialog.choose_future().await != "save" {
+            return;
+        }
+
+        let Some(text) = message_entry.as_markdown().await else {
+            return;
+        };
+        let content =
+            tdlib::enums::InputMessageContent::InputMessageText(tdlib::types::InputMessageText {
+                text,
+                disable_web_page_preview: false,
+                clear_draft: false,
+            });
+
+        let result =
+            tdlib::functions::edit_message_text(chat.id(), message_id, content, client_id).await;
+        if let Err(e) = result {
+            log::warn!("Failed to edit a scheduled message: {e:?}");
+            self.show_toast(gettext("Failed to edit the message"));
+        }
+    }
+
+    async fn delete(&self, message_id: MessageId) {
+        let Some(message) = self.message(message_id) else {
+            return;
+        };
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Delete Scheduled Message?"))
+            .body(gettext("The message will not be sent"))
+            .transient_for(self)
+            .build();
+
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("delete", &gettext("_Delete")),
+        ]);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+        if dialog.choose_future().await != "delete" {
+            return;
+        }
+
+        if let Err(e) = message.delete(true).await {
+            log::warn!("Failed to delete a scheduled message: {e:?}");
+            self.show_toast(gettext("Failed to delete the message"));
+        }
+    }
+}
+
+/// Returns a description of when the scheduled message will be sent.
+fn schedule_description(message: &model::Message) -> String {
+    match message.scheduling_state().map(|s| s.0) {
+        Some(tdlib::enums::MessageSchedulingState::SendAtDate(data)) => {
+            let date = glib::DateTime::from_unix_local(data.send_date as i64)
+                .and_then(|date| date.format("%x %R"))
+                .map(String::from)
+                .unwrap_or_default();
+            gettext_f("Scheduled for {date}", &[("date", &date)])
+        }
+        _ => gettext("Will be sent when online"),
+    }
+}
@@ -19,6 +19,7 @@ mod imp {
     pub(crate) struct SendMediaWindow {
         pub(super) chat: glib::WeakRef<model::Chat>,
         pub(super) path: OnceCell<String>,
+        pub(super) message_thread_id: OnceCell<i64>,
         pub(super) reply_to: OnceCell<i64>,
         pub(super) emoji_chooser: RefCell<Option<gtk::EmojiChooser>>,
         #[template_child]
@@ -119,6 +120,7 @@ impl SendMediaWindow {
         parent: &gtk::Window,
         chat: &model::Chat,
         path: String,
+        message_thread_id: i64,
         reply_to: i64,
     ) -> Self {
         let obj: Self = glib::Object::builder()
@@ -131,6 +133,7 @@ impl SendMediaWindow {
 
         imp.chat.set(Some(chat));
         imp.path.set(path).unwrap();
+        imp.message_thread_id.set(message_thread_id).unwrap();
         imp.reply_to.set(reply_to).unwrap();
 
         obj
@@ -194,7 +197,18 @@ impl SendMediaWindow {
             message_id: *imp.reply_to.get().unwrap(),
         }));
 
-        match tdlib::functions::send_message(chat_id, 0, reply_to, None, content, client_id).await {
+        let message_thread_id = *imp.message_thread_id.get().unwrap();
+
+        match tdlib::functions::send_message(
+            chat_id,
+            message_thread_id,
+            reply_to,
+            None,
+            content,
+            client_id,
+        )
+        .await
+        {
             Ok(_) => self.close(),
             Err(e) => imp.toast_overlay.add_toast(
                 adw::Toast::builder()
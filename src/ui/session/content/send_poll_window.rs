@@ -0,0 +1,415 @@
+use std::cell::OnceCell;
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::CompositeTemplate;
+
+use crate::i18n::gettext_f;
+use crate::model;
+
+/// The maximum length of the question, in characters.
+const MAX_QUESTION_LENGTH: usize = 255;
+/// The minimum number of options of a poll.
+const MIN_OPTIONS: usize = 2;
+/// The maximum number of options of a poll.
+const MAX_OPTIONS: usize = 10;
+/// The maximum length of an option, in characters.
+const MAX_OPTION_LENGTH: usize = 100;
+/// The maximum length of the explanation of a quiz, in characters.
+const MAX_EXPLANATION_LENGTH: usize = 200;
+/// The maximum number of line feeds in the explanation of a quiz.
+const MAX_EXPLANATION_LINE_FEEDS: usize = 2;
+
+#[derive(Debug)]
+struct OptionRow {
+    entry_row: adw::EntryRow,
+    /// Used to choose the correct option in quiz mode
+    check_button: gtk::CheckButton,
+    remove_button: gtk::Button,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/send_poll_window.ui")]
+    pub(crate) struct SendPollWindow {
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) message_thread_id: OnceCell<i64>,
+        pub(super) reply_to: OnceCell<i64>,
+        pub(super) option_rows: RefCell<Vec<OptionRow>>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) question_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub(super) add_option_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) options_list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) anonymous_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) multiple_answers_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) quiz_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) explanation_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub(super) close_row: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
+        pub(super) open_period_row: TemplateChild<adw::SpinRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SendPollWindow {
+        const NAME: &'static str = "PaplSendPollWindow";
+        type Type = super::SendPollWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+            klass.bind_template_instance_callbacks();
+
+            klass.install_action_async("send-poll-window.send", None, |widget, _, _| async move {
+                widget.send().await;
+            });
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SendPollWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            for _ in 0..MIN_OPTIONS {
+                obj.add_option();
+            }
+            obj.update_validity();
+        }
+    }
+
+    impl WidgetImpl for SendPollWindow {}
+    impl WindowImpl for SendPollWindow {}
+    impl AdwWindowImpl for SendPollWindow {}
+
+    #[gtk::template_callbacks]
+    impl SendPollWindow {
+        #[template_callback]
+        fn on_key_pressed(
+            &self,
+            key: gdk::Key,
+            _: u32,
+            modifier: gdk::ModifierType,
+            _: &gtk::EventControllerKey,
+        ) -> glib::Propagation {
+            if key == gdk::Key::Escape
+                || (key == gdk::Key::w && modifier == gdk::ModifierType::CONTROL_MASK)
+            {
+                self.obj().close();
+            }
+
+            glib::Propagation::Proceed
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A window to compose and send a poll or a quiz.
+    pub(crate) struct SendPollWindow(ObjectSubclass<imp::SendPollWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+#[gtk::template_callbacks]
+impl SendPollWindow {
+    pub(crate) fn new(
+        parent: &gtk::Window,
+        chat: &model::Chat,
+        message_thread_id: i64,
+        reply_to: i64,
+    ) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+        let imp = obj.imp();
+
+        imp.chat.set(Some(chat));
+        imp.message_thread_id.set(message_thread_id).unwrap();
+        imp.reply_to.set(reply_to).unwrap();
+
+        // Channels can only have anonymous polls
+        if let model::ChatType::Supergroup(supergroup) = chat.chat_type() {
+            if supergroup.is_channel() {
+                imp.anonymous_row.set_sensitive(false);
+            }
+        }
+
+        obj
+    }
+
+    fn add_option(&self) {
+        let imp = self.imp();
+
+        let mut option_rows = imp.option_rows.borrow_mut();
+        if option_rows.len() >= MAX_OPTIONS {
+            return;
+        }
+
+        let entry_row = adw::EntryRow::new();
+        entry_row.connect_changed(clone!(@weak self as obj => move |_| {
+            obj.update_validity();
+        }));
+
+        let check_button = gtk::CheckButton::builder()
+            .tooltip_text(gettext("Correct Answer"))
+            .valign(gtk::Align::Center)
+            .visible(imp.quiz_row.is_active())
+            .build();
+        if let Some(first) = option_rows.first() {
+            check_button.set_group(Some(&first.check_button));
+        }
+        check_button.connect_toggled(clone!(@weak self as obj => move |_| {
+            obj.update_validity();
+        }));
+        entry_row.add_prefix(&check_button);
+
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Option"))
+            .valign(gtk::Align::Center)
+            .build();
+        remove_button.add_css_class("flat");
+        remove_button.connect_clicked(clone!(@weak self as obj, @weak entry_row => move |_| {
+            obj.remove_option(&entry_row);
+        }));
+        entry_row.add_suffix(&remove_button);
+
+        imp.options_list_box.append(&entry_row);
+        option_rows.push(OptionRow {
+            entry_row: entry_row.clone(),
+            check_button,
+            remove_button,
+        });
+        drop(option_rows);
+
+        self.update_option_rows();
+    }
+
+    fn remove_option(&self, entry_row: &adw::EntryRow) {
+        let imp = self.imp();
+
+        {
+            let mut option_rows = imp.option_rows.borrow_mut();
+            if option_rows.len() <= MIN_OPTIONS {
+                return;
+            }
+            option_rows.retain(|row| row.entry_row != *entry_row);
+        }
+        imp.options_list_box.remove(entry_row);
+
+        self.update_option_rows();
+    }
+
+    /// Updates the titles of the option rows and the buttons depending on the number of options.
+    fn update_option_rows(&self) {
+        let imp = self.imp();
+        let option_rows = imp.option_rows.borrow();
+
+        for (index, row) in option_rows.iter().enumerate() {
+            row.entry_row.set_title(&gettext_f(
+                "Option {number}",
+                &[("number", &(index + 1).to_string())],
+            ));
+
+            row.remove_button
+                .set_sensitive(option_rows.len() > MIN_OPTIONS);
+        }
+
+        imp.add_option_button
+            .set_sensitive(option_rows.len() < MAX_OPTIONS);
+        drop(option_rows);
+
+        self.update_validity();
+    }
+
+    #[template_callback]
+    fn on_add_option_button_clicked(&self) {
+        self.add_option();
+
+        if let Some(row) = self.imp().option_rows.borrow().last() {
+            row.entry_row.grab_focus();
+        }
+    }
+
+    #[template_callback]
+    fn on_quiz_mode_changed(&self) {
+        let imp = self.imp();
+        let is_quiz = imp.quiz_row.is_active();
+
+        if is_quiz {
+            imp.multiple_answers_row.set_active(false);
+        }
+        for row in imp.option_rows.borrow().iter() {
+            row.check_button.set_visible(is_quiz);
+        }
+
+        self.update_validity();
+    }
+
+    #[template_callback]
+    fn on_changed(&self) {
+        self.update_validity();
+    }
+
+    /// Highlights the fields exceeding the limits of TDLib and enables sending only if the poll
+    /// is valid.
+    fn update_validity(&self) {
+        let imp = self.imp();
+
+        let question = imp.question_row.text();
+        let question_length = question.trim().chars().count();
+        let is_question_valid = question_length > 0 && question_length <= MAX_QUESTION_LENGTH;
+        set_error(
+            imp.question_row.upcast_ref(),
+            question_length > MAX_QUESTION_LENGTH,
+        );
+
+        let option_rows = imp.option_rows.borrow();
+        let mut are_options_valid = true;
+        for row in option_rows.iter() {
+            let length = row.entry_row.text().trim().chars().count();
+            are_options_valid &= length > 0 && length <= MAX_OPTION_LENGTH;
+            set_error(row.entry_row.upcast_ref(), length > MAX_OPTION_LENGTH);
+        }
+        are_options_valid &= option_rows.len() >= MIN_OPTIONS;
+
+        let is_quiz_valid = if imp.quiz_row.is_active() {
+            let explanation = imp.explanation_row.text();
+            let is_explanation_valid = explanation.trim().chars().count() <= MAX_EXPLANATION_LENGTH
+                && explanation.matches('\n').count() <= MAX_EXPLANATION_LINE_FEEDS;
+            set_error(imp.explanation_row.upcast_ref(), !is_explanation_valid);
+
+            let has_correct_option = option_rows.iter().any(|row| row.check_button.is_active());
+
+            is_explanation_valid && has_correct_option
+        } else {
+            set_error(imp.explanation_row.upcast_ref(), false);
+            true
+        };
+
+        self.action_set_enabled(
+            "send-poll-window.send",
+            is_question_valid && are_options_valid && is_quiz_valid,
+        );
+    }
+
+    fn poll_type(&self) -> tdlib::enums::PollType {
+        let imp = self.imp();
+
+        if imp.quiz_row.is_active() {
+            let correct_option_id = imp
+                .option_rows
+                .borrow()
+                .iter()
+                .position(|row| row.check_button.is_active())
+                .unwrap_or_default() as i32;
+
+            tdlib::enums::PollType::Quiz(tdlib::types::PollTypeQuiz {
+                correct_option_id,
+                explanation: tdlib::types::FormattedText {
+                    text: imp.explanation_row.text().trim().to_owned(),
+                    entities: vec![],
+                },
+            })
+        } else {
+            tdlib::enums::PollType::Regular(tdlib::types::PollTypeRegular {
+                allow_multiple_answers: imp.multiple_answers_row.is_active(),
+            })
+        }
+    }
+
+    async fn send(&self) {
+        use tdlib::enums::*;
+        use tdlib::types::*;
+
+        let imp = self.imp();
+
+        let Some(chat) = imp.chat.upgrade() else {
+            return;
+        };
+
+        let options = imp
+            .option_rows
+            .borrow()
+            .iter()
+            .map(|row| row.entry_row.text().trim().to_owned())
+            .collect();
+
+        let content = InputMessageContent::InputMessagePoll(InputMessagePoll {
+            question: imp.question_row.text().trim().to_owned(),
+            options,
+            is_anonymous: imp.anonymous_row.is_active(),
+            r#type: self.poll_type(),
+            open_period: if imp.close_row.enables_expansion() {
+                imp.open_period_row.value() as i32
+            } else {
+                0
+            },
+            close_date: 0,
+            is_closed: false,
+        });
+
+        let chat_id = chat.id();
+        let client_id = chat.session_().client_().id();
+
+        let reply_to = Some(MessageReplyTo::Message(MessageReplyToMessage {
+            chat_id,
+            message_id: *imp.reply_to.get().unwrap(),
+        }));
+
+        let message_thread_id = *imp.message_thread_id.get().unwrap();
+
+        self.action_set_enabled("send-poll-window.send", false);
+
+        match tdlib::functions::send_message(
+            chat_id,
+            message_thread_id,
+            reply_to,
+            None,
+            content,
+            client_id,
+        )
+        .await
+        {
+            Ok(_) => self.close(),
+            Err(e) => {
+                log::warn!("Error sending a poll: {e:?}");
+                imp.toast_overlay.add_toast(
+                    adw::Toast::builder()
+                        .title(e.message)
+                        .timeout(3)
+                        .priority(adw::ToastPriority::High)
+                        .build(),
+                );
+                self.update_validity();
+            }
+        }
+    }
+}
+
+fn set_error(widget: &gtk::Widget, is_error: bool) {
+    if is_error {
+        widget.add_css_class("error");
+    } else {
+        widget.remove_css_class("error");
+    }
+}
@@ -0,0 +1,203 @@
+use std::cell::Cell;
+
+use gettextrs::gettext;
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::strings;
+use crate::utils;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::SharedMediaRow)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/shared_media_row.ui")]
+    pub(crate) struct SharedMediaRow {
+        #[property(get, set = Self::set_message, explicit_notify, nullable)]
+        pub(super) message: glib::WeakRef<model::Message>,
+        /// Whether to show the links contained in the message, instead of its content
+        #[property(get, set, construct_only)]
+        pub(super) show_links: Cell<bool>,
+        #[template_child]
+        pub(super) icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) subtitle_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SharedMediaRow {
+        const NAME: &'static str = "PaplSharedMediaRow";
+        type Type = super::SharedMediaRow;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_css_name("sharedmediarow");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SharedMediaRow {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for SharedMediaRow {}
+
+    impl SharedMediaRow {
+        fn set_message(&self, message: Option<&model::Message>) {
+            let obj = &*self.obj();
+            if obj.message().as_ref() == message {
+                return;
+            }
+
+            self.message.set(message);
+            if let Some(message) = message {
+                obj.update(message);
+            }
+
+            obj.notify_message();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A row of the shared files, links, voice notes or music of a chat.
+    pub(crate) struct SharedMediaRow(ObjectSubclass<imp::SharedMediaRow>)
+        @extends gtk::Widget;
+}
+
+impl SharedMediaRow {
+    pub(crate) fn new(show_links: bool) -> Self {
+        glib::Object::builder()
+            .property("show-links", show_links)
+            .build()
+    }
+
+    fn update(&self, message: &model::Message) {
+        use tdlib::enums::MessageContent::*;
+
+        let imp = self.imp();
+
+        let date = glib::DateTime::from_unix_local(message.date() as i64)
+            .and_then(|date| date.format("%x"))
+            .map(String::from)
+            .unwrap_or_default();
+
+        let content = message.content().0;
+
+        let (icon_name, title, subtitle) = if self.show_links() {
+            let text = match content {
+                MessageText(data) => Some(data.text),
+                MessagePhoto(data) => Some(data.caption),
+                MessageVideo(data) => Some(data.caption),
+                MessageDocument(data) => Some(data.caption),
+                MessageAudio(data) => Some(data.caption),
+                MessageAnimation(data) => Some(data.caption),
+                _ => None,
+            };
+
+            (
+                "web-browser-symbolic",
+                text.as_ref().and_then(first_link).unwrap_or_default(),
+                strings::message_content(message),
+            )
+        } else {
+            match content {
+                MessageDocument(data) => (
+                    "text-x-generic-symbolic",
+                    data.document.file_name,
+                    format!(
+                        "{} · {date}",
+                        glib::format_size(data.document.document.size as u64)
+                    ),
+                ),
+                MessageAudio(data) => {
+                    let audio = data.audio;
+                    let title = if audio.title.is_empty() {
+                        audio.file_name
+                    } else {
+                        audio.title
+                    };
+                    let performer = if audio.performer.is_empty() {
+                        gettext("Unknown Artist")
+                    } else {
+                        audio.performer
+                    };
+
+                    (
+                        "audio-x-generic-symbolic",
+                        title,
+                        format!(
+                            "{performer} · {}",
+                            utils::format_duration(audio.duration.max(0) as u64)
+                        ),
+                    )
+                }
+                MessageVoiceNote(data) => (
+                    "audio-input-microphone-symbolic",
+                    strings::message_sender(&message.sender(), true),
+                    format!(
+                        "{} · {date}",
+                        utils::format_duration(data.voice_note.duration.max(0) as u64)
+                    ),
+                ),
+                _ => (
+                    "mail-attachment-symbolic",
+                    strings::message_content(message),
+                    date,
+                ),
+            }
+        };
+
+        imp.icon.set_icon_name(Some(icon_name));
+        imp.title_label.set_label(&title);
+        imp.subtitle_label.set_label(&subtitle);
+    }
+}
+
+/// Returns the first link of the text, if any.
+fn first_link(text: &tdlib::types::FormattedText) -> Option<String> {
+    use tdlib::enums::TextEntityType::*;
+
+    text.entities
+        .iter()
+        .find_map(|entity| match &entity.r#type {
+            Url => Some(
+                // The offsets of the entities are in UTF-16 code units
+                String::from_utf16_lossy(
+                    &text
+                        .text
+                        .encode_utf16()
+                        .skip(entity.offset as usize)
+                        .take(entity.length as usize)
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            TextUrl(data) => Some(data.url.clone()),
+            _ => None,
+        })
+}
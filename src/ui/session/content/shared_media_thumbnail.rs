@@ -0,0 +1,160 @@
+use glib::clone;
+use glib::Properties;
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::utils;
+
+/// The minimum width of the photo size shown as a thumbnail.
+const THUMBNAIL_WIDTH: i32 = 160;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::SharedMediaThumbnail)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/shared_media_thumbnail.ui")]
+    pub(crate) struct SharedMediaThumbnail {
+        #[property(get, set = Self::set_message, explicit_notify, nullable)]
+        pub(super) message: glib::WeakRef<model::Message>,
+        #[template_child]
+        pub(super) picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) duration_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SharedMediaThumbnail {
+        const NAME: &'static str = "PaplSharedMediaThumbnail";
+        type Type = super::SharedMediaThumbnail;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_css_name("sharedmediathumbnail");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SharedMediaThumbnail {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for SharedMediaThumbnail {}
+
+    impl SharedMediaThumbnail {
+        fn set_message(&self, message: Option<&model::Message>) {
+            let obj = &*self.obj();
+            if obj.message().as_ref() == message {
+                return;
+            }
+
+            self.message.set(message);
+            if let Some(message) = message {
+                obj.update(message);
+            }
+
+            obj.notify_message();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A thumbnail of a photo or a video shared in a chat.
+    pub(crate) struct SharedMediaThumbnail(ObjectSubclass<imp::SharedMediaThumbnail>)
+        @extends gtk::Widget;
+}
+
+impl Default for SharedMediaThumbnail {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl SharedMediaThumbnail {
+    fn update(&self, message: &model::Message) {
+        use tdlib::enums::MessageContent::*;
+
+        let imp = self.imp();
+
+        let (minithumbnail, file) = match message.content().0 {
+            MessagePhoto(data) => {
+                imp.duration_label.set_visible(false);
+
+                let mut sizes = data.photo.sizes;
+                let position = sizes
+                    .iter()
+                    .position(|size| size.width >= THUMBNAIL_WIDTH)
+                    .unwrap_or(sizes.len().saturating_sub(1));
+                let file = (!sizes.is_empty()).then(|| sizes.swap_remove(position).photo);
+
+                (data.photo.minithumbnail, file)
+            }
+            MessageVideo(data) => {
+                imp.duration_label.set_visible(true);
+                imp.duration_label
+                    .set_label(&utils::format_duration(data.video.duration.max(0) as u64));
+
+                let file = data
+                    .video
+                    .thumbnail
+                    .filter(|t| matches!(t.format, tdlib::enums::ThumbnailFormat::Jpeg))
+                    .map(|t| t.file);
+
+                (data.video.minithumbnail, file)
+            }
+            _ => (None, None),
+        };
+
+        imp.picture.set_paintable(
+            minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                })
+                .as_ref(),
+        );
+
+        let Some(file) = file else {
+            return;
+        };
+
+        if file.local.is_downloading_completed {
+            imp.picture.set_filename(Some(&file.local.path));
+        } else {
+            let session = message.chat_().session_();
+            utils::spawn(clone!(@weak self as obj, @weak message => async move {
+                match session.download_file(file.id).await {
+                    Ok(file) => {
+                        // The thumbnail may have been recycled in the meantime
+                        if obj.message().as_ref() == Some(&message) {
+                            obj.imp().picture.set_filename(Some(&file.local.path));
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to download a media thumbnail: {e:?}"),
+                }
+            }));
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use gettextrs::gettext;
+use glib::clone;
+use glib::subclass::Signal;
+use glib::Properties;
+use gtk::glib;
+use gtk::graphene;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::ui;
+use crate::utils;
+
+/// The size of the longer side of the stickers.
+const STICKER_SIZE: i32 = 64;
+/// The size of the longer side of the thumbnails of the sticker sets.
+const SET_THUMBNAIL_SIZE: i32 = 28;
+/// The maximum number of stickers found by a search.
+const SEARCH_LIMIT: i32 = 100;
+
+#[derive(Debug)]
+enum SectionContent {
+    /// Stickers that are already known, like the recent ones
+    Stickers(Vec<tdlib::types::Sticker>),
+    /// A sticker set whose stickers are loaded once the section is scrolled into view
+    StickerSet(i64),
+}
+
+#[derive(Debug)]
+struct SectionInfo {
+    title: String,
+    /// Shown in the bar of the sections if there's no thumbnail
+    icon_name: Option<&'static str>,
+    thumbnail: Option<tdlib::types::Sticker>,
+    content: SectionContent,
+}
+
+#[derive(Debug)]
+struct Section {
+    content: RefCell<Option<SectionContent>>,
+    header: gtk::Label,
+    flow_box: gtk::FlowBox,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::StickerPicker)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/sticker_picker.ui")]
+    pub(crate) struct StickerPicker {
+        #[property(get, set = Self::set_chat, explicit_notify, nullable)]
+        pub(super) chat: glib::WeakRef<model::Chat>,
+        pub(super) sections: RefCell<Vec<Rc<Section>>>,
+        /// Whether the stickers must be reloaded the next time the picker is shown
+        pub(super) is_outdated: Cell<bool>,
+        /// Used to discard the results of outdated requests
+        pub(super) generation: Cell<u32>,
+        pub(super) search_generation: Cell<u32>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) sets_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub(super) sections_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) search_flow_box: TemplateChild<gtk::FlowBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for StickerPicker {
+        const NAME: &'static str = "PaplStickerPicker";
+        type Type = super::StickerPicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("stickerpicker");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for StickerPicker {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("sticker-selected")
+                    .param_types([model::BoxedSticker::static_type()])
+                    .build()]
+            })
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = &*self.obj();
+
+            self.is_outdated.set(true);
+
+            self.scrolled_window.vadjustment().connect_value_changed(
+                clone!(@weak obj => move |_| {
+                    obj.load_visible_sections();
+                }),
+            );
+            self.scrolled_window.vadjustment().connect_notify_local(
+                Some("page-size"),
+                clone!(@weak obj => move |_, _| {
+                    obj.load_visible_sections();
+                }),
+            );
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for StickerPicker {
+        fn map(&self) {
+            self.parent_map();
+
+            if self.is_outdated.get() {
+                self.obj().load();
+            }
+        }
+    }
+
+    impl StickerPicker {
+        fn set_chat(&self, chat: Option<&model::Chat>) {
+            let obj = &*self.obj();
+            if obj.chat().as_ref() == chat {
+                return;
+            }
+
+            self.chat.set(chat);
+
+            // The chat may have a different group sticker set
+            self.is_outdated.set(true);
+            if obj.is_mapped() {
+                obj.load();
+            }
+
+            obj.notify_chat();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A panel to choose a sticker from the installed sticker sets, the recent and the favorite
+    /// stickers.
+    pub(crate) struct StickerPicker(ObjectSubclass<imp::StickerPicker>)
+        @extends gtk::Widget;
+}
+
+impl Default for StickerPicker {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+#[gtk::template_callbacks]
+impl StickerPicker {
+    pub(crate) fn connect_sticker_selected<F: Fn(&Self, tdlib::types::Sticker) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("sticker-selected", true, move |values| {
+            let obj = values[0].get::<Self>().unwrap();
+            let sticker = values[1].get::<model::BoxedSticker>().unwrap();
+            f(&obj, sticker.0);
+            None
+        })
+    }
+
+    /// Marks the stickers as outdated, to reload them the next time the picker is shown.
+    pub(crate) fn invalidate(&self) {
+        self.imp().is_outdated.set(true);
+    }
+
+    fn load(&self) {
+        let imp = self.imp();
+
+        let generation = imp.generation.get().wrapping_add(1);
+        imp.generation.set(generation);
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+
+        imp.is_outdated.set(false);
+        imp.stack.set_visible_child_name("loading");
+
+        utils::spawn(clone!(@weak self as obj, @weak chat => async move {
+            let sections = obj.fetch_sections(&chat).await;
+
+            if obj.imp().generation.get() == generation {
+                obj.set_sections(sections, &chat.session_());
+            }
+        }));
+    }
+
+    /// Fetches the titles, the thumbnails and the content of the sections of the picker.
+    async fn fetch_sections(&self, chat: &model::Chat) -> Vec<SectionInfo> {
+        use tdlib::enums::StickerSet;
+        use tdlib::enums::StickerSets;
+        use tdlib::enums::Stickers;
+
+        let client_id = chat.session_().client_().id();
+        let mut sections = Vec::new();
+
+        match tdlib::functions::get_favorite_stickers(client_id).await {
+            Ok(Stickers::Stickers(data)) if !data.stickers.is_empty() => {
+                sections.push(SectionInfo {
+                    title: gettext("Favorites"),
+                    icon_name: Some("starred-symbolic"),
+                    thumbnail: None,
+                    content: SectionContent::Stickers(data.stickers),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to load the favorite stickers: {e:?}"),
+        }
+
+        match tdlib::functions::get_recent_stickers(false, client_id).await {
+            Ok(Stickers::Stickers(data)) if !data.stickers.is_empty() => {
+                sections.push(SectionInfo {
+                    title: gettext("Recently Used"),
+                    icon_name: Some("document-open-recent-symbolic"),
+                    thumbnail: None,
+                    content: SectionContent::Stickers(data.stickers),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to load the recent stickers: {e:?}"),
+        }
+
+        if let Some(set_id) = group_sticker_set_id(chat).await {
+            match tdlib::functions::get_sticker_set(set_id, client_id).await {
+                Ok(StickerSet::StickerSet(data)) => {
+                    sections.push(SectionInfo {
+                        title: gettext("Group Stickers"),
+                        icon_name: Some("system-users-symbolic"),
+                        thumbnail: data.stickers.first().cloned(),
+                        content: SectionContent::Stickers(data.stickers),
+                    });
+                }
+                Err(e) => log::warn!("Failed to load the group sticker set: {e:?}"),
+            }
+        }
+
+        let result = tdlib::functions::get_installed_sticker_sets(
+            tdlib::enums::StickerType::Regular,
+            client_id,
+        )
+        .await;
+        match result {
+            Ok(StickerSets::StickerSets(data)) => {
+                sections.extend(data.sets.into_iter().map(|set| SectionInfo {
+                    title: set.title,
+                    icon_name: None,
+                    thumbnail: set.covers.into_iter().next(),
+                    content: SectionContent::StickerSet(set.id),
+                }));
+            }
+            Err(e) => log::warn!("Failed to load the installed sticker sets: {e:?}"),
+        }
+
+        sections
+    }
+
+    fn set_sections(&self, sections: Vec<SectionInfo>, session: &model::ClientStateSession) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.sections_box.first_child() {
+            imp.sections_box.remove(&child);
+        }
+        while let Some(child) = imp.sets_box.first_child() {
+            imp.sets_box.remove(&child);
+        }
+
+        let mut new_sections = Vec::with_capacity(sections.len());
+
+        for info in sections {
+            let header = gtk::Label::builder()
+                .label(&info.title)
+                .xalign(0.0)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            header.add_css_class("heading");
+            header.add_css_class("dim-label");
+
+            let flow_box = new_flow_box();
+
+            imp.sections_box.append(&header);
+            imp.sections_box.append(&flow_box);
+
+            let button = gtk::Button::builder().tooltip_text(&info.title).build();
+            button.add_css_class("flat");
+            match info.thumbnail {
+                Some(thumbnail) => {
+                    let sticker = new_sticker(SET_THUMBNAIL_SIZE);
+                    sticker.update_sticker(thumbnail, false, session.clone());
+                    button.set_child(Some(&sticker));
+                }
+                None => match info.icon_name {
+                    Some(icon_name) => button.set_icon_name(icon_name),
+                    None => {
+                        button.set_label(&info.title.chars().next().unwrap_or_default().to_string())
+                    }
+                },
+            }
+            button.connect_clicked(clone!(@weak self as obj, @weak header => move |_| {
+                obj.scroll_to_section(&header);
+            }));
+            imp.sets_box.append(&button);
+
+            new_sections.push(Rc::new(Section {
+                content: RefCell::new(Some(info.content)),
+                header,
+                flow_box,
+            }));
+        }
+
+        let is_empty = new_sections.is_empty();
+        imp.sections.replace(new_sections);
+
+        if imp.search_entry.text().is_empty() {
+            imp.stack
+                .set_visible_child_name(if is_empty { "empty" } else { "stickers" });
+        }
+
+        imp.scrolled_window.vadjustment().set_value(0.0);
+
+        // Wait for the sections to be allocated before checking which ones are visible
+        glib::idle_add_local_once(clone!(@weak self as obj => move || {
+            obj.load_visible_sections();
+        }));
+    }
+
+    fn scroll_to_section(&self, header: &gtk::Label) {
+        let imp = self.imp();
+
+        imp.search_entry.set_text("");
+        if let Some(point) = header.compute_point(&*imp.sections_box, &graphene::Point::zero()) {
+            imp.scrolled_window
+                .vadjustment()
+                .set_value(point.y() as f64);
+        }
+    }
+
+    /// Fills the sections that are visible or about to be scrolled into view.
+    fn load_visible_sections(&self) {
+        let imp = self.imp();
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let session = chat.session_();
+
+        let adjustment = imp.scrolled_window.vadjustment();
+        // Preload one page in advance
+        let limit = adjustment.value() + adjustment.page_size() * 2.0;
+
+        for section in imp.sections.borrow().iter() {
+            let Some(point) = section
+                .header
+                .compute_point(&*imp.sections_box, &graphene::Point::zero())
+            else {
+                continue;
+            };
+            if point.y() as f64 > limit {
+                break;
+            }
+
+            match section.content.take() {
+                Some(SectionContent::Stickers(stickers)) => {
+                    self.fill_flow_box(&section.flow_box, stickers, &session);
+                }
+                Some(SectionContent::StickerSet(set_id)) => {
+                    let section = section.clone();
+                    let client_id = session.client_().id();
+
+                    utils::spawn(clone!(@weak self as obj, @weak session => async move {
+                        match tdlib::functions::get_sticker_set(set_id, client_id).await {
+                            Ok(tdlib::enums::StickerSet::StickerSet(data)) => {
+                                obj.fill_flow_box(&section.flow_box, data.stickers, &session);
+                            }
+                            Err(e) => {
+                                log::warn!("Failed to load a sticker set: {e:?}");
+                                // Retry when the section is scrolled into view again
+                                section.content.replace(Some(SectionContent::StickerSet(set_id)));
+                            }
+                        }
+                    }));
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn fill_flow_box(
+        &self,
+        flow_box: &gtk::FlowBox,
+        stickers: Vec<tdlib::types::Sticker>,
+        session: &model::ClientStateSession,
+    ) {
+        for sticker in stickers {
+            flow_box.append(&self.new_sticker_button(sticker, session));
+        }
+    }
+
+    fn new_sticker_button(
+        &self,
+        sticker: tdlib::types::Sticker,
+        session: &model::ClientStateSession,
+    ) -> gtk::Button {
+        let sticker_widget = new_sticker(STICKER_SIZE);
+        sticker_widget.update_sticker(sticker.clone(), false, session.clone());
+
+        let button = gtk::Button::builder()
+            .child(&sticker_widget)
+            .tooltip_text(&sticker.emoji)
+            .build();
+        button.add_css_class("flat");
+        button.add_css_class("sticker");
+
+        // Play animated stickers again when hovered
+        let motion = gtk::EventControllerMotion::new();
+        motion.connect_enter(clone!(@weak sticker_widget => move |_, _, _| {
+            sticker_widget.play_animation();
+        }));
+        button.add_controller(motion);
+
+        button.connect_clicked(clone!(@weak self as obj => move |_| {
+            obj.emit_by_name::<()>(
+                "sticker-selected",
+                &[&model::BoxedSticker(sticker.clone())],
+            );
+        }));
+
+        button
+    }
+
+    #[template_callback]
+    fn on_search_changed(&self) {
+        let imp = self.imp();
+
+        let generation = imp.search_generation.get().wrapping_add(1);
+        imp.search_generation.set(generation);
+
+        let query = imp.search_entry.text().trim().to_owned();
+        if query.is_empty() {
+            let is_empty = imp.sections.borrow().is_empty();
+            imp.stack
+                .set_visible_child_name(if is_empty { "empty" } else { "stickers" });
+            return;
+        }
+
+        let Some(chat) = self.chat() else {
+            return;
+        };
+        let session = chat.session_();
+        let client_id = session.client_().id();
+
+        imp.stack.set_visible_child_name("loading");
+
+        utils::spawn(clone!(@weak self as obj, @weak session => async move {
+            let result = tdlib::functions::search_stickers(
+                tdlib::enums::StickerType::Regular,
+                query,
+                SEARCH_LIMIT,
+                client_id,
+            )
+            .await;
+
+            let imp = obj.imp();
+            if imp.search_generation.get() != generation {
+                return;
+            }
+
+            while let Some(child) = imp.search_flow_box.first_child() {
+                imp.search_flow_box.remove(&child);
+            }
+
+            let stickers = match result {
+                Ok(tdlib::enums::Stickers::Stickers(data)) => data.stickers,
+                Err(e) => {
+                    log::warn!("Failed to search stickers: {e:?}");
+                    Vec::new()
+                }
+            };
+
+            let is_empty = stickers.is_empty();
+            obj.fill_flow_box(&imp.search_flow_box, stickers, &session);
+
+            imp.stack
+                .set_visible_child_name(if is_empty { "empty" } else { "search" });
+        }));
+    }
+}
+
+/// Returns the id of the sticker set of the group, if any.
+async fn group_sticker_set_id(chat: &model::Chat) -> Option<i64> {
+    let model::ChatType::Supergroup(supergroup) = chat.chat_type() else {
+        return None;
+    };
+    if supergroup.is_channel() {
+        return None;
+    }
+
+    let client_id = chat.session_().client_().id();
+    match tdlib::functions::get_supergroup_full_info(supergroup.id(), client_id).await {
+        Ok(tdlib::enums::SupergroupFullInfo::SupergroupFullInfo(full_info)) => {
+            Some(full_info.sticker_set_id).filter(|id| *id != 0)
+        }
+        Err(e) => {
+            log::warn!("Failed to load the full info of a supergroup: {e:?}");
+            None
+        }
+    }
+}
+
+fn new_sticker(size: i32) -> ui::Sticker {
+    glib::Object::builder()
+        .property("longer-side-size", size)
+        .build()
+}
+
+fn new_flow_box() -> gtk::FlowBox {
+    gtk::FlowBox::builder()
+        .homogeneous(true)
+        .selection_mode(gtk::SelectionMode::None)
+        .min_children_per_line(4)
+        .max_children_per_line(5)
+        .build()
+}
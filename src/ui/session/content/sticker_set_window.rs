@@ -0,0 +1,242 @@
+use std::cell::Cell;
+use std::cell::OnceCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gdk;
+use gtk::glib;
+use gtk::CompositeTemplate;
+
+use crate::i18n::ngettext_f;
+use crate::model;
+use crate::ui;
+use crate::utils;
+
+/// The size of the longer side of the previewed stickers.
+const STICKER_SIZE: i32 = 72;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/content/sticker_set_window.ui")]
+    pub(crate) struct StickerSetWindow {
+        pub(super) session: glib::WeakRef<model::ClientStateSession>,
+        pub(super) set_id: OnceCell<i64>,
+        pub(super) is_installed: Cell<bool>,
+        pub(super) sticker_count: Cell<u32>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) flow_box: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub(super) install_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for StickerSetWindow {
+        const NAME: &'static str = "PaplStickerSetWindow";
+        type Type = super::StickerSetWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for StickerSetWindow {}
+    impl WidgetImpl for StickerSetWindow {}
+    impl WindowImpl for StickerSetWindow {}
+    impl AdwWindowImpl for StickerSetWindow {}
+
+    #[gtk::template_callbacks]
+    impl StickerSetWindow {
+        #[template_callback]
+        fn on_key_pressed(
+            &self,
+            key: gdk::Key,
+            _: u32,
+            modifier: gdk::ModifierType,
+            _: &gtk::EventControllerKey,
+        ) -> glib::Propagation {
+            if key == gdk::Key::Escape
+                || (key == gdk::Key::w && modifier == gdk::ModifierType::CONTROL_MASK)
+            {
+                self.obj().close();
+            }
+
+            glib::Propagation::Proceed
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A window previewing the stickers of a sticker set, to add or remove it.
+    pub(crate) struct StickerSetWindow(ObjectSubclass<imp::StickerSetWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+#[gtk::template_callbacks]
+impl StickerSetWindow {
+    pub(crate) fn new(
+        parent: Option<&gtk::Window>,
+        session: &model::ClientStateSession,
+        set_id: i64,
+    ) -> Self {
+        let obj: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+        let imp = obj.imp();
+
+        imp.session.set(Some(session));
+        imp.set_id.set(set_id).unwrap();
+
+        utils::spawn(clone!(@weak obj => async move {
+            obj.load().await;
+        }));
+
+        obj
+    }
+
+    async fn load(&self) {
+        let imp = self.imp();
+
+        let Some(session) = imp.session.upgrade() else {
+            return;
+        };
+        let set_id = *imp.set_id.get().unwrap();
+
+        match tdlib::functions::get_sticker_set(set_id, session.client_().id()).await {
+            Ok(tdlib::enums::StickerSet::StickerSet(data)) => {
+                imp.window_title.set_title(&data.title);
+                let count = data.stickers.len() as u32;
+                imp.window_title.set_subtitle(&ngettext_f(
+                    "{num} sticker",
+                    "{num} stickers",
+                    count,
+                    &[("num", &count.to_string())],
+                ));
+
+                for sticker in data.stickers {
+                    imp.flow_box.append(&new_sticker_preview(sticker, &session));
+                }
+
+                imp.sticker_count.set(count);
+                self.set_installed(data.is_installed && !data.is_archived);
+                imp.stack.set_visible_child_name("stickers");
+            }
+            Err(e) => {
+                log::warn!("Failed to load a sticker set: {e:?}");
+                imp.stack.set_visible_child_name("error");
+            }
+        }
+    }
+
+    fn set_installed(&self, is_installed: bool) {
+        let imp = self.imp();
+        imp.is_installed.set(is_installed);
+
+        let count = imp.sticker_count.get();
+        let num = count.to_string();
+        let label = if is_installed {
+            imp.install_button.remove_css_class("suggested-action");
+            imp.install_button.add_css_class("destructive-action");
+            ngettext_f(
+                "Remove {num} Sticker",
+                "Remove {num} Stickers",
+                count,
+                &[("num", &num)],
+            )
+        } else {
+            imp.install_button.remove_css_class("destructive-action");
+            imp.install_button.add_css_class("suggested-action");
+            ngettext_f(
+                "Add {num} Sticker",
+                "Add {num} Stickers",
+                count,
+                &[("num", &num)],
+            )
+        };
+
+        imp.install_button.set_label(&label);
+        imp.install_button.set_visible(true);
+    }
+
+    #[template_callback]
+    fn on_install_button_clicked(&self) {
+        utils::spawn(clone!(@weak self as obj => async move {
+            obj.toggle_installed().await;
+        }));
+    }
+
+    async fn toggle_installed(&self) {
+        let imp = self.imp();
+
+        let Some(session) = imp.session.upgrade() else {
+            return;
+        };
+        let set_id = *imp.set_id.get().unwrap();
+        let is_installed = !imp.is_installed.get();
+
+        imp.install_button.set_sensitive(false);
+        let result = tdlib::functions::change_sticker_set(
+            set_id,
+            is_installed,
+            false,
+            session.client_().id(),
+        )
+        .await;
+        imp.install_button.set_sensitive(true);
+
+        match result {
+            Ok(_) => {
+                self.set_installed(is_installed);
+
+                imp.toast_overlay
+                    .add_toast(adw::Toast::new(&if is_installed {
+                        gettext("Stickers added")
+                    } else {
+                        gettext("Stickers removed")
+                    }));
+            }
+            Err(e) => {
+                log::warn!("Failed to change a sticker set: {e:?}");
+                imp.toast_overlay.add_toast(adw::Toast::new(&gettext(
+                    "Failed to change the sticker set",
+                )));
+            }
+        }
+    }
+}
+
+fn new_sticker_preview(
+    sticker: tdlib::types::Sticker,
+    session: &model::ClientStateSession,
+) -> gtk::Widget {
+    let sticker_widget: ui::Sticker = glib::Object::builder()
+        .property("longer-side-size", STICKER_SIZE)
+        .build();
+    sticker_widget.set_tooltip_text(Some(&sticker.emoji));
+    sticker_widget.update_sticker(sticker, false, session.clone());
+
+    // Play animated stickers again when hovered
+    let motion = gtk::EventControllerMotion::new();
+    motion.connect_enter(clone!(@weak sticker_widget => move |_, _, _| {
+        sticker_widget.play_animation();
+    }));
+    sticker_widget.add_controller(motion);
+
+    sticker_widget.upcast()
+}
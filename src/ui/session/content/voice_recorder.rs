@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use gst::prelude::*;
+use gtk::glib;
+
+use crate::utils;
+
+/// The number of samples of the waveform sent along with a voice note.
+const WAVEFORM_SAMPLES: usize = 100;
+
+/// The pipeline used to record Opus/OGG voice notes. The level element reports the loudness
+/// of the input, which is then used to build the waveform.
+const PIPELINE_DESCRIPTION: &str = "autoaudiosrc \
+    ! audioconvert \
+    ! level name=level interval=50000000 post-messages=true \
+    ! audioresample \
+    ! audio/x-raw,rate=48000,channels=1 \
+    ! opusenc bitrate=32000 \
+    ! oggmux \
+    ! filesink name=sink";
+
+/// A voice note that finished being recorded and that is ready to be sent.
+#[derive(Debug)]
+pub(crate) struct RecordedVoiceNote {
+    pub(crate) path: String,
+    pub(crate) duration: i32,
+    pub(crate) waveform: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct VoiceRecorder {
+    pipeline: gst::Pipeline,
+    path: PathBuf,
+    started: Instant,
+    levels: Rc<RefCell<Vec<f64>>>,
+    eos_receiver: async_channel::Receiver<Result<(), glib::Error>>,
+    _bus_watch: gst::bus::BusWatchGuard,
+}
+
+impl VoiceRecorder {
+    /// Starts recording from the default audio input.
+    pub(crate) fn start() -> anyhow::Result<Self> {
+        // This fails again if GStreamer couldn't be initialized on startup
+        gst::init()?;
+
+        let temp_dir =
+            utils::temp_dir().ok_or_else(|| anyhow!("The temporary directory doesn't exist"))?;
+        let path = temp_dir
+            .join(format!(
+                "voice_note_{}",
+                glib::DateTime::now_utc()?.to_unix()
+            ))
+            .with_extension("ogg");
+
+        let pipeline = gst::parse::launch(PIPELINE_DESCRIPTION)?
+            .downcast::<gst::Pipeline>()
+            .map_err(|_| anyhow!("The recording pipeline is not a pipeline"))?;
+        pipeline
+            .by_name("sink")
+            .ok_or_else(|| anyhow!("The recording pipeline has no sink"))?
+            .set_property("location", path.to_str());
+
+        let levels = Rc::new(RefCell::new(Vec::new()));
+        let (eos_sender, eos_receiver) = async_channel::bounded(1);
+
+        let bus_watch = pipeline.bus().unwrap().add_watch_local({
+            let levels = levels.clone();
+            move |_, message| {
+                match message.view() {
+                    gst::MessageView::Element(element) => {
+                        if let Some(level) = element
+                            .structure()
+                            .filter(|structure| structure.name() == "level")
+                            .and_then(|structure| structure.get::<glib::ValueArray>("rms").ok())
+                            .and_then(|rms| rms.first().and_then(|value| value.get::<f64>().ok()))
+                        {
+                            // Convert the decibels to a linear amplitude
+                            levels.borrow_mut().push(10f64.powf(level / 20.0));
+                        }
+                    }
+                    gst::MessageView::Eos(_) => {
+                        let _ = eos_sender.try_send(Ok(()));
+                    }
+                    gst::MessageView::Error(error) => {
+                        let _ = eos_sender.try_send(Err(error.error()));
+                    }
+                    _ => {}
+                }
+
+                glib::ControlFlow::Continue
+            }
+        })?;
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        Ok(Self {
+            pipeline,
+            path,
+            started: Instant::now(),
+            levels,
+            eos_receiver,
+            _bus_watch: bus_watch,
+        })
+    }
+
+    /// Returns the time elapsed since the recording started.
+    pub(crate) fn duration(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Stops recording and waits for the file to be completely written.
+    pub(crate) async fn finish(self) -> anyhow::Result<RecordedVoiceNote> {
+        let duration = self.duration().as_secs_f64().round() as i32;
+
+        self.pipeline.send_event(gst::event::Eos::new());
+        let result = self.eos_receiver.recv().await;
+
+        self.pipeline.set_state(gst::State::Null)?;
+        result??;
+
+        Ok(RecordedVoiceNote {
+            path: self
+                .path
+                .to_str()
+                .ok_or_else(|| anyhow!("The recording path is not valid UTF-8"))?
+                .to_string(),
+            duration,
+            waveform: utils::encode_waveform(&waveform_samples(&self.levels.borrow())),
+        })
+    }
+
+    /// Stops recording and discards the recorded audio.
+    pub(crate) fn cancel(self) {
+        if let Err(e) = self.pipeline.set_state(gst::State::Null) {
+            log::warn!("Failed to stop the voice note recording: {e:?}");
+        }
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove a cancelled voice note: {e:?}");
+        }
+    }
+}
+
+/// Reduces the recorded levels to the number of samples of a voice note waveform, each in the
+/// `0..32` range.
+fn waveform_samples(levels: &[f64]) -> Vec<u8> {
+    if levels.is_empty() {
+        return vec![];
+    }
+
+    let n_samples = WAVEFORM_SAMPLES.min(levels.len());
+    let samples: Vec<f64> = (0..n_samples)
+        .map(|index| {
+            let start = index * levels.len() / n_samples;
+            let end = (index + 1) * levels.len() / n_samples;
+            levels[start..end.max(start + 1)]
+                .iter()
+                .copied()
+                .fold(0.0, f64::max)
+        })
+        .collect();
+
+    let max = samples.iter().copied().fold(0.0, f64::max);
+    samples
+        .into_iter()
+        .map(|sample| {
+            if max > 0.0 {
+                (sample / max * 31.0).round() as u8
+            } else {
+                0
+            }
+        })
+        .collect()
+}
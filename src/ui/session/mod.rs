@@ -16,14 +16,22 @@ use gtk::CompositeTemplate;
 
 pub(crate) use self::contacts_window::ContactsWindow;
 pub(crate) use self::contacts_window::Row as ContactRow;
+pub(crate) use self::content::AnimationPicker;
+pub(crate) use self::content::AnimationThumbnail;
+pub(crate) use self::content::AudioPlayerBar;
 pub(crate) use self::content::Background;
 pub(crate) use self::content::ChatActionBar;
 pub(crate) use self::content::ChatHistory;
 pub(crate) use self::content::ChatHistoryRow;
 pub(crate) use self::content::ChatInfoWindow;
+pub(crate) use self::content::ChatSearchBar;
 pub(crate) use self::content::Content;
 pub(crate) use self::content::EventRow;
+pub(crate) use self::content::ForumTopicList;
+pub(crate) use self::content::ForwardWindow;
 pub(crate) use self::content::MediaPicture;
+pub(crate) use self::content::MediaViewer;
+pub(crate) use self::content::MessageAudio;
 pub(crate) use self::content::MessageBase;
 pub(crate) use self::content::MessageBaseExt;
 pub(crate) use self::content::MessageBaseImpl;
@@ -34,13 +42,27 @@ pub(crate) use self::content::MessageIndicators;
 pub(crate) use self::content::MessageLabel;
 pub(crate) use self::content::MessageLocation;
 pub(crate) use self::content::MessagePhoto;
+pub(crate) use self::content::MessagePoll;
+pub(crate) use self::content::MessageReactionPicker;
+pub(crate) use self::content::MessageReactions;
 pub(crate) use self::content::MessageReply;
 pub(crate) use self::content::MessageRow;
 pub(crate) use self::content::MessageSticker;
 pub(crate) use self::content::MessageText;
 pub(crate) use self::content::MessageVenue;
 pub(crate) use self::content::MessageVideo;
+pub(crate) use self::content::MessageVideoNote;
+pub(crate) use self::content::MessageVoiceNote;
+pub(crate) use self::content::MessageWaveform;
+pub(crate) use self::content::MessageWebPage;
+pub(crate) use self::content::PinnedMessagesBar;
+pub(crate) use self::content::ScheduledMessagesWindow;
 pub(crate) use self::content::SendMediaWindow;
+pub(crate) use self::content::SendPollWindow;
+pub(crate) use self::content::SharedMediaRow;
+pub(crate) use self::content::SharedMediaThumbnail;
+pub(crate) use self::content::StickerPicker;
+pub(crate) use self::content::StickerSetWindow;
 pub(crate) use self::preferences_window::PreferencesWindow;
 pub(crate) use self::row::Row;
 pub(crate) use self::sidebar::Avatar as SidebarAvatar;
@@ -53,6 +75,7 @@ pub(crate) use self::sidebar::MiniThumbnail as SidebarMiniThumbnail;
 pub(crate) use self::sidebar::Row as SidebarRow;
 pub(crate) use self::sidebar::Search as SidebarSearch;
 pub(crate) use self::sidebar::SearchItemRow as SidebarSearchItemRow;
+pub(crate) use self::sidebar::SearchMessageRow as SidebarSearchMessageRow;
 pub(crate) use self::sidebar::SearchRow as SidebarSearchRow;
 pub(crate) use self::sidebar::SearchSection as SidebarSearchSection;
 pub(crate) use self::sidebar::SearchSectionRow as SidebarSearchSectionRow;
@@ -189,6 +212,13 @@ impl Session {
         }
     }
 
+    /// Opens the chat of the message and scrolls to the message.
+    pub(crate) fn select_message(&self, message: &model::Message) {
+        let imp = self.imp();
+        imp.sidebar.set_selected_chat(Some(&message.chat_()));
+        imp.content.scroll_to_message(message.id());
+    }
+
     pub(crate) fn handle_paste_action(&self) {
         self.imp().content.handle_paste_action();
     }
@@ -1,4 +1,5 @@
 use std::cell::OnceCell;
+use std::cell::RefCell;
 use std::sync::OnceLock;
 
 use adw::prelude::*;
@@ -10,9 +11,14 @@ use gtk::glib;
 use gtk::CompositeTemplate;
 
 use crate::config;
+use crate::i18n::ngettext_f;
+use crate::model;
 use crate::ui;
 use crate::utils;
 
+/// The size of the longer side of the thumbnails of the sticker sets.
+const STICKER_SET_THUMBNAIL_SIZE: i32 = 32;
+
 mod imp {
     use super::*;
 
@@ -20,12 +26,20 @@ mod imp {
     #[template(resource = "/app/drey/paper-plane/ui/session/preferences_window.ui")]
     pub(crate) struct PreferencesWindow {
         pub(super) session: OnceCell<ui::Session>,
+        /// The installed sticker sets, in the order in which they're shown
+        pub(super) sticker_sets: RefCell<Vec<tdlib::types::StickerSetInfo>>,
         #[template_child]
         pub(super) follow_system_colors_switch: TemplateChild<gtk::Switch>,
         #[template_child]
         pub(super) dark_theme_switch: TemplateChild<gtk::Switch>,
         #[template_child]
         pub(super) cache_size_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) suggest_stickers_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) sticker_sets_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) sticker_sets_list_box: TemplateChild<gtk::ListBox>,
     }
 
     #[glib::object_subclass]
@@ -36,6 +50,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_instance_callbacks();
 
             klass.install_action_async(
                 "preferences.clear-cache",
@@ -44,6 +59,38 @@ mod imp {
                     widget.clear_cache().await;
                 },
             );
+            klass.install_action_async(
+                "preferences.move-sticker-set-up",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let set_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.move_sticker_set(set_id, -1).await;
+                },
+            );
+            klass.install_action_async(
+                "preferences.move-sticker-set-down",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let set_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.move_sticker_set(set_id, 1).await;
+                },
+            );
+            klass.install_action_async(
+                "preferences.archive-sticker-set",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let set_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.change_sticker_set(set_id, true).await;
+                },
+            );
+            klass.install_action_async(
+                "preferences.remove-sticker-set",
+                Some(glib::VariantTy::INT64),
+                |widget, _, variant| async move {
+                    let set_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.change_sticker_set(set_id, false).await;
+                },
+            );
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -99,6 +146,9 @@ mod imp {
             utils::spawn(clone!(@weak obj => async move {
                 obj.calculate_cache_size().await;
             }));
+            utils::spawn(clone!(@weak obj => async move {
+                obj.load_sticker_sets().await;
+            }));
         }
     }
 
@@ -113,6 +163,7 @@ glib::wrapper! {
         @extends gtk::Widget, gtk::Window, adw::Window, adw::PreferencesWindow;
 }
 
+#[gtk::template_callbacks]
 impl PreferencesWindow {
     pub(crate) fn new(parent_window: Option<&gtk::Window>, session: &ui::Session) -> Self {
         glib::Object::builder()
@@ -173,6 +224,186 @@ impl PreferencesWindow {
             .bind_property("dark", &*imp.dark_theme_switch, "active")
             .flags(glib::BindingFlags::SYNC_CREATE)
             .build();
+
+        gio::Settings::new(config::APP_ID)
+            .bind(
+                "suggest-stickers-by-emoji",
+                &*imp.suggest_stickers_row,
+                "active",
+            )
+            .build();
+    }
+
+    fn client_state_session(&self) -> model::ClientStateSession {
+        self.session().model().unwrap()
+    }
+
+    async fn load_sticker_sets(&self) {
+        let client_id = self.client_state_session().client_().id();
+        let result = tdlib::functions::get_installed_sticker_sets(
+            tdlib::enums::StickerType::Regular,
+            client_id,
+        )
+        .await;
+
+        match result {
+            Ok(tdlib::enums::StickerSets::StickerSets(data)) => {
+                self.imp().sticker_sets.replace(data.sets);
+                self.update_sticker_sets();
+            }
+            Err(e) => {
+                log::warn!("Error getting the installed sticker sets: {e:?}");
+            }
+        }
+    }
+
+    fn update_sticker_sets(&self) {
+        let imp = self.imp();
+
+        while let Some(row) = imp.sticker_sets_list_box.first_child() {
+            imp.sticker_sets_list_box.remove(&row);
+        }
+
+        let session = self.client_state_session();
+        let sticker_sets = imp.sticker_sets.borrow();
+        for (index, set) in sticker_sets.iter().enumerate() {
+            let row = new_sticker_set_row(set, &session);
+
+            let target = set.id.to_variant();
+
+            let up_button = gtk::Button::builder()
+                .icon_name("go-up-symbolic")
+                .tooltip_text(gettext("Move Up"))
+                .valign(gtk::Align::Center)
+                .action_name("preferences.move-sticker-set-up")
+                .action_target(&target)
+                .sensitive(index > 0)
+                .build();
+            up_button.add_css_class("flat");
+            row.add_suffix(&up_button);
+
+            let down_button = gtk::Button::builder()
+                .icon_name("go-down-symbolic")
+                .tooltip_text(gettext("Move Down"))
+                .valign(gtk::Align::Center)
+                .action_name("preferences.move-sticker-set-down")
+                .action_target(&target)
+                .sensitive(index + 1 < sticker_sets.len())
+                .build();
+            down_button.add_css_class("flat");
+            row.add_suffix(&down_button);
+
+            let menu = gio::Menu::new();
+            let item = gio::MenuItem::new(Some(&gettext("_Archive")), None);
+            item.set_action_and_target_value(
+                Some("preferences.archive-sticker-set"),
+                Some(&target),
+            );
+            menu.append_item(&item);
+            let item = gio::MenuItem::new(Some(&gettext("_Remove")), None);
+            item.set_action_and_target_value(Some("preferences.remove-sticker-set"), Some(&target));
+            menu.append_item(&item);
+
+            let menu_button = gtk::MenuButton::builder()
+                .icon_name("view-more-symbolic")
+                .menu_model(&menu)
+                .valign(gtk::Align::Center)
+                .build();
+            menu_button.add_css_class("flat");
+            row.add_suffix(&menu_button);
+
+            imp.sticker_sets_list_box.append(&row);
+        }
+
+        imp.sticker_sets_stack
+            .set_visible_child_name(if sticker_sets.is_empty() {
+                "empty"
+            } else {
+                "list"
+            });
+    }
+
+    async fn move_sticker_set(&self, set_id: i64, offset: isize) {
+        let imp = self.imp();
+
+        let set_ids = {
+            let mut sticker_sets = imp.sticker_sets.borrow_mut();
+            let Some(index) = sticker_sets.iter().position(|set| set.id == set_id) else {
+                return;
+            };
+            let Some(new_index) = index
+                .checked_add_signed(offset)
+                .filter(|i| *i < sticker_sets.len())
+            else {
+                return;
+            };
+
+            sticker_sets.swap(index, new_index);
+            sticker_sets.iter().map(|set| set.id).collect::<Vec<_>>()
+        };
+        self.update_sticker_sets();
+
+        let client_id = self.client_state_session().client_().id();
+        let result = tdlib::functions::reorder_installed_sticker_sets(
+            tdlib::enums::StickerType::Regular,
+            set_ids,
+            client_id,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Error reordering the installed sticker sets: {e:?}");
+            self.add_toast(adw::Toast::new(&gettext(
+                "Failed to reorder the sticker sets",
+            )));
+            self.load_sticker_sets().await;
+        }
+    }
+
+    /// Archives or removes an installed sticker set.
+    async fn change_sticker_set(&self, set_id: i64, archive: bool) {
+        let client_id = self.client_state_session().client_().id();
+        let result = tdlib::functions::change_sticker_set(set_id, false, archive, client_id).await;
+
+        match result {
+            Ok(_) => {
+                self.imp()
+                    .sticker_sets
+                    .borrow_mut()
+                    .retain(|set| set.id != set_id);
+                self.update_sticker_sets();
+
+                self.add_toast(adw::Toast::new(&if archive {
+                    gettext("Sticker set archived")
+                } else {
+                    gettext("Sticker set removed")
+                }));
+            }
+            Err(e) => {
+                log::warn!("Error changing a sticker set: {e:?}");
+                self.add_toast(adw::Toast::new(&gettext(
+                    "Failed to change the sticker set",
+                )));
+            }
+        }
+    }
+
+    #[template_callback]
+    fn on_sticker_set_row_activated(&self, row: &gtk::ListBoxRow) {
+        let sticker_set = self
+            .imp()
+            .sticker_sets
+            .borrow()
+            .get(row.index() as usize)
+            .map(|set| set.id);
+
+        if let Some(set_id) = sticker_set {
+            ui::StickerSetWindow::new(
+                Some(self.upcast_ref()),
+                &self.client_state_session(),
+                set_id,
+            )
+            .present();
+        }
     }
 
     async fn calculate_cache_size(&self) {
@@ -220,3 +451,31 @@ impl PreferencesWindow {
         self.imp().session.get().unwrap()
     }
 }
+
+fn new_sticker_set_row(
+    set: &tdlib::types::StickerSetInfo,
+    session: &model::ClientStateSession,
+) -> adw::ActionRow {
+    let row = adw::ActionRow::builder()
+        .title(&set.title)
+        .subtitle(ngettext_f(
+            "{num} sticker",
+            "{num} stickers",
+            set.size as u32,
+            &[("num", &set.size.to_string())],
+        ))
+        .use_markup(false)
+        .activatable(true)
+        .build();
+
+    if let Some(thumbnail) = set.covers.first() {
+        let sticker: ui::Sticker = glib::Object::builder()
+            .property("longer-side-size", STICKER_SET_THUMBNAIL_SIZE)
+            .build();
+        sticker.set_valign(gtk::Align::Center);
+        sticker.update_sticker(thumbnail.clone(), false, session.clone());
+        row.add_prefix(&sticker);
+    }
+
+    row
+}
@@ -26,6 +26,7 @@ pub(crate) use self::chat_list::ChatList;
 pub(crate) use self::mini_thumbnail::MiniThumbnail;
 pub(crate) use self::row::Row;
 pub(crate) use self::search::ItemRow as SearchItemRow;
+pub(crate) use self::search::MessageRow as SearchMessageRow;
 pub(crate) use self::search::Row as SearchRow;
 pub(crate) use self::search::Search;
 pub(crate) use self::search::Section as SearchSection;
@@ -38,6 +38,9 @@ mod imp {
         pub(super) minithumbnail: TemplateChild<ui::SidebarMiniThumbnail>,
         #[template_child]
         pub(super) subtitle_label: TemplateChild<gtk::Inscription>,
+        /// Used instead of the subtitle label when the last message contains custom emoji
+        #[template_child]
+        pub(super) custom_emoji_subtitle_label: TemplateChild<ui::MessageLabel>,
         #[template_child]
         pub(super) status_stack: TemplateChild<gtk::Stack>,
         #[template_child]
@@ -140,6 +143,10 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
+            // Don't request the width of the whole message preview
+            self.custom_emoji_subtitle_label.set_single_line();
+            self.custom_emoji_subtitle_label.set_max_width_chars(1);
+
             let obj = &*self.obj();
 
             obj.setup_expressions();
@@ -473,6 +480,9 @@ impl Row {
             let imp = self.imp();
             let chat = item.chat_();
             let label = &imp.subtitle_label;
+            let custom_emoji_label = &imp.custom_emoji_subtitle_label;
+
+            let mut custom_emoji_entities = Vec::new();
 
             if let Some(actions) = chat.actions().last().map(|a| strings::chat_action(&a)) {
                 label.set_text(Some(&actions));
@@ -484,15 +494,35 @@ impl Row {
                 label.set_text(Some(&draft_message));
                 label.add_css_class("dim-label");
                 label.remove_css_class("accent");
-            } else if let Some(last_message) =
-                chat.last_message().map(|m| strings::message_content(&m))
-            {
-                label.set_text(Some(&last_message));
+            } else if let Some(last_message) = chat.last_message() {
+                let text = strings::message_content(&last_message);
+                custom_emoji_entities =
+                    utils::preview_text_entities(&text, last_message.content().0)
+                        .into_iter()
+                        .filter(|entity| {
+                            matches!(entity.r#type, tdlib::enums::TextEntityType::CustomEmoji(_))
+                        })
+                        .collect();
+
+                if !custom_emoji_entities.is_empty() {
+                    custom_emoji_label.set_label(utils::escape(&text));
+                }
+
+                label.set_text(Some(&text));
                 label.add_css_class("dim-label");
                 label.remove_css_class("accent");
             } else {
                 label.set_text(None);
             }
+
+            // Only use the heavier message label when there are custom emoji to render
+            let has_custom_emoji = !custom_emoji_entities.is_empty();
+            if !has_custom_emoji {
+                custom_emoji_label.set_label(String::new());
+            }
+            custom_emoji_label.set_entities(&custom_emoji_entities, &chat.session_());
+            custom_emoji_label.set_visible(has_custom_emoji);
+            label.set_visible(!has_custom_emoji);
         }
     }
 
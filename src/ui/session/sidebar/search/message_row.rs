@@ -0,0 +1,104 @@
+use gettextrs::gettext;
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::CompositeTemplate;
+
+use crate::model;
+use crate::strings;
+use crate::ui;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties, CompositeTemplate)]
+    #[properties(wrapper_type = super::MessageRow)]
+    #[template(resource = "/app/drey/paper-plane/ui/session/sidebar/search/message_row.ui")]
+    pub(crate) struct MessageRow {
+        #[property(get, set = Self::set_message, explicit_notify, nullable)]
+        pub(super) message: glib::WeakRef<model::Message>,
+        #[template_child]
+        pub(super) avatar: TemplateChild<ui::Avatar>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) message_label: TemplateChild<gtk::Inscription>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageRow {
+        const NAME: &'static str = "PaplSidebarSearchMessageRow";
+        type Type = super::MessageRow;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_css_name("sidebarsearchmessagerow");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageRow {
+        fn properties() -> &'static [glib::ParamSpec] {
+            Self::derived_properties()
+        }
+
+        fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            self.derived_set_property(id, value, pspec)
+        }
+
+        fn property(&self, id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            self.derived_property(id, pspec)
+        }
+
+        fn dispose(&self) {
+            self.dispose_template();
+        }
+    }
+
+    impl WidgetImpl for MessageRow {}
+
+    impl MessageRow {
+        fn set_message(&self, message: Option<&model::Message>) {
+            let obj = &*self.obj();
+            if obj.message().as_ref() == message {
+                return;
+            }
+
+            if let Some(message) = message {
+                let chat = message.chat_();
+
+                self.avatar.set_item(Some(chat.clone().upcast()));
+                self.title_label.set_text(Some(&if chat.is_own_chat() {
+                    gettext("Saved Messages")
+                } else {
+                    chat.title()
+                }));
+                self.message_label
+                    .set_text(Some(&strings::message_content(message)));
+            } else {
+                self.avatar.set_item(None);
+                self.title_label.set_text(None);
+                self.message_label.set_text(None);
+            }
+
+            self.message.set(message);
+            obj.notify_message();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageRow(ObjectSubclass<imp::MessageRow>)
+        @extends gtk::Widget;
+}
+
+impl MessageRow {
+    pub(crate) fn new(message: Option<&model::Message>) -> Self {
+        glib::Object::builder().property("message", message).build()
+    }
+}
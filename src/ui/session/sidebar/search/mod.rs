@@ -1,4 +1,5 @@
 mod item_row;
+mod message_row;
 mod row;
 mod section;
 mod section_row;
@@ -15,6 +16,7 @@ use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 
 pub(crate) use self::item_row::ItemRow;
+pub(crate) use self::message_row::MessageRow;
 pub(crate) use self::row::Row;
 pub(crate) use self::section::Section;
 pub(crate) use self::section::SectionType;
@@ -164,6 +166,7 @@ impl Search {
         let mut found_chat_ids: Vec<i64> = vec![];
 
         const MAX_KNOWN_CHATS: i32 = 50;
+        const MAX_MESSAGES: i32 = 30;
 
         imp.selection.set_model(Some(&list));
         list.connect_items_changed(clone!(@weak self as obj => move |list, _, _, _| {
@@ -303,7 +306,7 @@ impl Search {
         }
 
         // Search public chats
-        match tdlib::functions::search_public_chats(query, session.client_().id()).await {
+        match tdlib::functions::search_public_chats(query.clone(), session.client_().id()).await {
             Ok(tdlib::enums::Chats::Chats(data)) if !data.chat_ids.is_empty() => {
                 list.append(&Section::new(SectionType::Global));
 
@@ -326,6 +329,36 @@ impl Search {
             }
             _ => {}
         }
+
+        // Search messages in all chats
+        match tdlib::functions::search_messages(
+            None,
+            query,
+            String::new(),
+            MAX_MESSAGES,
+            None,
+            0,
+            0,
+            session.client_().id(),
+        )
+        .await
+        {
+            Ok(tdlib::enums::FoundMessages::FoundMessages(data)) if !data.messages.is_empty() => {
+                list.append(&Section::new(SectionType::Messages));
+
+                let messages: Vec<model::Message> = data
+                    .messages
+                    .into_iter()
+                    .map(|message| model::Message::new(&session.chat(message.chat_id), message))
+                    .collect();
+
+                list.extend_from_slice(&messages);
+            }
+            Err(e) => {
+                log::warn!("Error searching messages: {:?}", e);
+            }
+            _ => {}
+        }
     }
 
     #[template_callback]
@@ -342,6 +375,8 @@ impl Search {
             {
                 log::warn!("Failed to add recently found chat: {:?}", e);
             }
+        } else if let Some(message) = item.downcast_ref::<model::Message>() {
+            utils::ancestor::<_, ui::Session>(self).select_message(message);
         } else if let Some(user) = item.downcast_ref::<model::User>() {
             // TODO
             // session.select_chat(chat);
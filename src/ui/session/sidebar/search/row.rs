@@ -14,7 +14,7 @@ mod imp {
 
     #[derive(Debug, Default)]
     pub(crate) struct Row {
-        /// A `Chat` or `User`
+        /// A `Chat`, `User` or `Message`
         pub(super) item: glib::WeakRef<glib::Object>,
         pub(super) list_item: OnceCell<gtk::ListItem>,
         pub(super) child: RefCell<Option<gtk::Widget>>,
@@ -98,6 +98,9 @@ impl Row {
         {
             imp.list_item.get().unwrap().set_activatable(true);
             self.update_or_create_item_row(item);
+        } else if let Some(message) = item.and_then(|i| i.downcast_ref::<model::Message>()) {
+            imp.list_item.get().unwrap().set_activatable(true);
+            self.update_or_create_message_row(message);
         } else if let Some(section) = item
             .as_ref()
             .and_then(|i| i.downcast_ref::<ui::SidebarSearchSection>())
@@ -139,6 +142,23 @@ impl Row {
         }
     }
 
+    fn update_or_create_message_row(&self, message: &model::Message) {
+        let mut child_ref = self.imp().child.borrow_mut();
+        match child_ref
+            .as_ref()
+            .and_then(|c| c.downcast_ref::<ui::SidebarSearchMessageRow>())
+        {
+            Some(message_row) => {
+                message_row.set_message(Some(message));
+            }
+            None => {
+                let message_row = ui::SidebarSearchMessageRow::new(Some(message));
+                message_row.set_parent(self);
+                *child_ref = Some(message_row.upcast());
+            }
+        }
+    }
+
     fn update_or_create_section_row(&self, section_type: ui::SidebarSearchSectionType) {
         let mut child_ref = self.imp().child.borrow_mut();
         match child_ref
@@ -11,6 +11,7 @@ pub(crate) enum SectionType {
     #[default]
     Chats,
     Global,
+    Messages,
     Recent,
 }
 
@@ -126,6 +126,9 @@ impl SectionRow {
             ui::SidebarSearchSectionType::Global => {
                 imp.label.set_text(Some(&gettext("Global Search")));
             }
+            ui::SidebarSearchSectionType::Messages => {
+                imp.label.set_text(Some(&gettext("Messages")));
+            }
             ui::SidebarSearchSectionType::Recent => {
                 imp.label.set_text(Some(&gettext("Recent")));
 
@@ -67,84 +67,397 @@ pub(crate) fn linkify(text: &str) -> String {
     }
 }
 
-pub(crate) fn convert_to_markup(text: String, entity: &tdlib::enums::TextEntityType) -> String {
+/// The prefix of the links to the profile of a user by their username.
+pub(crate) const USERNAME_URI_PREFIX: &str = "paper-plane-username:";
+/// The prefix of the links to the profile of a user by their id.
+pub(crate) const USER_ID_URI_PREFIX: &str = "paper-plane-user:";
+/// The prefix of the links searching a hashtag or a cashtag in the chat.
+pub(crate) const HASHTAG_URI_PREFIX: &str = "paper-plane-hashtag:";
+/// The prefix of the links sending a bot command to the chat.
+pub(crate) const BOT_COMMAND_URI_PREFIX: &str = "paper-plane-bot-command:";
+
+/// A part of a formatted text that is laid out apart from the others.
+#[derive(Debug)]
+pub(crate) enum TextBlock {
+    Text(tdlib::types::FormattedText),
+    Quote(tdlib::types::FormattedText),
+    Code { text: String, language: String },
+}
+
+/// Returns the byte index of the character at the offset in UTF-16 code units, or the one of
+/// the next character if the offset falls inside a character.
+fn utf16_to_byte_index(text: &str, offset: usize) -> usize {
+    let mut code_units = 0;
+
+    for (index, c) in text.char_indices() {
+        if code_units >= offset {
+            return index;
+        }
+        code_units += c.len_utf16();
+    }
+
+    text.len()
+}
+
+/// Returns the part of the text between the offsets in UTF-16 code units.
+fn utf16_substring(text: &str, start: usize, end: usize) -> &str {
+    let start = utf16_to_byte_index(text, start);
+    let end = utf16_to_byte_index(text, end).max(start);
+    &text[start..end]
+}
+
+/// Returns the opening and the closing markup tags of an entity, given its text.
+fn entity_tags(
+    entity_type: &tdlib::enums::TextEntityType,
+    text: &str,
+) -> Option<(String, &'static str)> {
     use tdlib::enums::TextEntityType::*;
 
-    match entity {
-        Url => format!("<a href='{}'>{}</a>", linkify(&text), text),
-        EmailAddress => format!("<a href='mailto:{text}'>{text}</a>"),
-        PhoneNumber => format!("<a href='tel:{text}'>{text}</a>"),
-        Bold => format!("<b>{text}</b>"),
-        Italic => format!("<i>{text}</i>"),
-        Underline => format!("<u>{text}</u>"),
-        Strikethrough => format!("<s>{text}</s>"),
-        Code | Pre | PreCode(_) => format!("<tt>{text}</tt>"),
-        TextUrl(data) => format!("<a href='{}'>{}</a>", escape(&data.url), text),
-        _ => text,
+    let link = |href: String| Some((format!("<a href='{}'>", escape(&href)), "</a>"));
+    let tag = |name: &str, closing_tag| Some((format!("<{name}>"), closing_tag));
+
+    match entity_type {
+        Url => link(linkify(text)),
+        EmailAddress => link(format!("mailto:{text}")),
+        PhoneNumber => link(format!("tel:{text}")),
+        TextUrl(data) => link(data.url.clone()),
+        Mention => link(format!(
+            "{USERNAME_URI_PREFIX}{}",
+            text.trim_start_matches('@')
+        )),
+        MentionName(data) => link(format!("{USER_ID_URI_PREFIX}{}", data.user_id)),
+        Hashtag | Cashtag => link(format!("{HASHTAG_URI_PREFIX}{text}")),
+        BotCommand => link(format!("{BOT_COMMAND_URI_PREFIX}{text}")),
+        Bold => tag("b", "</b>"),
+        Italic | BlockQuote => tag("i", "</i>"),
+        Underline => tag("u", "</u>"),
+        Strikethrough => tag("s", "</s>"),
+        Code | Pre | PreCode(_) => tag("tt", "</tt>"),
+        // Spoilers and custom emoji are drawn by the message label
+        _ => None,
     }
 }
 
+/// Converts a formatted text to markup.
+///
+/// Entities may be nested and may overlap: the text is split at every entity boundary and the
+/// tags of the overlapping entities are closed and reopened around the boundaries, so that the
+/// markup stays well-formed.
 pub(crate) fn parse_formatted_text(formatted_text: tdlib::types::FormattedText) -> String {
-    let mut entities = formatted_text.entities.iter();
-    let mut entity = entities.next();
+    let text = formatted_text.text;
+    let text_length = text.encode_utf16().count();
+
+    let mut ranges = formatted_text
+        .entities
+        .iter()
+        .filter_map(|entity| {
+            let start = entity.offset.max(0) as usize;
+            let end = (start + entity.length.max(0) as usize).min(text_length);
+            (start < end).then_some((start, end, &entity.r#type))
+        })
+        .collect::<Vec<_>>();
+    // Outer entities must be opened before the inner ones
+    ranges.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+
+    // The range and the tags of the entities, skipping the links that intersect other links,
+    // as they can't be nested
+    let mut entities: Vec<(usize, usize, String, &'static str)> = Vec::new();
+    for (start, end, entity_type) in ranges {
+        let Some((opening_tag, closing_tag)) =
+            entity_tags(entity_type, utf16_substring(&text, start, end))
+        else {
+            continue;
+        };
+
+        let is_nested_link = closing_tag == "</a>"
+            && entities
+                .iter()
+                .any(|(s, e, _, c)| *c == "</a>" && *s < end && start < *e);
+        if !is_nested_link {
+            entities.push((start, end, opening_tag, closing_tag));
+        }
+    }
+
+    let mut boundaries = entities
+        .iter()
+        .flat_map(|(start, end, _, _)| [*start, *end])
+        .chain([0, text_length])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
     let mut output = String::new();
-    let mut buffer = String::new();
-    let mut is_inside_entity = false;
-
-    // This is the offset in utf16 code units of the text to parse. We need this variable
-    // because tdlib stores the offset and length parameters as utf16 code units instead
-    // of regular code points.
-    let mut code_units_offset = 0;
-
-    for c in formatted_text.text.chars() {
-        if !is_inside_entity
-            && entity.is_some()
-            && code_units_offset >= entity.unwrap().offset as usize
-        {
-            is_inside_entity = true;
-
-            if !buffer.is_empty() {
-                output.push_str(&escape(&buffer));
-                buffer = String::new();
+    // The indices of the entities whose tags are open, from the outer to the inner one
+    let mut open_entities: Vec<usize> = Vec::new();
+
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+
+        let active_entities = (0..entities.len())
+            .filter(|i| entities[*i].0 <= start && end <= entities[*i].1)
+            .collect::<Vec<_>>();
+        let common = open_entities
+            .iter()
+            .zip(&active_entities)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        for i in open_entities.drain(common..).rev() {
+            output.push_str(entities[i].3);
+        }
+        for i in &active_entities[common..] {
+            output.push_str(&entities[*i].2);
+        }
+        open_entities.extend_from_slice(&active_entities[common..]);
+
+        output.push_str(&escape(utf16_substring(&text, start, end)));
+    }
+
+    for i in open_entities.into_iter().rev() {
+        output.push_str(entities[i].3);
+    }
+
+    output
+}
+
+/// Returns the part of a formatted text between the offsets in UTF-16 code units, with the
+/// entities clipped to it, except the skipped one.
+fn formatted_substring(
+    formatted_text: &tdlib::types::FormattedText,
+    start: usize,
+    end: usize,
+    skipped_entity: Option<usize>,
+) -> tdlib::types::FormattedText {
+    let entities = formatted_text
+        .entities
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != skipped_entity)
+        .filter_map(|(_, entity)| {
+            let entity_start = (entity.offset.max(0) as usize).max(start);
+            let entity_end =
+                (entity.offset.max(0) as usize + entity.length.max(0) as usize).min(end);
+
+            (entity_start < entity_end).then(|| tdlib::types::TextEntity {
+                offset: (entity_start - start) as i32,
+                length: (entity_end - entity_start) as i32,
+                r#type: entity.r#type.clone(),
+            })
+        })
+        .collect();
+
+    tdlib::types::FormattedText {
+        text: utf16_substring(&formatted_text.text, start, end).to_owned(),
+        entities,
+    }
+}
+
+/// Splits a formatted text into blocks of text, quotes and code, dropping the line breaks
+/// that separate them.
+pub(crate) fn text_blocks(formatted_text: tdlib::types::FormattedText) -> Vec<TextBlock> {
+    use tdlib::enums::TextEntityType::*;
+
+    let text_length = formatted_text.text.encode_utf16().count();
+    let entities = &formatted_text.entities;
+
+    let mut block_entities = entities
+        .iter()
+        .enumerate()
+        .filter(|(_, entity)| matches!(entity.r#type, Pre | PreCode(_) | BlockQuote))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    block_entities.sort_by_key(|i| (entities[*i].offset, std::cmp::Reverse(entities[*i].length)));
+
+    let is_line_break = |offset| utf16_substring(&formatted_text.text, offset, offset + 1) == "\n";
+
+    let mut blocks = Vec::new();
+    let mut position = 0;
+
+    for i in block_entities {
+        let entity = &entities[i];
+        let start = entity.offset.max(0) as usize;
+        let end = (start + entity.length.max(0) as usize).min(text_length);
+
+        // Blocks nested in or overlapping the previous one stay inline
+        if start < position || start >= end {
+            continue;
+        }
+
+        let text_start = if !blocks.is_empty() && is_line_break(position) {
+            position + 1
+        } else {
+            position
+        };
+        let text_end = if start > text_start && is_line_break(start - 1) {
+            start - 1
+        } else {
+            start
+        };
+        if text_start < text_end {
+            blocks.push(TextBlock::Text(formatted_substring(
+                &formatted_text,
+                text_start,
+                text_end,
+                None,
+            )));
+        }
+
+        blocks.push(match &entity.r#type {
+            BlockQuote => {
+                TextBlock::Quote(formatted_substring(&formatted_text, start, end, Some(i)))
             }
+            PreCode(data) => TextBlock::Code {
+                text: utf16_substring(&formatted_text.text, start, end).to_owned(),
+                language: data.language.clone(),
+            },
+            _ => TextBlock::Code {
+                text: utf16_substring(&formatted_text.text, start, end).to_owned(),
+                language: String::new(),
+            },
+        });
+
+        position = end;
+    }
+
+    if blocks.is_empty() {
+        return vec![TextBlock::Text(formatted_text)];
+    }
+
+    let text_start = if is_line_break(position) {
+        position + 1
+    } else {
+        position
+    };
+    if text_start < text_length {
+        blocks.push(TextBlock::Text(formatted_substring(
+            &formatted_text,
+            text_start,
+            text_length,
+            None,
+        )));
+    }
+
+    blocks
+}
+
+/// Returns the formatted text of the message content, which is either its text or its caption.
+pub(crate) fn message_formatted_text(
+    content: tdlib::enums::MessageContent,
+) -> Option<tdlib::types::FormattedText> {
+    use tdlib::enums::MessageContent::*;
+
+    match content {
+        MessageText(data) => Some(data.text),
+        MessageAnimation(data) => Some(data.caption),
+        MessageAudio(data) => Some(data.caption),
+        MessageDocument(data) => Some(data.caption),
+        MessagePhoto(data) => Some(data.caption),
+        MessageVideo(data) => Some(data.caption),
+        MessageVoiceNote(data) => Some(data.caption),
+        _ => None,
+    }
+}
+
+/// Returns the text entities of the message content with their offsets moved to be relative to
+/// `preview`, which is expected to end with the text or the caption of the content.
+pub(crate) fn preview_text_entities(
+    preview: &str,
+    content: tdlib::enums::MessageContent,
+) -> Vec<tdlib::types::TextEntity> {
+    let Some(formatted_text) = message_formatted_text(content) else {
+        return Vec::new();
+    };
+    let Some(prefix) = preview.strip_suffix(&formatted_text.text) else {
+        return Vec::new();
+    };
+
+    let shift = prefix.encode_utf16().count() as i32;
+    formatted_text
+        .entities
+        .into_iter()
+        .map(|mut entity| {
+            entity.offset += shift;
+            entity
+        })
+        .collect()
+}
+
+/// Returns the number of emoji of the text, if it consists only of emoji and whitespace.
+pub(crate) fn emoji_count(text: &str) -> Option<usize> {
+    let mut count = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if is_regional_indicator(c) {
+            // Flags are made of a pair of regional indicators
+            chars.next_if(|c| is_regional_indicator(*c))?;
+        } else if matches!(c, '0'..='9' | '#' | '*') {
+            // Keycaps
+            chars.next_if_eq(&'\u{FE0F}');
+            chars.next_if_eq(&'\u{20E3}')?;
+        } else if !is_emoji(c) {
+            return None;
         }
 
-        buffer.push(c);
-        code_units_offset += c.len_utf16();
-
-        if let Some(entity_) = entity {
-            if code_units_offset >= (entity_.offset + entity_.length) as usize {
-                buffer = escape(&buffer);
-
-                entity = loop {
-                    let entity = entities.next();
-
-                    // Handle eventual nested entities
-                    match entity {
-                        Some(entity) => {
-                            if entity.offset == entity_.offset {
-                                buffer = convert_to_markup(buffer, &entity.r#type);
-                            } else {
-                                break Some(entity);
-                            }
-                        }
-                        None => break None,
-                    }
-                };
-
-                output.push_str(&convert_to_markup(buffer, &entity_.r#type));
-                buffer = String::new();
-                is_inside_entity = false;
+        // Modifiers and sequences of emoji joined by zero width joiners
+        loop {
+            if chars.next_if(|c| is_emoji_modifier(*c)).is_some() {
+                continue;
+            }
+            if chars.next_if_eq(&'\u{200D}').is_some() {
+                chars.next_if(|c| is_emoji(*c))?;
+                continue;
             }
+            break;
         }
-    }
 
-    // Add the eventual leftovers from the buffer to the output
-    if !buffer.is_empty() {
-        output.push_str(&escape(&buffer));
+        count += 1;
     }
 
-    output
+    (count > 0).then_some(count)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9
+            | 0x00AE
+            | 0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x2194..=0x21AA
+            | 0x231A..=0x23FF
+            | 0x24C2
+            | 0x25AA..=0x25FE
+            | 0x2600..=0x27BF
+            | 0x2934
+            | 0x2935
+            | 0x2B05..=0x2B55
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x1F000..=0x1FAFF
+    ) && !is_regional_indicator(c)
+        && !is_emoji_modifier(c)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Whether the character modifies the preceding emoji, like variation selectors, skin tones
+/// and tags.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E | 0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F
+    )
 }
 
 pub(crate) fn human_friendly_duration(mut seconds: i32) -> String {
@@ -173,6 +486,55 @@ pub(crate) fn human_friendly_duration(mut seconds: i32) -> String {
     }
 }
 
+/// Formats a duration in seconds like a media player does, e.g. "1:05" or "1:02:05".
+pub(crate) fn format_duration(seconds: u64) -> String {
+    let hours = seconds / (60 * 60);
+    let minutes = (seconds % (60 * 60)) / 60;
+    let seconds = seconds % 60;
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Decodes a TDLib voice note waveform, which is a base64 encoded sequence of 5-bit values.
+pub(crate) fn decode_waveform(waveform: &str) -> Vec<u8> {
+    let bytes = glib::base64_decode(waveform);
+    let n_samples = bytes.len() * 8 / 5;
+
+    (0..n_samples)
+        .map(|index| {
+            let bit_offset = index * 5;
+            let byte_index = bit_offset / 8;
+
+            let lower = bytes[byte_index] as u16;
+            let upper = bytes.get(byte_index + 1).copied().unwrap_or_default() as u16;
+
+            (((upper << 8 | lower) >> (bit_offset % 8)) & 0x1F) as u8
+        })
+        .collect()
+}
+
+/// Encodes the given samples, each in the `0..32` range, to a TDLib voice note waveform.
+pub(crate) fn encode_waveform(samples: &[u8]) -> String {
+    let mut bytes = vec![0u8; (samples.len() * 5 + 7) / 8];
+
+    for (index, sample) in samples.iter().enumerate() {
+        let bit_offset = index * 5;
+        let byte_index = bit_offset / 8;
+        let value = ((*sample).min(0x1F) as u16) << (bit_offset % 8);
+
+        bytes[byte_index] |= value as u8;
+        if let Some(byte) = bytes.get_mut(byte_index + 1) {
+            *byte |= (value >> 8) as u8;
+        }
+    }
+
+    glib::base64_encode(&bytes).into()
+}
+
 /// Returns the Paper Plane data directory (e.g. /home/bob/.local/share/paper-plane).
 pub(crate) fn data_dir() -> &'static PathBuf {
     &APPLICATION_OPTS.get().unwrap().data_dir
@@ -299,3 +661,198 @@ impl Iterator for ChildIter {
         r
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tdlib::enums::TextEntityType;
+    use tdlib::types::FormattedText;
+    use tdlib::types::TextEntity;
+
+    use super::*;
+
+    fn formatted_text(text: &str, entities: &[(i32, i32, TextEntityType)]) -> FormattedText {
+        FormattedText {
+            text: text.to_owned(),
+            entities: entities
+                .iter()
+                .map(|(offset, length, r#type)| TextEntity {
+                    offset: *offset,
+                    length: *length,
+                    r#type: r#type.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn entity_ranges(formatted_text: &FormattedText) -> Vec<(i32, i32)> {
+        formatted_text
+            .entities
+            .iter()
+            .map(|entity| (entity.offset, entity.length))
+            .collect()
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        assert_eq!(utf16_to_byte_index("a😀b", 1), 1);
+        assert_eq!(utf16_to_byte_index("a😀b", 3), 5);
+        // Inside the surrogate pair of the emoji
+        assert_eq!(utf16_to_byte_index("a😀b", 2), 5);
+        assert_eq!(utf16_to_byte_index("a😀b", 10), 6);
+        assert_eq!(utf16_substring("😀 bold", 3, 7), "bold");
+    }
+
+    #[test]
+    fn escape_text() {
+        let text = formatted_text("<b> & 'x'", &[]);
+        assert_eq!(parse_formatted_text(text), "&lt;b&gt; &amp; &apos;x&apos;");
+    }
+
+    #[test]
+    fn entity_after_emoji() {
+        let text = formatted_text("😀 bold 👍", &[(3, 4, TextEntityType::Bold)]);
+        assert_eq!(parse_formatted_text(text), "😀 <b>bold</b> 👍");
+    }
+
+    #[test]
+    fn nested_entities() {
+        let text = formatted_text(
+            "hello world",
+            &[
+                (0, 11, TextEntityType::Bold),
+                (6, 5, TextEntityType::Italic),
+            ],
+        );
+        assert_eq!(parse_formatted_text(text), "<b>hello <i>world</i></b>");
+
+        // The inner entity may come first
+        let text = formatted_text(
+            "hello world",
+            &[
+                (0, 5, TextEntityType::Italic),
+                (0, 11, TextEntityType::Bold),
+            ],
+        );
+        assert_eq!(parse_formatted_text(text), "<b><i>hello</i> world</b>");
+    }
+
+    #[test]
+    fn overlapping_entities() {
+        let text = formatted_text(
+            "abcdefghij",
+            &[(0, 7, TextEntityType::Bold), (4, 6, TextEntityType::Italic)],
+        );
+        assert_eq!(
+            parse_formatted_text(text),
+            "<b>abcd<i>efg</i></b><i>hij</i>"
+        );
+    }
+
+    #[test]
+    fn links() {
+        let text = formatted_text(
+            "hi @user, try /start #tag",
+            &[
+                (3, 5, TextEntityType::Mention),
+                (14, 6, TextEntityType::BotCommand),
+                (21, 4, TextEntityType::Hashtag),
+            ],
+        );
+        assert_eq!(
+            parse_formatted_text(text),
+            format!(
+                "hi <a href='{USERNAME_URI_PREFIX}user'>@user</a>, \
+                try <a href='{BOT_COMMAND_URI_PREFIX}/start'>/start</a> \
+                <a href='{HASHTAG_URI_PREFIX}#tag'>#tag</a>"
+            )
+        );
+
+        // Links can't be nested
+        let text = formatted_text(
+            "#tag",
+            &[
+                (
+                    0,
+                    4,
+                    TextEntityType::TextUrl(tdlib::types::TextEntityTypeTextUrl {
+                        url: "https://example.com".to_owned(),
+                    }),
+                ),
+                (0, 4, TextEntityType::Hashtag),
+            ],
+        );
+        assert_eq!(
+            parse_formatted_text(text),
+            "<a href='https://example.com'>#tag</a>"
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        let text = formatted_text(
+            "😀 intro\nlet x;\nend",
+            &[
+                (3, 5, TextEntityType::Bold),
+                (9, 6, TextEntityType::Pre),
+                (16, 3, TextEntityType::Italic),
+            ],
+        );
+        let blocks = text_blocks(text);
+        assert_eq!(blocks.len(), 3);
+
+        let TextBlock::Text(ref intro) = blocks[0] else {
+            panic!("expected a text block");
+        };
+        assert_eq!(intro.text, "😀 intro");
+        assert_eq!(entity_ranges(intro), [(3, 5)]);
+
+        let TextBlock::Code { ref text, .. } = blocks[1] else {
+            panic!("expected a code block");
+        };
+        assert_eq!(text, "let x;");
+
+        let TextBlock::Text(ref end) = blocks[2] else {
+            panic!("expected a text block");
+        };
+        assert_eq!(end.text, "end");
+        assert_eq!(entity_ranges(end), [(0, 3)]);
+    }
+
+    #[test]
+    fn quote_blocks() {
+        let text = formatted_text(
+            "quote 👍 bold",
+            &[
+                (0, 13, TextEntityType::BlockQuote),
+                (9, 4, TextEntityType::Bold),
+            ],
+        );
+        let blocks = text_blocks(text);
+        assert_eq!(blocks.len(), 1);
+
+        let TextBlock::Quote(ref quote) = blocks[0] else {
+            panic!("expected a quote block");
+        };
+        assert_eq!(quote.text, "quote 👍 bold");
+        assert_eq!(entity_ranges(quote), [(9, 4)]);
+    }
+
+    #[test]
+    fn waveform_round_trip() {
+        let samples: Vec<u8> = (0..100).map(|index| (index * 7 % 32) as u8).collect();
+        assert_eq!(decode_waveform(&encode_waveform(&samples)), samples);
+
+        // 13 samples don't fill the last byte, so its padding bits are decoded as an extra
+        // silent sample
+        let samples = &samples[..13];
+        let decoded = decode_waveform(&encode_waveform(samples));
+        assert_eq!(&decoded[..13], samples);
+        assert_eq!(&decoded[13..], [0]);
+
+        // Samples out of the 5-bit range are clamped
+        assert_eq!(
+            decode_waveform(&encode_waveform(&[40, 0, 31, 255, 1, 2, 3, 4])),
+            [31, 0, 31, 31, 1, 2, 3, 4]
+        );
+    }
+}